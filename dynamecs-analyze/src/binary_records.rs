@@ -0,0 +1,113 @@
+//! A compact binary record format, stored in `.reclog` files.
+//!
+//! Each record is stored as a little-endian `u32` length followed by the record encoded as
+//! MessagePack. MessagePack is self-describing, so span fields and record fields are stored as
+//! native values rather than as JSON strings. The format is smaller than the JSON log, at the
+//! cost of not being human-readable.
+use crate::{AnalyzeError, RawRecord, Record};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::io;
+use std::io::{ErrorKind, Read, Write};
+use time::OffsetDateTime;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct BinaryRecord {
+    timestamp: OffsetDateTime,
+    level: String,
+    fields: Value,
+    target: String,
+    span: Option<Value>,
+    spans: Option<Vec<Value>>,
+    thread_id: String,
+}
+
+impl BinaryRecord {
+    fn from_record(record: Record) -> Self {
+        let raw_record = RawRecord::from_record(record);
+        Self {
+            timestamp: raw_record.timestamp,
+            level: raw_record.level,
+            fields: raw_record.fields,
+            target: raw_record.target,
+            span: raw_record.span,
+            spans: raw_record.spans,
+            thread_id: raw_record.thread_id,
+        }
+    }
+
+    fn into_raw_record(self) -> RawRecord {
+        RawRecord {
+            timestamp: self.timestamp,
+            level: self.level,
+            fields: self.fields,
+            target: self.target,
+            span: self.span,
+            spans: self.spans,
+            thread_id: self.thread_id,
+        }
+    }
+}
+
+/// Writes the records in the binary `.reclog` format.
+///
+/// Each record is written with a single call to `write_all`, so that records written concurrently
+/// to a shared writer are not interleaved.
+///
+/// The records can be read back with [`iterate_binary_records_from_reader`](crate::iterate_binary_records_from_reader),
+/// or with [`iterate_records`](crate::iterate_records) for files with the `.reclog` extension.
+pub fn write_binary_records(mut writer: impl Write, records: impl Iterator<Item = Record>) -> io::Result<()> {
+    let mut bytes = Vec::new();
+    for record in records {
+        // Reserve space for the length, which is only known after encoding the record
+        bytes.clear();
+        bytes.extend_from_slice(&[0u8; 4]);
+        rmp_serde::encode::write_named(&mut bytes, &BinaryRecord::from_record(record))
+            .map_err(|err| io::Error::new(ErrorKind::InvalidData, err))?;
+        let length = u32::try_from(bytes.len() - 4).map_err(|err| io::Error::new(ErrorKind::InvalidData, err))?;
+        bytes[..4].copy_from_slice(&length.to_le_bytes());
+        writer.write_all(&bytes)?;
+    }
+    Ok(())
+}
+
+/// Reads the next record from the reader, or returns `None` if the reader is exhausted.
+///
+/// The record number is 1-based, and takes the place of the line number in parse errors.
+pub(crate) fn read_binary_record(mut reader: impl Read, record_number: usize) -> Option<Result<Record, AnalyzeError>> {
+    let mut length_bytes = [0u8; 4];
+    match read_exact_or_eof(&mut reader, &mut length_bytes) {
+        Ok(false) => return None,
+        Ok(true) => {}
+        Err(err) => return Some(Err(err.into())),
+    }
+
+    let mut bytes = vec![0u8; u32::from_le_bytes(length_bytes) as usize];
+    if let Err(err) = reader.read_exact(&mut bytes) {
+        return Some(Err(err.into()));
+    }
+
+    Some(
+        rmp_serde::from_slice::<BinaryRecord>(&bytes)
+            .map_err(|err| AnalyzeError::Parse {
+                line: record_number,
+                source: Box::new(err),
+            })
+            .and_then(|binary_record| binary_record.into_raw_record().try_to_record(record_number)),
+    )
+}
+
+/// Fills the buffer, returning `false` if the reader is exhausted before any bytes are read.
+fn read_exact_or_eof(reader: &mut impl Read, buf: &mut [u8]) -> io::Result<bool> {
+    let mut num_read = 0;
+    while num_read < buf.len() {
+        match reader.read(&mut buf[num_read..]) {
+            Ok(0) if num_read == 0 => return Ok(false),
+            Ok(0) => return Err(io::Error::new(ErrorKind::UnexpectedEof, "truncated binary record")),
+            Ok(n) => num_read += n,
+            Err(err) if err.kind() == ErrorKind::Interrupted => {}
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(true)
+}
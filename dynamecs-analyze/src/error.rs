@@ -0,0 +1,60 @@
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::io;
+
+/// The error type used throughout `dynamecs-analyze`.
+#[derive(Debug)]
+pub enum AnalyzeError {
+    /// A line in the log could not be parsed as a record.
+    ///
+    /// Line numbers are 1-based and count every line in the log, including empty lines.
+    Parse {
+        line: usize,
+        source: Box<dyn Error + Send + Sync>,
+    },
+    /// An IO error occurred while reading the log.
+    Io(io::Error),
+    /// A field required for the analysis is missing.
+    MissingField { field: String },
+    /// The log is well-formed but its contents are inconsistent or incomplete for the requested analysis.
+    InvalidLog { message: String },
+}
+
+impl AnalyzeError {
+    pub(crate) fn missing_field(field: impl Into<String>) -> Self {
+        Self::MissingField { field: field.into() }
+    }
+
+    pub(crate) fn invalid_log(message: impl Into<String>) -> Self {
+        Self::InvalidLog {
+            message: message.into(),
+        }
+    }
+}
+
+impl Display for AnalyzeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Parse { line, source } => write!(f, "failed to parse record on line {line}: {source}"),
+            Self::Io(err) => write!(f, "IO error: {err}"),
+            Self::MissingField { field } => write!(f, "missing field `{field}`"),
+            Self::InvalidLog { message } => write!(f, "{message}"),
+        }
+    }
+}
+
+impl Error for AnalyzeError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Parse { source, .. } => Some(source.as_ref()),
+            Self::Io(err) => Some(err),
+            Self::MissingField { .. } | Self::InvalidLog { .. } => None,
+        }
+    }
+}
+
+impl From<io::Error> for AnalyzeError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
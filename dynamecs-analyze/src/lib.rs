@@ -1,23 +1,33 @@
-use eyre::{eyre, ErrReport};
 use flate2::read::GzDecoder;
 use serde::{Deserialize, Serialize};
 use serde_json::{Map, Value};
+use std::borrow::Cow;
+use std::collections::BTreeSet;
 use std::ffi::OsStr;
 use std::fmt::{Display, Formatter};
 use std::fs::File;
 use std::io;
 use std::io::{BufRead, BufReader, Lines, Read, Write};
+use std::net::{TcpListener, ToSocketAddrs};
 use std::path::Path;
 use std::str::FromStr;
+use time::format_description::well_known::Rfc3339;
 use time::OffsetDateTime;
 
 pub mod timing;
+pub mod timing_history;
+
+mod error;
+pub use error::AnalyzeError;
 
 mod span_path;
 pub use span_path::SpanPath;
 
+mod binary_records;
+pub use binary_records::write_binary_records;
+
 mod span_tree;
-pub use span_tree::{SpanTree, SpanTreeNode};
+pub use span_tree::{SpanTree, SpanTreeNode, StructuralChange};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Span {
@@ -41,11 +51,11 @@ impl Span {
         Self { name, fields }
     }
 
-    fn try_from_json_value(value: serde_json::Value) -> eyre::Result<Self> {
+    fn try_from_json_value(value: serde_json::Value) -> Result<Self, AnalyzeError> {
         let name = value
             .as_object()
             .and_then(|obj| obj.get("name").and_then(|val| val.as_str()))
-            .ok_or_else(|| eyre!("missing name in span"))?
+            .ok_or_else(|| AnalyzeError::missing_field("name"))?
             .to_string();
         Ok(Self { name, fields: value })
     }
@@ -63,6 +73,18 @@ impl Span {
     pub fn fields(&self) -> &serde_json::Value {
         &self.fields
     }
+
+    /// Returns the id of the span, if the span has an unsigned integer `span_id` field.
+    ///
+    /// Span ids distinguish multiple simultaneously active spans with the same span path, such as
+    /// spans instrumenting concurrently running `async` tasks.
+    ///
+    /// The JSON log format does not record the internal ids that `tracing` assigns to spans,
+    /// so the id must be provided explicitly as a field of the span, for example
+    /// `info_span!("task", span_id = task_index)`. Spans without a `span_id` field return `None`.
+    pub fn id(&self) -> Option<u64> {
+        self.fields.get("span_id").and_then(|value| value.as_u64())
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -118,7 +140,7 @@ impl Record {
     ///
     /// For span enter/exit records, this is the span that is currently being entered/exited,
     /// and for events it is the path to the span in which the event takes place.
-    pub fn create_span_path(&self) -> eyre::Result<SpanPath> {
+    pub fn create_span_path(&self) -> Result<SpanPath, AnalyzeError> {
         let mut span_names: Vec<_> = self
             .spans
             .iter()
@@ -133,7 +155,7 @@ impl Record {
                 let span_name = self
                     .span()
                     .map(|span| span.name())
-                    .ok_or_else(|| eyre!("No span in exit record"))?;
+                    .ok_or_else(|| AnalyzeError::missing_field("span"))?;
                 span_names.push(span_name.to_string());
             }
         }
@@ -147,6 +169,38 @@ impl Record {
     pub fn fields(&self) -> &serde_json::Value {
         &self.fields
     }
+
+    /// Returns the index of the `step` span that this record belongs to, if any.
+    ///
+    /// The index is taken from the `step_index` field of the innermost `step` span, including
+    /// the span being entered or exited by this record.
+    pub fn step_index(&self) -> Option<u64> {
+        self.span()
+            .into_iter()
+            .chain(self.spans().into_iter().flatten().rev())
+            .find(|span| span.name() == "step")
+            .and_then(|span| span.fields().get("step_index"))
+            .and_then(|value| value.as_u64())
+    }
+
+    /// Returns the value of the given field of the record, or otherwise of the innermost enclosing span.
+    ///
+    /// The field is either a name, such as `residual`, or a JSON pointer, such as `/stats/residual`.
+    /// Fields of enclosing spans make it possible to associate events with span fields, such as the
+    /// iteration number of a `Newton iteration` span.
+    pub fn field_or_span_field(&self, field: &str) -> Option<&Value> {
+        let pointer = match field.starts_with('/') {
+            true => Cow::Borrowed(field),
+            false => Cow::Owned(format!("/{field}")),
+        };
+        self.fields.pointer(&pointer).or_else(|| {
+            self.spans()
+                .into_iter()
+                .flatten()
+                .rev()
+                .find_map(|span| span.fields().pointer(&pointer))
+        })
+    }
 }
 
 #[derive(Default, Debug, Clone)]
@@ -374,35 +428,74 @@ impl RecordBuilder {
 }
 
 pub struct RecordIter<'a> {
-    lines_iter: Lines<BufReader<Box<dyn Read + 'a>>>,
+    source: RecordSource<'a>,
+    /// The line number for JSON logs, or the record number for binary logs.
+    line_number: usize,
+    min_level: Option<Level>,
+    target_prefix: Option<String>,
 }
 
-pub fn iterate_records(json_log_file_path: impl AsRef<Path>) -> eyre::Result<RecordIter<'static>> {
+enum RecordSource<'a> {
+    JsonLines(Lines<BufReader<Box<dyn Read + 'a>>>),
+    Binary(BufReader<Box<dyn Read + 'a>>),
+}
+
+/// Iterates over the records in a log file.
+///
+/// JSON logs (`.jsonlog` or `.jsonlog.gz`) and binary logs (`.reclog`) are supported.
+pub fn iterate_records(json_log_file_path: impl AsRef<Path>) -> Result<RecordIter<'static>, AnalyzeError> {
     iterate_records_(json_log_file_path.as_ref())
 }
 
-fn iterate_records_(json_log_file_path: &Path) -> eyre::Result<RecordIter<'static>> {
+fn iterate_records_(json_log_file_path: &Path) -> Result<RecordIter<'static>, AnalyzeError> {
     let file = File::open(json_log_file_path)?;
     let file_name = json_log_file_path
         .file_name()
         .and_then(OsStr::to_str)
-        .ok_or_else(|| eyre!("non-utf filename, cannot proceed"))?;
+        .ok_or_else(|| AnalyzeError::invalid_log("non-utf filename, cannot proceed"))?;
     if file_name.ends_with(".jsonlog") {
         Ok(iterate_records_from_reader(file))
     } else if file_name.ends_with(".jsonlog.gz") {
         Ok(iterate_records_from_reader(GzDecoder::new(file)))
+    } else if file_name.ends_with(".reclog") {
+        Ok(iterate_binary_records_from_reader(file))
     } else {
-        Err(eyre!("unexpected extension. Expected .jsonlog or .jsonlog.gz"))
+        Err(AnalyzeError::invalid_log(
+            "unexpected extension. Expected .jsonlog, .jsonlog.gz or .reclog",
+        ))
     }
 }
 
+/// Listens for a single incoming connection on the given address and iterates over the records streamed to it.
+///
+/// This is the receiving end of the `--stream-logs <addr>` option of `dynamecs-app`, and can be used
+/// for monitoring a running application. The function blocks until a connection is established.
+pub fn listen_for_records(address: impl ToSocketAddrs) -> Result<RecordIter<'static>, AnalyzeError> {
+    let listener = TcpListener::bind(address)?;
+    let (stream, _) = listener.accept()?;
+    Ok(iterate_records_from_reader(stream))
+}
+
 pub fn iterate_records_from_reader<'a, R: Read + 'a>(reader: R) -> RecordIter<'a> {
     iterate_records_from_reader_(BufReader::new(Box::new(reader)))
 }
 
 fn iterate_records_from_reader_<'a>(reader: BufReader<Box<dyn Read + 'a>>) -> RecordIter<'a> {
     RecordIter {
-        lines_iter: reader.lines(),
+        source: RecordSource::JsonLines(reader.lines()),
+        line_number: 0,
+        min_level: None,
+        target_prefix: None,
+    }
+}
+
+/// Iterates over records in the binary format written by [`write_binary_records`].
+pub fn iterate_binary_records_from_reader<'a, R: Read + 'a>(reader: R) -> RecordIter<'a> {
+    RecordIter {
+        source: RecordSource::Binary(BufReader::new(Box::new(reader))),
+        line_number: 0,
+        min_level: None,
+        target_prefix: None,
     }
 }
 
@@ -415,20 +508,182 @@ pub fn write_records(mut writer: impl Write, records: impl Iterator<Item = Recor
     Ok(())
 }
 
+/// Writes a tab-separated table with the timestamp, span path and the given fields of every event record.
+///
+/// Field values are looked up with [`Record::field_or_span_field`]. String values are written verbatim,
+/// other values as JSON, and cells for missing fields are left empty.
+pub fn write_event_fields(
+    mut writer: impl Write,
+    records: impl IntoIterator<Item = Record>,
+    fields: &[impl AsRef<str>],
+) -> Result<(), AnalyzeError> {
+    write!(writer, "timestamp\tspan_path")?;
+    for field in fields {
+        write!(writer, "\t{}", field.as_ref())?;
+    }
+    writeln!(writer)?;
+
+    for record in records {
+        if record.kind() != RecordKind::Event {
+            continue;
+        }
+        let timestamp = record
+            .timestamp()
+            .format(&Rfc3339)
+            .map_err(|err| AnalyzeError::invalid_log(format!("failed to format timestamp: {err}")))?;
+        write!(writer, "{timestamp}\t{}", record.create_span_path()?)?;
+        for field in fields {
+            match record.field_or_span_field(field.as_ref()) {
+                Some(Value::String(string)) => write!(writer, "\t{string}")?,
+                Some(value) => write!(writer, "\t{value}")?,
+                None => write!(writer, "\t")?,
+            }
+        }
+        writeln!(writer)?;
+    }
+    Ok(())
+}
+
+/// Collects the distinct span paths of all span enter records.
+pub fn collect_span_paths(records: impl IntoIterator<Item = Record>) -> BTreeSet<SpanPath> {
+    records
+        .into_iter()
+        .filter(|record| record.kind() == RecordKind::SpanEnter)
+        .filter_map(|record| record.create_span_path().ok())
+        .collect()
+}
+
+/// Yields only the records whose timestamp lies within the inclusive range `[start, end]`.
+///
+/// Filtering by time may separate the enter and exit records of spans, which breaks the pairing of
+/// enter and exit records that downstream analysis relies on. Callers should therefore prefer windows
+/// that cover whole steps. Since the window will usually not contain the `run` span of `dynamecs_app`,
+/// the filtered records are best analyzed per step with
+/// [`group_records_by_step`](timing::group_records_by_step) and
+/// [`extract_single_step_timings`](timing::extract_single_step_timings), which skip incomplete steps.
+pub fn filter_records_in_range(
+    records: impl IntoIterator<Item = Record>,
+    start: OffsetDateTime,
+    end: OffsetDateTime,
+) -> impl Iterator<Item = Record> {
+    records
+        .into_iter()
+        .filter(move |record| (start..=end).contains(record.timestamp()))
+}
+
+impl<'a> RecordIter<'a> {
+    /// Only yields records with the given level or a more severe level.
+    ///
+    /// For example, [`Level::Warn`] yields warnings and errors. Records are skipped before they are fully parsed.
+    pub fn with_min_level(self, min_level: Level) -> Self {
+        Self {
+            min_level: Some(min_level),
+            ..self
+        }
+    }
+
+    /// Only yields records whose target starts with the given prefix.
+    ///
+    /// Records are skipped before they are fully parsed.
+    pub fn with_target_prefix(self, target_prefix: impl Into<String>) -> Self {
+        Self {
+            target_prefix: Some(target_prefix.into()),
+            ..self
+        }
+    }
+
+    /// Determines whether a fully parsed record should be skipped.
+    fn should_skip_record(&self, record: &Record) -> bool {
+        let below_min_level = self
+            .min_level
+            .is_some_and(|min_level| record.level() > min_level);
+        let mismatched_target = self
+            .target_prefix
+            .as_ref()
+            .is_some_and(|prefix| !record.target().starts_with(prefix.as_str()));
+        below_min_level || mismatched_target
+    }
+
+    /// Determines from only the level and target whether the record on the given line should be skipped.
+    ///
+    /// Lines that cannot be parsed are not skipped, so that the error is reported when the full record is parsed.
+    fn should_skip(&self, line: &str) -> bool {
+        if self.min_level.is_none() && self.target_prefix.is_none() {
+            return false;
+        }
+        let Ok(header) = serde_json::from_str::<RawRecordHeader>(line) else {
+            return false;
+        };
+        let below_min_level = self
+            .min_level
+            .is_some_and(|min_level| Level::from_str(&header.level).is_ok_and(|level| level > min_level));
+        let mismatched_target = self
+            .target_prefix
+            .as_ref()
+            .is_some_and(|prefix| !header.target.starts_with(prefix.as_str()));
+        below_min_level || mismatched_target
+    }
+
+    /// Transforms every successfully parsed record with the given function, passing errors through unchanged.
+    ///
+    /// This is useful for normalizing records, for example with [`redact`].
+    pub fn map_records<F>(self, mut transform: F) -> impl Iterator<Item = Result<Record, AnalyzeError>> + 'a
+    where
+        F: FnMut(Record) -> Record + 'a,
+    {
+        self.map(move |record_result| record_result.map(&mut transform))
+    }
+}
+
+/// Redacts the variable part of the message of the record.
+///
+/// For each `(prefix, suffix)` pair, a message of the form `<prefix><middle><suffix>` has its middle part
+/// replaced by `replacement`. Only the first matching pair is applied. Records without a matching message
+/// are returned unchanged.
+pub fn redact(record: Record, patterns: &[(&str, &str)], replacement: &str) -> Record {
+    let redacted_message = record.message().and_then(|message| {
+        patterns.iter().find_map(|(prefix, suffix)| {
+            message
+                .strip_prefix(prefix)
+                .and_then(|remainder| remainder.strip_suffix(suffix))
+                .map(|_| format!("{prefix}{replacement}{suffix}"))
+        })
+    });
+
+    match redacted_message {
+        Some(message) => RecordBuilder::from_record(record).message(message).build(),
+        None => record,
+    }
+}
+
 impl<'a> Iterator for RecordIter<'a> {
-    // TODO: Use a proper error type here
-    type Item = eyre::Result<Record>;
+    type Item = Result<Record, AnalyzeError>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        while let Some(line_result) = self.lines_iter.next() {
+        if matches!(self.source, RecordSource::Binary(_)) {
+            loop {
+                match self.next_binary_record()? {
+                    Ok(record) if self.should_skip_record(&record) => {}
+                    result => return Some(result),
+                }
+            }
+        }
+
+        while let Some(line_result) = self.next_line() {
+            self.line_number += 1;
             match line_result {
                 Ok(line) if line.trim().is_empty() => {}
+                Ok(line) if self.should_skip(&line) => {}
                 Ok(line) => {
+                    let line_number = self.line_number;
                     return Some(
                         serde_json::from_str(&line)
-                            .map_err(|err| ErrReport::from(err))
-                            .and_then(|raw_record: RawRecord| raw_record.try_to_record()),
-                    )
+                            .map_err(|err| AnalyzeError::Parse {
+                                line: line_number,
+                                source: Box::new(err),
+                            })
+                            .and_then(|raw_record: RawRecord| raw_record.try_to_record(line_number)),
+                    );
                 }
                 Err(err) => {
                     return Some(Err(err.into()));
@@ -440,6 +695,25 @@ impl<'a> Iterator for RecordIter<'a> {
     }
 }
 
+impl<'a> RecordIter<'a> {
+    fn next_line(&mut self) -> Option<io::Result<String>> {
+        match &mut self.source {
+            RecordSource::JsonLines(lines_iter) => lines_iter.next(),
+            RecordSource::Binary(_) => None,
+        }
+    }
+
+    fn next_binary_record(&mut self) -> Option<Result<Record, AnalyzeError>> {
+        match &mut self.source {
+            RecordSource::JsonLines(_) => None,
+            RecordSource::Binary(reader) => {
+                self.line_number += 1;
+                binary_records::read_binary_record(reader, self.line_number)
+            }
+        }
+    }
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 struct RawRecord {
     // TODO: Consider replacing time with Chrono. From my understanding, only Chrono
@@ -457,8 +731,17 @@ struct RawRecord {
     thread_id: String,
 }
 
+/// The fields of a [`RawRecord`] that are needed for filtering, which are much cheaper to parse.
+#[derive(Debug, Deserialize)]
+struct RawRecordHeader<'a> {
+    #[serde(borrow)]
+    level: Cow<'a, str>,
+    #[serde(borrow)]
+    target: Cow<'a, str>,
+}
+
 impl RawRecord {
-    fn try_to_record(self) -> eyre::Result<Record> {
+    fn try_to_record(self, line_number: usize) -> Result<Record, AnalyzeError> {
         let message = self.fields.pointer("/message").and_then(|val| val.as_str());
 
         Ok(Record {
@@ -467,14 +750,17 @@ impl RawRecord {
                 .span
                 .map(|json_val| Span::try_from_json_value(json_val))
                 .transpose()?,
-            level: Level::from_str(&self.level)?,
+            level: Level::from_str(&self.level).map_err(|err| AnalyzeError::Parse {
+                line: line_number,
+                source: Box::new(err),
+            })?,
             spans: self
                 .spans
                 .map(|json_vals| {
                     json_vals
                         .into_iter()
                         .map(Span::try_from_json_value)
-                        .collect::<eyre::Result<_>>()
+                        .collect::<Result<_, _>>()
                 })
                 .transpose()?,
             kind: match message {
@@ -526,7 +812,10 @@ impl RawRecord {
 
 // We reproduce a Level enum here so that we don't have to depend on tracing only for that one
 // type
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The level of a record.
+///
+/// As for levels in `tracing`, more verbose levels compare greater, so that [`Level::Error`] is the smallest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub enum Level {
     Error,
     Warn,
@@ -1,5 +1,6 @@
 use crate::SpanPath;
 use itertools::izip;
+use std::collections::HashSet;
 use std::fmt::{Debug, Display, Formatter};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -103,6 +104,51 @@ impl<Payload> SpanTree<Payload> {
             payloads: new_payloads,
         }
     }
+
+    /// Iterate over all nodes in the tree in depth-first order.
+    ///
+    /// Each item consists of the depth of the node relative to the root (so that the root has depth 0),
+    /// the path of the node and its payload.
+    pub fn iter_depth_first(&self) -> impl Iterator<Item = (usize, &SpanPath, &Payload)> {
+        let root_depth = self
+            .tree_depth_first
+            .first()
+            .map(SpanPath::depth)
+            .unwrap_or(0);
+        izip!(&self.tree_depth_first, &self.payloads)
+            .map(move |(path, payload)| (path.depth() - root_depth, path, payload))
+    }
+
+    /// Compare the structure of this tree with another tree, ignoring payloads.
+    ///
+    /// Paths that are only present in this tree are reported as [`StructuralChange::Removed`],
+    /// followed by paths that are only present in `other`, reported as [`StructuralChange::Added`].
+    /// Each group is given in depth-first order.
+    pub fn structural_diff<Payload2>(&self, other: &SpanTree<Payload2>) -> Vec<StructuralChange> {
+        let self_paths: HashSet<_> = self.tree_depth_first.iter().collect();
+        let other_paths: HashSet<_> = other.tree_depth_first.iter().collect();
+
+        let removed = self
+            .tree_depth_first
+            .iter()
+            .filter(|path| !other_paths.contains(path))
+            .map(|path| StructuralChange::Removed(path.clone()));
+        let added = other
+            .tree_depth_first
+            .iter()
+            .filter(|path| !self_paths.contains(path))
+            .map(|path| StructuralChange::Added(path.clone()));
+        removed.chain(added).collect()
+    }
+}
+
+/// A difference in structure between two span trees, see [`SpanTree::structural_diff`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StructuralChange {
+    /// A span path that is present in the other tree, but not in this tree.
+    Added(SpanPath),
+    /// A span path that is present in this tree, but not in the other tree.
+    Removed(SpanPath),
 }
 
 pub struct SpanTreeNode<'a, Payload> {
@@ -1,9 +1,11 @@
-use crate::{Record, RecordKind, SpanPath, SpanTree, SpanTreeNode};
-use eyre::eyre;
+use crate::{AnalyzeError, Record, RecordKind, SpanPath, SpanTree, SpanTreeNode};
+use serde::{Deserialize, Serialize};
 use std::cmp::max;
 use std::collections::hash_map::Entry;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fmt::Write;
+use std::io;
+use std::io::IsTerminal;
 use std::iter;
 use std::time::Duration;
 use time::OffsetDateTime;
@@ -42,6 +44,34 @@ pub struct DerivedStats {
     pub self_relative: Option<f64>,
 }
 
+impl TimingTree {
+    /// The total duration of the root span, if the tree is non-empty and the root has statistics.
+    pub fn root_duration(&self) -> Option<Duration> {
+        self.root()
+            .and_then(|root| root.payload().as_ref().map(|stats| stats.duration))
+    }
+
+    /// The number of times the root span was completed, if the tree is non-empty and the root has statistics.
+    pub fn total_count(&self) -> Option<u64> {
+        self.root()
+            .and_then(|root| root.payload().as_ref().map(|stats| stats.count))
+    }
+}
+
+impl<'a> TimingTreeNode<'a> {
+    /// The time spent in this span itself, i.e. its total duration minus the durations of its children.
+    ///
+    /// Returns `None` if this node or any of its children has no statistics.
+    pub fn self_duration(&self) -> Option<Duration> {
+        let duration = self.payload().as_ref()?.duration;
+        let children_duration = self
+            .visit_children()
+            .map(|child| child.payload().as_ref().map(|stats| stats.duration))
+            .sum::<Option<Duration>>()?;
+        Some(duration.saturating_sub(children_duration))
+    }
+}
+
 fn update_column_widths_for_line(column_widths: &mut Vec<usize>, line: &str) {
     let mut column_iter = line.split("\t");
     // Update existing column widths
@@ -54,15 +84,34 @@ fn update_column_widths_for_line(column_widths: &mut Vec<usize>, line: &str) {
     }
 }
 
-fn write_table_line(output: &mut String, line: &str, column_widths: &[usize], alignments: &[Alignment]) {
+fn write_table_line(
+    output: &mut String,
+    line: &str,
+    column_widths: &[usize],
+    alignments: &[Alignment],
+    cell_colors: &[Option<&str>],
+) {
     let padding = 2;
     debug_assert_eq!(line.lines().count(), 1, "line string must consist of a single line");
     let alignment_iter = alignments.iter().chain(iter::repeat(&Alignment::Left));
-    for ((cell, width), alignment) in line.split("\t").zip(column_widths).zip(alignment_iter) {
+    let color_iter = cell_colors.iter().chain(iter::repeat(&None));
+    for (((cell, width), alignment), color) in line
+        .split("\t")
+        .zip(column_widths)
+        .zip(alignment_iter)
+        .zip(color_iter)
+    {
+        // Color codes are written around the padded cell so that they do not affect column widths
+        if let Some(color) = color {
+            output.push_str(color);
+        }
         match alignment {
             Alignment::Left => write!(output, "{cell:width$}", width = width).unwrap(),
             Alignment::Right => write!(output, "{cell: >width$}", width = width).unwrap(),
         }
+        if color.is_some() {
+            output.push_str(ANSI_RESET);
+        }
         for _ in 0..padding {
             output.push(' ');
         }
@@ -77,6 +126,16 @@ enum Alignment {
 }
 
 fn format_table(header: &str, table: &str, alignments: &[Alignment]) -> String {
+    format_table_with_cell_colors(header, table, alignments, |_| Vec::new())
+}
+
+/// Formats a table, where the colors of the cells in each row are given by `row_cell_colors(row_index)`.
+fn format_table_with_cell_colors<'a>(
+    header: &str,
+    table: &str,
+    alignments: &[Alignment],
+    row_cell_colors: impl Fn(usize) -> Vec<Option<&'a str>>,
+) -> String {
     debug_assert_eq!(header.lines().count(), 1, "Header must only have a single line");
     let mut column_widths = vec![];
     update_column_widths_for_line(&mut column_widths, header);
@@ -86,13 +145,19 @@ fn format_table(header: &str, table: &str, alignments: &[Alignment]) -> String {
 
     let mut output = String::new();
     // Use default alignment for table headers, apply alignments only to cells
-    write_table_line(&mut output, header, &column_widths, &[]);
+    write_table_line(&mut output, header, &column_widths, &[], &[]);
     let header_len = output.len();
     output.push_str(&"═".repeat(header_len));
     writeln!(output).unwrap();
 
-    for line in table.lines() {
-        write_table_line(&mut output, line, &column_widths, alignments);
+    for (row_index, line) in table.lines().enumerate() {
+        write_table_line(
+            &mut output,
+            line,
+            &column_widths,
+            alignments,
+            &row_cell_colors(row_index),
+        );
     }
 
     output.push_str(&"═".repeat(header_len));
@@ -101,17 +166,88 @@ fn format_table(header: &str, table: &str, alignments: &[Alignment]) -> String {
     output
 }
 
+/// The header of the table produced by [`format_timing_tree`] and [`format_timing_tree_colored`].
+const TIMING_TREE_HEADER: &str = "Total\tAverage\tSelf time\tSelf\tCount\tRel parent\tRel root\tSpan";
+
+/// The column alignments of the table produced by [`format_timing_tree`] and [`format_timing_tree_colored`].
+const TIMING_TREE_ALIGNMENTS: &[Alignment] = {
+    use Alignment::{Left, Right};
+    &[Right, Right, Right, Right, Right, Right, Left]
+};
+
 pub fn format_timing_tree(tree: &TimingTree) -> String {
     let mut table = String::new();
     if let Some(root) = tree.root() {
         write_timing_tree_node(&mut table, root, &mut vec![]);
     }
-    use Alignment::{Left, Right};
-    format_table(
-        "Total\tAverage\tSelf\tCount\tRel parent\tRel root\tSpan",
-        &table,
-        &vec![Right, Right, Right, Right, Right, Left],
-    )
+    format_table(TIMING_TREE_HEADER, &table, TIMING_TREE_ALIGNMENTS)
+}
+
+const ANSI_RED: &str = "\x1b[31m";
+const ANSI_YELLOW: &str = "\x1b[33m";
+const ANSI_RESET: &str = "\x1b[0m";
+
+/// Determines whether output should be colored with ANSI escape codes.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum ColorChoice {
+    /// Use colors if standard output is a terminal and the `NO_COLOR` environment variable is not set.
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    pub fn use_color(&self) -> bool {
+        match self {
+            ColorChoice::Auto => {
+                let no_color = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+                !no_color && std::io::stdout().is_terminal()
+            }
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+        }
+    }
+}
+
+/// Same as [`format_timing_tree`], but highlights hotspots with ANSI colors.
+///
+/// The duration and percentage cells of each row are colored by the duration of the span relative to the root:
+/// red for more than 50% and yellow for more than 10%. If colors are disabled by `color`, the output is
+/// identical to [`format_timing_tree`].
+pub fn format_timing_tree_colored(tree: &TimingTree, color: ColorChoice) -> String {
+    if !color.use_color() {
+        return format_timing_tree(tree);
+    }
+
+    let mut table = String::new();
+    let mut relative_to_root = Vec::new();
+    if let Some(root) = tree.root() {
+        collect_duration_relative_to_root(&root, &mut relative_to_root);
+        write_timing_tree_node(&mut table, root, &mut vec![]);
+    }
+    format_table_with_cell_colors(TIMING_TREE_HEADER, &table, TIMING_TREE_ALIGNMENTS, |row_index| {
+        let color = match relative_to_root[row_index] {
+            Some(proportion) if proportion > 0.5 => Some(ANSI_RED),
+            Some(proportion) if proportion > 0.1 => Some(ANSI_YELLOW),
+            _ => None,
+        };
+        // Leave the count and span columns uncolored
+        vec![color, color, color, color, None, color, color]
+    })
+}
+
+/// Collects the duration relative to root for each node, in the same (depth-first) order as the rows
+/// written by [`write_timing_tree_node`].
+fn collect_duration_relative_to_root(node: &TimingTreeNode, output: &mut Vec<Option<f64>>) {
+    output.push(
+        node.payload()
+            .as_ref()
+            .and_then(|stats| stats.duration_relative_to_root),
+    );
+    for child in node.visit_children() {
+        collect_duration_relative_to_root(&child, output);
+    }
 }
 
 fn write_proportion(output: &mut String, proportion: Option<f64>) {
@@ -123,8 +259,139 @@ fn write_proportion(output: &mut String, proportion: Option<f64>) {
     }
 }
 
-fn write_timing_tree_node(output: &mut String, node: TimingTreeNode, active_stack: &mut Vec<bool>) {
-    let optional_stats = node.payload().as_ref();
+/// Formats the timing tree as tab-separated values, suitable for consumption by other tools.
+///
+/// Instead of drawing the hierarchy with box-drawing characters, each row has a leading `Depth` column
+/// that holds the depth of the node relative to the root of the tree.
+pub fn format_timing_tree_tsv(tree: &TimingTree) -> String {
+    let mut output = String::new();
+    writeln!(
+        output,
+        "Depth\tTotal\tAverage\tSelf time\tSelf\tCount\tRel parent\tRel root\tSpan"
+    )
+    .unwrap();
+    if let Some(root) = tree.root() {
+        write_timing_tree_node_tsv(&mut output, root, 0);
+    }
+    output
+}
+
+/// A serializable representation of a node in a [`TimingTree`], for example for consumption by
+/// dashboards or other tools.
+///
+/// Durations are given in integer nanoseconds. The statistics are `None` for intermediate spans
+/// for which no timings were recorded.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimingTreeJsonNode {
+    pub name: String,
+    pub total_ns: Option<u64>,
+    pub count: Option<u64>,
+    pub children: Vec<TimingTreeJsonNode>,
+}
+
+impl TimingTreeJsonNode {
+    fn from_timing_tree_node(node: TimingTreeNode) -> Self {
+        let stats = node.payload().as_ref();
+        Self {
+            name: node.path().span_name().unwrap_or("<root span>").to_string(),
+            total_ns: stats.map(|stats| u64::try_from(stats.duration.as_nanos()).unwrap_or(u64::MAX)),
+            count: stats.map(|stats| stats.count),
+            children: node
+                .visit_children()
+                .map(Self::from_timing_tree_node)
+                .collect(),
+        }
+    }
+}
+
+/// Converts the timing tree to a recursive JSON representation, see [`TimingTreeJsonNode`].
+///
+/// Returns `null` if the tree is empty.
+pub fn timing_tree_to_json(tree: &TimingTree) -> serde_json::Value {
+    let root = tree.root().map(TimingTreeJsonNode::from_timing_tree_node);
+    serde_json::to_value(root).expect("timing tree nodes are always serializable")
+}
+
+/// Writes the timings as comma-separated values, with one row per span of the timing tree.
+///
+/// The rows are given in depth-first order with the columns
+/// `span_path,total_seconds,count,avg_seconds,rel_parent,rel_root`, using the same statistics as
+/// [`AccumulatedTimings::create_timing_tree`]. The span path is given in its `>`-separated form.
+/// Cells are left empty for statistics that are not available, such as for intermediate spans
+/// without recorded timings.
+pub fn write_timings_csv(mut writer: impl io::Write, timings: &AccumulatedTimings) -> io::Result<()> {
+    writeln!(writer, "span_path,total_seconds,count,avg_seconds,rel_parent,rel_root")?;
+    let tree = timings.create_timing_tree();
+    for (_, path, optional_stats) in tree.iter_depth_first() {
+        write_csv_field(&mut writer, &path.to_string())?;
+        if let Some(stats) = optional_stats {
+            let total_seconds = stats.duration.as_secs_f64();
+            let avg_seconds = total_seconds / stats.count as f64;
+            write!(writer, ",{total_seconds},{},{avg_seconds},", stats.count)?;
+            if let Some(rel_parent) = stats.duration_relative_to_parent {
+                write!(writer, "{rel_parent}")?;
+            }
+            write!(writer, ",")?;
+            if let Some(rel_root) = stats.duration_relative_to_root {
+                write!(writer, "{rel_root}")?;
+            }
+            writeln!(writer)?;
+        } else {
+            writeln!(writer, ",,,,,")?;
+        }
+    }
+    Ok(())
+}
+
+/// Writes the timings in the folded stack format used by flamegraph tools such as `inferno` and
+/// `flamegraph.pl`.
+///
+/// Every span of the timing tree is written as a line of the form `run;step;simulate;solve 1234`,
+/// where the number is the self time of the span in whole microseconds, i.e. its total duration minus
+/// the durations of its children. Lines are written in depth-first order. Spans without statistics
+/// or with zero self time are omitted, since they do not contribute to the flamegraph.
+pub fn write_folded_stacks(mut writer: impl io::Write, timings: &AccumulatedTimings) -> io::Result<()> {
+    let tree = timings.create_timing_tree();
+    if let Some(root) = tree.root() {
+        write_folded_stacks_node(&mut writer, root)?;
+    }
+    Ok(())
+}
+
+fn write_folded_stacks_node(writer: &mut impl io::Write, node: TimingTreeNode) -> io::Result<()> {
+    let self_micros = node
+        .self_duration()
+        .map(|self_duration| self_duration.as_micros())
+        .unwrap_or_default();
+    if self_micros > 0 {
+        writeln!(writer, "{} {self_micros}", node.path().span_names().join(";"))?;
+    }
+    for child in node.visit_children() {
+        write_folded_stacks_node(writer, child)?;
+    }
+    Ok(())
+}
+
+/// Writes a single CSV field, quoting it if necessary.
+fn write_csv_field(writer: &mut impl io::Write, field: &str) -> io::Result<()> {
+    if field.contains([',', '"', '\n', '\r']) {
+        write!(writer, "\"{}\"", field.replace('"', "\"\""))
+    } else {
+        write!(writer, "{field}")
+    }
+}
+
+fn write_timing_tree_node_tsv(output: &mut String, node: TimingTreeNode, depth: usize) {
+    write!(output, "{depth}\t").unwrap();
+    write_timing_stats_cells(output, node.payload().as_ref());
+    writeln!(output, "\t{}", node.path().span_name().unwrap_or("<root span>")).unwrap();
+    for child in node.visit_children() {
+        write_timing_tree_node_tsv(output, child, depth + 1);
+    }
+}
+
+/// Writes the tab-separated statistics cells of a single node (every column except the span name).
+fn write_timing_stats_cells(output: &mut String, optional_stats: Option<&DerivedStats>) {
     let duration = optional_stats.map(|stats| stats.duration);
     let count = optional_stats.map(|stats| stats.count);
     write_duration(output, duration);
@@ -136,6 +403,10 @@ fn write_timing_tree_node(output: &mut String, node: TimingTreeNode, active_stac
     write_duration(output, avg_duration);
     write!(output, "\t").unwrap();
 
+    let self_duration = optional_stats.and_then(|stats| stats.self_duration);
+    write_duration(output, self_duration);
+    write!(output, "\t").unwrap();
+
     let self_relative = optional_stats.and_then(|stats| stats.self_relative);
     write_proportion(output, self_relative);
 
@@ -151,7 +422,10 @@ fn write_timing_tree_node(output: &mut String, node: TimingTreeNode, active_stac
     write!(output, "\t").unwrap();
     let duration_relative_to_root = optional_stats.and_then(|stats| stats.duration_relative_to_root);
     write_proportion(output, duration_relative_to_root);
+}
 
+fn write_timing_tree_node(output: &mut String, node: TimingTreeNode, active_stack: &mut Vec<bool>) {
+    write_timing_stats_cells(output, node.payload().as_ref());
     write!(output, "\t").unwrap();
     if let Some((&parent_is_active, predecessors)) = active_stack.split_last() {
         for &is_active in predecessors {
@@ -181,6 +455,40 @@ fn write_timing_tree_node(output: &mut String, node: TimingTreeNode, active_stac
     }
 }
 
+/// Formats the `n` slowest systems as a table with one row per system, see
+/// [`AccumulatedTimings::slowest_systems`].
+///
+/// Each row holds the total duration, the number of times the system ran, and the system span
+/// together with the system group it ran in.
+pub fn format_slowest_systems(timings: &AccumulatedTimings, n: usize) -> String {
+    let mut output = String::new();
+    for (path, stats) in timings.slowest_systems(n) {
+        write_duration(&mut output, Some(stats.duration));
+        let group = path
+            .parent()
+            .and_then(|parent| parent.span_name().map(str::to_string))
+            .unwrap_or_default();
+        let system = path.span_name().unwrap_or_default();
+        writeln!(output, "  {:>5}x  {system} ({group})", stats.count).unwrap();
+    }
+    output
+}
+
+/// Formats a flat table of span paths with their total duration and count, see
+/// [`AccumulatedTimings::spans_by_total_duration`].
+///
+/// If `top` is given, only the given number of spans with the largest total duration are included.
+pub fn format_span_list(timings: &AccumulatedTimings, top: Option<usize>) -> String {
+    let mut table = String::new();
+    let spans = timings.spans_by_total_duration();
+    for (path, stats) in spans.iter().take(top.unwrap_or(spans.len())) {
+        write_duration(&mut table, Some(stats.duration));
+        writeln!(table, "\t{}\t{path}", stats.count).unwrap();
+    }
+    use Alignment::{Left, Right};
+    format_table("Total\tCount\tSpan", &table, &[Right, Right, Left])
+}
+
 // TODO: Unit tests for this one?
 fn write_duration(output: &mut String, duration: Option<Duration>) {
     if let Some(duration) = duration {
@@ -201,7 +509,7 @@ fn write_duration(output: &mut String, duration: Option<Duration>) {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct AccumulatedTimings {
     span_stats: HashMap<SpanPath, DirectStats>,
 }
@@ -212,6 +520,9 @@ pub struct AccumulatedStepTimings {
     pub step_index: u64,
 }
 
+/// Names of the spans that `dynamecs_app` enters around each group of systems.
+const SYSTEM_GROUP_SPAN_NAMES: [&str; 3] = ["pre_systems", "simulation_systems", "post_systems"];
+
 impl AccumulatedTimings {
     pub fn new() -> Self {
         Self {
@@ -227,6 +538,97 @@ impl AccumulatedTimings {
             }
         }
     }
+
+    /// Returns timings in which spans that were completed fewer than `min_count` times are removed,
+    /// together with all their descendants.
+    ///
+    /// The root of the timing tree is always retained. Since the durations of the remaining spans
+    /// are unchanged, the time spent in removed spans is accounted for in the self time of their
+    /// parents, so that totals remain consistent.
+    pub fn filter_by_min_count(&self, min_count: u64) -> AccumulatedTimings {
+        let root = self.common_ancestor();
+        let removed_paths: Vec<_> = self
+            .span_stats
+            .iter()
+            .filter(|(path, stats)| stats.count < min_count && Some(*path) != root.as_ref())
+            .map(|(path, _)| path)
+            .collect();
+        let span_stats = self
+            .span_stats
+            .iter()
+            .filter(|(path, _)| {
+                !removed_paths
+                    .iter()
+                    .any(|removed_path| removed_path.is_ancestor_of(path))
+            })
+            .map(|(path, stats)| (path.clone(), stats.clone()))
+            .collect();
+        AccumulatedTimings { span_stats }
+    }
+
+    /// Returns the statistics of all spans, sorted by decreasing total duration.
+    ///
+    /// Spans with the same total duration are sorted by their path.
+    pub fn spans_by_total_duration(&self) -> Vec<(SpanPath, DirectStats)> {
+        let mut spans: Vec<_> = self
+            .span_stats
+            .iter()
+            .map(|(path, stats)| (path.clone(), stats.clone()))
+            .collect();
+        spans.sort_by(|(path1, stats1), (path2, stats2)| {
+            stats2
+                .duration
+                .cmp(&stats1.duration)
+                .then_with(|| path1.cmp(path2))
+        });
+        spans
+    }
+
+    /// Returns the statistics of the `n` slowest systems, sorted by decreasing total duration.
+    ///
+    /// Systems are identified as the spans that are entered directly inside the `pre_systems`,
+    /// `simulation_systems` or `post_systems` spans of `dynamecs_app`. A system therefore only
+    /// appears if it enters its own span when it runs, for example with `#[instrument]`.
+    pub fn slowest_systems(&self, n: usize) -> Vec<(SpanPath, DirectStats)> {
+        self.spans_by_total_duration()
+            .into_iter()
+            .filter(|(path, _)| {
+                path.parent()
+                    .and_then(|parent| {
+                        parent
+                            .span_name()
+                            .map(|name| SYSTEM_GROUP_SPAN_NAMES.contains(&name))
+                    })
+                    .unwrap_or(false)
+            })
+            .take(n)
+            .collect()
+    }
+
+    /// Creates a span tree with the same structure as [`create_timing_tree`](Self::create_timing_tree),
+    /// but in which the payload of each node is only the total duration of the span.
+    ///
+    /// Intermediate spans for which no statistics were recorded are given a zero duration.
+    pub fn to_duration_tree(&self) -> SpanTree<Duration> {
+        self.create_timing_tree().transform_payloads(|node| {
+            node.payload()
+                .as_ref()
+                .map(|stats| stats.duration)
+                .unwrap_or_default()
+        })
+    }
+
+    fn common_ancestor(&self) -> Option<SpanPath> {
+        self.span_stats
+            .keys()
+            // TODO: This can be done much more efficiently with some manual labor
+            // (i.e. start with the first element and keep knocking off names
+            // so that the path is an ancestor of *all* paths)
+            .fold(None, |common: Option<SpanPath>, path| match common {
+                None => Some(path.clone()),
+                Some(current_common) => Some(current_common.common_ancestor(path)),
+            })
+    }
 }
 
 impl AccumulatedTimings {
@@ -245,16 +647,7 @@ impl AccumulatedTimings {
             .collect();
 
         // The root node is the common ancestor of all the paths
-        let common_ancestor = self
-            .span_stats
-            .keys()
-            // TODO: This can be done much more efficiently with some manual labor
-            // (i.e. start with the first element and keep knocking off names
-            // so that the path is an ancestor of *all* paths)
-            .fold(None, |common: Option<SpanPath>, path| match common {
-                None => Some(path.clone()),
-                Some(current_common) => Some(current_common.common_ancestor(path)),
-            });
+        let common_ancestor = self.common_ancestor();
 
         if let Some(common_ancestor) = common_ancestor {
             // Insert all "intermediate nodes". For example, if the hash map contains
@@ -295,7 +688,9 @@ impl AccumulatedTimings {
                         .fold(Some(Duration::default()), |acc, maybe_duration| {
                             acc.zip(maybe_duration).map(|(a, b)| a + b)
                         });
-                    let self_duration = maybe_children_duration.map(|children_duration| duration - children_duration);
+                    // Spans on different threads may overlap, so that children can take longer than their parent
+                    let self_duration =
+                        maybe_children_duration.map(|children_duration| duration.saturating_sub(children_duration));
                     let self_relative = self_duration
                         .map(|self_time| self_time.as_secs_f64() / duration.as_secs_f64())
                         // If duration is zero, we get a NaN. Return None instead in this case
@@ -324,6 +719,39 @@ impl AccumulatedTimings {
     }
 }
 
+/// Assembles a [`TimingTree`] from manually specified span statistics.
+///
+/// This is primarily intended for building fixtures for tools that render timing trees, without
+/// having to construct log records. As with [`AccumulatedTimings::create_timing_tree`], missing
+/// intermediate spans are inserted without statistics and derived statistics are computed on
+/// [`build`](Self::build).
+#[derive(Debug, Clone, Default)]
+pub struct TimingTreeBuilder {
+    timings: AccumulatedTimings,
+}
+
+impl TimingTreeBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds statistics for the span with the given path.
+    ///
+    /// If the span has already been added, the statistics are combined.
+    pub fn add_span(mut self, path: SpanPath, duration: Duration, count: u64) -> Self {
+        self.timings
+            .span_stats
+            .entry(path)
+            .or_default()
+            .combine_mut(&DirectStats { duration, count });
+        self
+    }
+
+    pub fn build(&self) -> TimingTree {
+        self.timings.create_timing_tree()
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct AccumulatedTimingSeries {
     steps: Vec<AccumulatedStepTimings>,
@@ -339,68 +767,385 @@ impl AccumulatedTimingSeries {
         summary.merge_with_others(self.steps().iter().map(|step| &step.timings));
         summary
     }
+
+    /// Merges several series, for example from the log files of a simulation that was restarted
+    /// from a checkpoint, into a single series with steps sorted by step index.
+    ///
+    /// If the same step index appears in several series, for example because a step was re-run after
+    /// restarting, the occurrence in the later series is kept. Intransient timings are combined.
+    pub fn merge(series: impl IntoIterator<Item = AccumulatedTimingSeries>) -> Self {
+        let mut steps = BTreeMap::new();
+        let mut intransient_timings = AccumulatedTimings::new();
+        for series in series {
+            for step in series.steps {
+                steps.insert(step.step_index, step);
+            }
+            intransient_timings.merge_with_others(iter::once(&series.intransient_timings));
+        }
+        Self {
+            steps: steps.into_values().collect(),
+            intransient_timings,
+        }
+    }
 }
 
 impl AccumulatedTimingSeries {
     pub fn steps(&self) -> &[AccumulatedStepTimings] {
         &self.steps
     }
+
+    /// Returns the step index and the total duration of the span with the given path in each step,
+    /// in step order.
+    ///
+    /// If the span is entered several times in a step, the durations are summed. Steps in which the
+    /// span was never completed are skipped.
+    pub fn per_step_span_durations(&self, span_path: &SpanPath) -> Vec<(u64, Duration)> {
+        self.steps
+            .iter()
+            .filter_map(|step| {
+                let stats = step.timings.span_stats.get(span_path)?;
+                Some((step.step_index, stats.duration))
+            })
+            .collect()
+    }
+
+    /// Computes the distribution of the per-step durations of the span with the given path,
+    /// see [`per_step_span_durations`](Self::per_step_span_durations).
+    ///
+    /// Returns `None` if the span was not completed in any step.
+    pub fn span_duration_distribution(&self, span_path: &SpanPath) -> Option<DurationDistribution> {
+        let durations = self
+            .per_step_span_durations(span_path)
+            .into_iter()
+            .map(|(_, duration)| duration)
+            .collect();
+        DurationDistribution::from_durations(durations)
+    }
 }
 
-pub fn extract_step_timings<'a>(records: impl IntoIterator<Item = Record>) -> eyre::Result<AccumulatedTimingSeries> {
+/// Summary statistics of a collection of durations, such as the durations of a span across steps.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DurationDistribution {
+    pub count: usize,
+    pub min: Duration,
+    pub max: Duration,
+    pub mean: Duration,
+    /// The median, which is the mean of the two middle durations if the count is even.
+    pub median: Duration,
+    /// The 95th percentile, computed with the nearest-rank method.
+    pub p95: Duration,
+}
+
+impl DurationDistribution {
+    /// Computes the distribution of the given durations, or returns `None` if there are none.
+    pub fn from_durations(mut durations: Vec<Duration>) -> Option<Self> {
+        if durations.is_empty() {
+            return None;
+        }
+        durations.sort();
+        let count = durations.len();
+        let total: Duration = durations.iter().sum();
+        let median = if count.is_multiple_of(2) {
+            (durations[count / 2 - 1] + durations[count / 2]) / 2
+        } else {
+            durations[count / 2]
+        };
+        // Nearest rank: the smallest duration such that at least 95 % of the durations are no larger
+        let p95_rank = (95 * count).div_ceil(100);
+        Some(Self {
+            count,
+            min: durations[0],
+            max: durations[count - 1],
+            mean: total.div_f64(count as f64),
+            median,
+            p95: durations[p95_rank - 1],
+        })
+    }
+}
+
+/// Identifies the span that is treated as a single step during timing extraction.
+///
+/// By default, this is the `step` span of `dynamecs_app`, indexed by its `step_index` field.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StepUnit {
+    /// The name of the step span.
+    pub span_name: String,
+    /// The target of the records associated with the step span.
+    pub target: String,
+    /// The name of the span field that holds the (non-negative integer) index of the step.
+    pub index_field: String,
+}
+
+impl Default for StepUnit {
+    fn default() -> Self {
+        Self::new("step", "dynamecs_app", "step_index")
+    }
+}
+
+impl StepUnit {
+    pub fn new(span_name: impl Into<String>, target: impl Into<String>, index_field: impl Into<String>) -> Self {
+        Self {
+            span_name: span_name.into(),
+            target: target.into(),
+            index_field: index_field.into(),
+        }
+    }
+
+    fn matches(&self, record: &Record) -> bool {
+        record.span().map(|span| span.name()) == Some(self.span_name.as_str()) && record.target() == self.target
+    }
+}
+
+/// Extracts the timings of every step from the given records.
+///
+/// Spans are matched by their span path and thread. Spans with the same path that are active
+/// at the same time on the same thread, such as spans instrumenting concurrently running
+/// `async` tasks, must additionally be distinguished by an explicit `span_id` field
+/// (see [`Span::id`](crate::Span::id)), since the log does not record span ids otherwise.
+/// Without distinct ids, such spans are reported as an invalid log.
+pub fn extract_step_timings<'a>(
+    records: impl IntoIterator<Item = Record>,
+) -> Result<AccumulatedTimingSeries, AnalyzeError> {
+    extract_step_timings_with_unit(records, &StepUnit::default())
+}
+
+/// Extracts the step timings of several sets of records, such as the records of consecutive log files,
+/// and merges them with [`AccumulatedTimingSeries::merge`].
+pub fn extract_step_timings_from_many(
+    record_sets: impl IntoIterator<Item = impl IntoIterator<Item = Record>>,
+) -> Result<AccumulatedTimingSeries, AnalyzeError> {
+    let series = record_sets
+        .into_iter()
+        .map(extract_step_timings)
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(AccumulatedTimingSeries::merge(series))
+}
+
+/// Same as [`extract_step_timings`], but with a custom span as the step unit.
+pub fn extract_step_timings_with_unit(
+    records: impl IntoIterator<Item = Record>,
+    unit: &StepUnit,
+) -> Result<AccumulatedTimingSeries, AnalyzeError> {
+    let mut steps = Vec::new();
+    let intransient_timings = visit_step_timings(records, unit, |step| steps.push(step))?;
+    Ok(AccumulatedTimingSeries {
+        steps,
+        intransient_timings,
+    })
+}
+
+/// Extracts the timings of each complete step and passes them to the given visitor as soon as the step
+/// is complete.
+///
+/// Records are processed as they are produced by the iterator and are not retained, so that arbitrarily
+/// large logs can be processed with bounded memory. Returns the timings of spans that are not part of
+/// any step.
+///
+/// Steps are delimited by the spans on the thread that entered the `run` span of `dynamecs_app`.
+/// Spans on other threads, for example from a multi-threaded solver, are included if they are nested
+/// inside the `run` span, and are attributed to the active spans of their own thread. Durations of
+/// overlapping spans on different threads are summed, so that the children of a span may take
+/// longer than the span itself.
+pub fn visit_step_timings(
+    records: impl IntoIterator<Item = Record>,
+    unit: &StepUnit,
+    visitor: impl FnMut(AccumulatedStepTimings),
+) -> Result<AccumulatedTimings, AnalyzeError> {
     // TODO: Collect statistics from spans outside run as well
-    find_and_visit_dynamecs_run_span(records.into_iter())
+    find_and_visit_dynamecs_run_span(records.into_iter(), unit, visitor)
 }
 
-pub fn extract_timing_summary<'a>(records: impl IntoIterator<Item = Record>) -> eyre::Result<AccumulatedTimings> {
-    extract_step_timings(records).map(|series| series.summarize())
+/// Extracts the aggregate timings of all steps and any spans outside of steps.
+///
+/// In contrast to `extract_step_timings(records)?.summarize()`, the timings of individual steps
+/// are merged into the summary as they are completed instead of being retained.
+pub fn extract_timing_summary<'a>(
+    records: impl IntoIterator<Item = Record>,
+) -> Result<AccumulatedTimings, AnalyzeError> {
+    let mut summary = AccumulatedTimings::new();
+    let intransient_timings = visit_step_timings(records, &StepUnit::default(), |step| {
+        summary.merge_with_others(iter::once(&step.timings))
+    })?;
+    summary.merge_with_others(iter::once(&intransient_timings));
+    Ok(summary)
+}
+
+/// Groups records by the `step` span of `dynamecs_app` that they occur in.
+///
+/// Each group starts with the record entering a step span and ends with the record exiting the
+/// same span on the same thread, and is yielded together with the index of the step.
+/// Records in between are included regardless of the thread they originate from, while records
+/// outside of any step are skipped. The last group may be incomplete if the log ends in the
+/// middle of a step.
+pub fn group_records_by_step(records: impl IntoIterator<Item = Record>) -> impl Iterator<Item = (u64, Vec<Record>)> {
+    let unit = StepUnit::default();
+    let mut records = records.into_iter();
+    iter::from_fn(move || {
+        let (step_index, step_enter_record) = loop {
+            let record = records.next()?;
+            if record.kind() == SpanEnter && unit.matches(&record) {
+                if let Some(step_index) = record.step_index() {
+                    break (step_index, record);
+                }
+            }
+        };
+
+        let step_path = step_enter_record.create_span_path().ok();
+        let step_thread = step_enter_record.thread_id().to_string();
+        let mut group = vec![step_enter_record];
+        for record in records.by_ref() {
+            let is_step_exit = record.kind() == SpanExit
+                && unit.matches(&record)
+                && record.thread_id() == step_thread
+                && record.create_span_path().ok() == step_path;
+            group.push(record);
+            if is_step_exit {
+                break;
+            }
+        }
+        Some((step_index, group))
+    })
+}
+
+/// Extracts the timings of a single step from its records, as yielded by [`group_records_by_step`].
+///
+/// The first record must be the record entering the step span. Returns `None` if there are no
+/// records or the step is not complete.
+pub fn extract_single_step_timings(
+    records: impl IntoIterator<Item = Record>,
+) -> Result<Option<AccumulatedStepTimings>, AnalyzeError> {
+    let mut records = records.into_iter();
+    match records.next() {
+        Some(step_enter_record) => visit_dynamecs_step_span(&step_enter_record, &mut records, &StepUnit::default()),
+        None => Ok(None),
+    }
+}
+
+/// Writes span enter and exit records in the [Chrome Trace Event format].
+///
+/// The output can be loaded into `chrome://tracing` or [Perfetto](https://ui.perfetto.dev).
+/// Each span enter record becomes a `"B"` (begin) event and each matching exit record becomes
+/// an `"E"` (end) event, named after the span and with the record target as category.
+/// Timestamps are given in microseconds relative to the first record. The thread ids of the
+/// records are used as `tid`, with the number extracted from ids of the form `ThreadId(n)`.
+///
+/// Exit records for spans that were never entered on the same thread are skipped, so that
+/// logs that start in the middle of a span still produce a well-formed trace.
+///
+/// [Chrome Trace Event format]: https://docs.google.com/document/d/1CvAClvFfyA5R-PhYUmn5OOQtYMH4h6I0nSsKchNAySU
+pub fn write_chrome_trace(mut writer: impl io::Write, records: impl IntoIterator<Item = Record>) -> io::Result<()> {
+    let mut start_timestamp = None;
+    let mut active_spans = HashSet::new();
+    let mut is_first_event = true;
+
+    writer.write_all(b"{\"traceEvents\":[")?;
+    for record in records {
+        let start = *start_timestamp.get_or_insert(*record.timestamp());
+        let phase = match record.kind() {
+            SpanEnter => "B",
+            SpanExit => "E",
+            _ => continue,
+        };
+        let (Some(span), Ok(path)) = (record.span(), record.create_span_path()) else {
+            continue;
+        };
+        let key = (record.thread_id().to_string(), path);
+        if record.kind() == SpanEnter {
+            active_spans.insert(key);
+        } else if !active_spans.remove(&key) {
+            continue;
+        }
+
+        let mut event = serde_json::json!({
+            "name": span.name(),
+            "cat": record.target(),
+            "ph": phase,
+            "ts": (*record.timestamp() - start).whole_microseconds(),
+            "pid": 1,
+            "tid": chrome_thread_id(record.thread_id()),
+        });
+        if record.kind() == SpanEnter {
+            event["args"] = span.fields().clone();
+        }
+
+        if !is_first_event {
+            writer.write_all(b",")?;
+        }
+        is_first_event = false;
+        writer.write_all(b"\n")?;
+        serde_json::to_writer(&mut writer, &event)?;
+    }
+    writer.write_all(b"\n]}\n")?;
+    Ok(())
+}
+
+fn chrome_thread_id(thread_id: &str) -> u64 {
+    let digits: String = thread_id.chars().filter(char::is_ascii_digit).collect();
+    digits.parse().unwrap_or(0)
 }
 
 fn find_and_visit_dynamecs_run_span<'a>(
     mut records: impl Iterator<Item = Record>,
-) -> eyre::Result<AccumulatedTimingSeries> {
+    unit: &StepUnit,
+    visitor: impl FnMut(AccumulatedStepTimings),
+) -> Result<AccumulatedTimings, AnalyzeError> {
     // First try to find the `run` span in the records
     while let Some(record) = records.next() {
         if let Some(span) = record.span() {
             if span.name() == "run" && record.target() == "dynamecs_app" && record.kind() == RecordKind::SpanEnter {
-                return visit_dynamecs_run_span(&record, records);
+                return visit_dynamecs_run_span(&record, records, unit, visitor);
             }
         }
     }
 
-    Err(eyre!(
-        "Could not find new event for `run` span of dynamecs among records"
+    Err(AnalyzeError::invalid_log(
+        "Could not find new event for `run` span of dynamecs among records",
     ))
 }
 
 fn visit_dynamecs_run_span<'a>(
     run_new_record: &Record,
     remaining_records: impl Iterator<Item = Record>,
-) -> eyre::Result<AccumulatedTimingSeries> {
+    unit: &StepUnit,
+    mut visitor: impl FnMut(AccumulatedStepTimings),
+) -> Result<AccumulatedTimings, AnalyzeError> {
     let run_thread = run_new_record.thread_id();
+    let run_path = run_new_record.create_span_path()?;
     let mut iter = remaining_records;
-    let mut steps = Vec::new();
 
-    let mut intransient_accumulator = TimingAccumulator::new();
-    intransient_accumulator.enter_span(run_new_record.create_span_path()?, *run_new_record.timestamp())?;
+    let mut intransient_accumulators = ThreadTimingAccumulators::new();
+    intransient_accumulators.enter_span(
+        run_thread,
+        run_path.clone(),
+        record_span_id(run_new_record),
+        *run_new_record.timestamp(),
+    )?;
 
     while let Some(record) = iter.next() {
         if record.thread_id() == run_thread {
             if let Some(span) = record.span() {
                 match (span.name(), record.target(), record.kind()) {
-                    ("step", "dynamecs_app", SpanEnter) => {
-                        if let Some(step) = visit_dynamecs_step_span(&record, &mut iter)? {
+                    (_, _, SpanEnter) if unit.matches(&record) => {
+                        if let Some(step) = visit_dynamecs_step_span(&record, &mut iter, unit)? {
                             // Only collect complete time steps
-                            steps.push(step);
+                            visitor(step);
                         }
                     }
                     // Accumulate "intransient timings", i.e. timings for things that are
                     // not inside of a step
-                    (_, _, SpanEnter) => {
-                        intransient_accumulator.enter_span(record.create_span_path()?, *record.timestamp())?
-                    }
+                    (_, _, SpanEnter) => intransient_accumulators.enter_span(
+                        run_thread,
+                        record.create_span_path()?,
+                        record_span_id(&record),
+                        *record.timestamp(),
+                    )?,
                     (span_name, record_target, SpanExit) => {
-                        intransient_accumulator.exit_span(record.create_span_path()?, *record.timestamp())?;
+                        intransient_accumulators.exit_span(
+                            run_thread,
+                            record.create_span_path()?,
+                            record_span_id(&record),
+                            *record.timestamp(),
+                        )?;
                         if span_name == "run" && record_target == "dynamecs_app" {
                             break;
                         }
@@ -408,14 +1153,13 @@ fn visit_dynamecs_run_span<'a>(
                     _ => {}
                 }
             }
+        } else if let Some(path) = nested_span_path_on_other_thread(&record, &run_path) {
+            intransient_accumulators.visit_record(&record, path)?;
         }
     }
 
-    Ok(AccumulatedTimingSeries {
-        steps,
-        intransient_timings: AccumulatedTimings {
-            span_stats: intransient_accumulator.collect_completed_statistics(),
-        },
+    Ok(AccumulatedTimings {
+        span_stats: intransient_accumulators.collect_completed_statistics(),
     })
 }
 
@@ -423,41 +1167,58 @@ fn visit_dynamecs_run_span<'a>(
 fn visit_dynamecs_step_span<'a>(
     step_new_record: &Record,
     remaining_records: &mut impl Iterator<Item = Record>,
-) -> eyre::Result<Option<AccumulatedStepTimings>> {
+    unit: &StepUnit,
+) -> Result<Option<AccumulatedStepTimings>, AnalyzeError> {
+    let step_thread = step_new_record.thread_id();
     let step_path = step_new_record.create_span_path()?;
 
-    let mut accumulator = TimingAccumulator::new();
-    accumulator.enter_span(step_path.clone(), step_new_record.timestamp().clone())?;
+    let mut accumulators = ThreadTimingAccumulators::new();
+    accumulators.enter_span(
+        step_thread,
+        step_path.clone(),
+        record_span_id(step_new_record),
+        step_new_record.timestamp().clone(),
+    )?;
 
     let step_index = step_new_record
         .span()
-        .and_then(|span| span.fields().pointer("/step_index"))
+        .and_then(|span| span.fields().get(&unit.index_field))
         .and_then(|value| value.as_u64())
-        .ok_or_else(|| eyre!("step span does not have step_index field"))?;
+        .ok_or_else(|| AnalyzeError::missing_field(&unit.index_field))?;
 
     while let Some(record) = remaining_records.next() {
-        if record.thread_id() == step_new_record.thread_id() {
-            if let Some(span) = record.span() {
-                match record.kind() {
-                    SpanEnter => {
-                        accumulator.enter_span(record.create_span_path()?, record.timestamp().clone())?;
-                    }
-                    SpanExit => {
-                        // TODO: use a stack to verify that open/close events are consistent?
-                        let span_path = record.create_span_path()?;
-                        let is_step_span_path = span_path == step_path;
-                        accumulator.exit_span(span_path, record.timestamp().clone())?;
-                        if span.name() == "step" && record.target() == "dynamecs_app" && is_step_span_path {
-                            break;
-                        }
+        if record.thread_id() == step_thread && record.span().is_some() {
+            match record.kind() {
+                SpanEnter => {
+                    accumulators.enter_span(
+                        step_thread,
+                        record.create_span_path()?,
+                        record_span_id(&record),
+                        record.timestamp().clone(),
+                    )?;
+                }
+                SpanExit => {
+                    // TODO: use a stack to verify that open/close events are consistent?
+                    let span_path = record.create_span_path()?;
+                    let is_step_span_path = span_path == step_path;
+                    accumulators.exit_span(
+                        step_thread,
+                        span_path,
+                        record_span_id(&record),
+                        record.timestamp().clone(),
+                    )?;
+                    if unit.matches(&record) && is_step_span_path {
+                        break;
                     }
-                    _ => {}
                 }
+                _ => {}
             }
+        } else if let Some(path) = nested_span_path_on_other_thread(&record, &step_path) {
+            accumulators.visit_record(&record, path)?;
         }
     }
 
-    if accumulator.has_active_spans() {
+    if accumulators.has_active_spans() {
         // If there are active spans, then the step is not yet complete,
         // so we do not want to include it in accumulation
         // (would lead to inconsistent time between parent and children)
@@ -465,7 +1226,7 @@ fn visit_dynamecs_step_span<'a>(
     } else {
         Ok(Some(AccumulatedStepTimings {
             timings: AccumulatedTimings {
-                span_stats: accumulator.collect_completed_statistics(),
+                span_stats: accumulators.collect_completed_statistics(),
             },
             step_index,
         }))
@@ -475,7 +1236,11 @@ fn visit_dynamecs_step_span<'a>(
 #[derive(Debug)]
 struct TimingAccumulator {
     completed_statistics: HashMap<SpanPath, DirectStats>,
-    enter_timestamps: HashMap<SpanPath, OffsetDateTime>,
+    /// Enter timestamps of active spans, keyed by span path and span id.
+    ///
+    /// The span id allows the same span path to be active multiple times simultaneously,
+    /// which happens for spans instrumenting concurrent `async` tasks.
+    enter_timestamps: HashMap<(SpanPath, Option<u64>), OffsetDateTime>,
 }
 
 impl TimingAccumulator {
@@ -486,25 +1251,41 @@ impl TimingAccumulator {
         }
     }
 
-    pub fn enter_span(&mut self, path: SpanPath, timestamp: OffsetDateTime) -> eyre::Result<()> {
-        match self.enter_timestamps.entry(path) {
+    pub fn enter_span(
+        &mut self,
+        path: SpanPath,
+        span_id: Option<u64>,
+        timestamp: OffsetDateTime,
+    ) -> Result<(), AnalyzeError> {
+        match self.enter_timestamps.entry((path, span_id)) {
             Entry::Vacant(vacancy) => {
                 vacancy.insert(timestamp);
                 Ok(())
             }
-            Entry::Occupied(old) => Err(eyre!(
-                "tried to create new span {} that is already active\
+            Entry::Occupied(old) => Err(AnalyzeError::invalid_log(format!(
+                "tried to create new span {}{} that is already active\
                                                (not closed)",
-                old.key()
-            )),
+                old.key().0,
+                format_span_id(span_id)
+            ))),
         }
     }
 
-    pub fn exit_span(&mut self, path: SpanPath, timestamp_close: OffsetDateTime) -> eyre::Result<()> {
-        let timestamp_enter = self
-            .enter_timestamps
-            .remove(&path)
-            .ok_or_else(|| eyre!("found close event for span that is not currently active. Span path: {path}"))?;
+    pub fn exit_span(
+        &mut self,
+        path: SpanPath,
+        span_id: Option<u64>,
+        timestamp_close: OffsetDateTime,
+    ) -> Result<(), AnalyzeError> {
+        let key = (path, span_id);
+        let timestamp_enter = self.enter_timestamps.remove(&key).ok_or_else(|| {
+            AnalyzeError::invalid_log(format!(
+                "found close event for span that is not currently active. Span path: {}{}",
+                key.0,
+                format_span_id(span_id)
+            ))
+        })?;
+        let (path, _) = key;
         let span_duration: Duration = (timestamp_close - timestamp_enter).unsigned_abs();
         let accumulated_stats = self.completed_statistics.entry(path).or_default();
         accumulated_stats.combine_mut(&DirectStats::from_single_duration(span_duration));
@@ -519,3 +1300,96 @@ impl TimingAccumulator {
         self.completed_statistics
     }
 }
+
+/// Accumulates timings with a separate [`TimingAccumulator`] for each thread, so that every span
+/// is attributed to the active spans of its own thread.
+#[derive(Debug)]
+struct ThreadTimingAccumulators {
+    accumulators: HashMap<String, TimingAccumulator>,
+}
+
+impl ThreadTimingAccumulators {
+    pub fn new() -> Self {
+        Self {
+            accumulators: Default::default(),
+        }
+    }
+
+    fn accumulator(&mut self, thread_id: &str) -> &mut TimingAccumulator {
+        self.accumulators
+            .entry(thread_id.to_string())
+            .or_insert_with(TimingAccumulator::new)
+    }
+
+    pub fn enter_span(
+        &mut self,
+        thread_id: &str,
+        path: SpanPath,
+        span_id: Option<u64>,
+        timestamp: OffsetDateTime,
+    ) -> Result<(), AnalyzeError> {
+        self.accumulator(thread_id)
+            .enter_span(path, span_id, timestamp)
+    }
+
+    pub fn exit_span(
+        &mut self,
+        thread_id: &str,
+        path: SpanPath,
+        span_id: Option<u64>,
+        timestamp_close: OffsetDateTime,
+    ) -> Result<(), AnalyzeError> {
+        self.accumulator(thread_id)
+            .exit_span(path, span_id, timestamp_close)
+    }
+
+    /// Enters or exits the span of the record with the given path on the thread of the record.
+    pub fn visit_record(&mut self, record: &Record, path: SpanPath) -> Result<(), AnalyzeError> {
+        match record.kind() {
+            SpanEnter => self.enter_span(record.thread_id(), path, record_span_id(record), *record.timestamp()),
+            SpanExit => self.exit_span(record.thread_id(), path, record_span_id(record), *record.timestamp()),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn has_active_spans(&self) -> bool {
+        self.accumulators
+            .values()
+            .any(|accumulator| accumulator.has_active_spans())
+    }
+
+    /// Combines the completed statistics of all threads.
+    pub fn collect_completed_statistics(self) -> HashMap<SpanPath, DirectStats> {
+        let mut statistics: HashMap<SpanPath, DirectStats> = HashMap::new();
+        for accumulator in self.accumulators.into_values() {
+            for (path, stats) in accumulator.collect_completed_statistics() {
+                statistics.entry(path).or_default().combine_mut(&stats);
+            }
+        }
+        statistics
+    }
+}
+
+/// Returns the path of the span entered or exited by a record from a thread other than the one
+/// driving the extraction, if the span is nested inside the span with the given path.
+///
+/// Spans on other threads that are unrelated to the simulation, for example from background
+/// threads, are skipped.
+fn nested_span_path_on_other_thread(record: &Record, ancestor_path: &SpanPath) -> Option<SpanPath> {
+    record.span()?;
+    record
+        .create_span_path()
+        .ok()
+        .filter(|path| path != ancestor_path && ancestor_path.is_ancestor_of(path))
+}
+
+/// The id of the span entered or exited by the record, see [`Span::id`](crate::Span::id).
+fn record_span_id(record: &Record) -> Option<u64> {
+    record.span().and_then(|span| span.id())
+}
+
+fn format_span_id(span_id: Option<u64>) -> String {
+    span_id
+        .map(|id| format!(" (span id {id})"))
+        .unwrap_or_default()
+}
@@ -0,0 +1,100 @@
+//! An append-only store of aggregate timings, for tracking performance across commits.
+//!
+//! The store is a [JSON Lines](https://jsonlines.org) file in which every line is a
+//! [`TimingHistoryEntry`], holding the timings of a single run tagged with a commit hash and a timestamp.
+use crate::timing::TimingTree;
+use crate::AnalyzeError;
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::io::{BufRead, BufReader};
+use time::OffsetDateTime;
+
+/// The timings of a single run in a timing history.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimingHistoryEntry {
+    /// The commit hash of the code that produced the timings.
+    pub commit: String,
+    /// The time at which the entry was recorded.
+    #[serde(with = "time::serde::rfc3339")]
+    pub timestamp: OffsetDateTime,
+    /// The timings of all spans with recorded statistics, in depth-first order.
+    pub spans: Vec<TimingHistorySpan>,
+}
+
+/// The aggregate timings of a single span in a [`TimingHistoryEntry`].
+///
+/// As for [`TimingTreeJsonNode`](crate::timing::TimingTreeJsonNode), durations are given in integer nanoseconds.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimingHistorySpan {
+    /// The span path in its `>`-separated form, for example `run>step>simulate`.
+    pub path: String,
+    pub total_ns: u64,
+    pub count: u64,
+}
+
+impl TimingHistoryEntry {
+    /// Creates an entry from the spans of the timing tree that have statistics.
+    pub fn from_timing_tree(commit: impl Into<String>, timestamp: OffsetDateTime, tree: &TimingTree) -> Self {
+        let spans = tree
+            .iter_depth_first()
+            .filter_map(|(_, path, stats)| {
+                stats.as_ref().map(|stats| TimingHistorySpan {
+                    path: path.to_string(),
+                    total_ns: u64::try_from(stats.duration.as_nanos()).unwrap_or(u64::MAX),
+                    count: stats.count,
+                })
+            })
+            .collect();
+        Self {
+            commit: commit.into(),
+            timestamp,
+            spans,
+        }
+    }
+}
+
+/// Appends the entry as a single line to the timing history.
+///
+/// The line is written with a single call to `write_all`, so that entries appended concurrently
+/// to a file opened in append mode are not interleaved.
+pub fn append_timing_history(mut writer: impl io::Write, entry: &TimingHistoryEntry) -> io::Result<()> {
+    let mut line = serde_json::to_string(entry)?;
+    line.push('\n');
+    writer.write_all(line.as_bytes())
+}
+
+/// Reads all entries of a timing history, in the order in which they were appended.
+pub fn read_timing_history(reader: impl io::Read) -> Result<Vec<TimingHistoryEntry>, AnalyzeError> {
+    let mut entries = Vec::new();
+    for (line_index, line) in BufReader::new(reader).lines().enumerate() {
+        let line = line?;
+        if !line.trim().is_empty() {
+            let entry = serde_json::from_str(&line).map_err(|err| AnalyzeError::Parse {
+                line: line_index + 1,
+                source: Box::new(err),
+            })?;
+            entries.push(entry);
+        }
+    }
+    Ok(entries)
+}
+
+/// Groups the entries by commit.
+///
+/// The groups are ordered by the first appearance of each commit, which for an append-only history is
+/// the order in which the commits were first recorded. Entries within a group keep their order.
+pub fn group_timing_history_by_commit(
+    entries: impl IntoIterator<Item = TimingHistoryEntry>,
+) -> Vec<(String, Vec<TimingHistoryEntry>)> {
+    let mut groups: Vec<(String, Vec<TimingHistoryEntry>)> = Vec::new();
+    for entry in entries {
+        match groups
+            .iter_mut()
+            .find(|(commit, _)| *commit == entry.commit)
+        {
+            Some((_, group)) => group.push(entry),
+            None => groups.push((entry.commit.clone(), vec![entry])),
+        }
+    }
+    groups
+}
@@ -1,35 +1,18 @@
-use dynamecs_analyze::{iterate_records, iterate_records_from_reader, write_records, Record, RecordBuilder};
+use dynamecs_analyze::{iterate_records, iterate_records_from_reader, redact, write_records, Record, RecordBuilder};
 use escargot::CargoBuild;
 use insta::assert_snapshot;
 use tempfile::tempdir;
 use time::{Date, Month, UtcOffset};
 
-fn replace_middle(s: &str, prefix: &str, suffix: &str, replacement: &str) -> Option<String> {
-    s.strip_prefix(prefix)
-        .and_then(|s| s.strip_suffix(suffix))
-        .map(|_| format!("{prefix}{replacement}{suffix}"))
-}
-
-fn redact_message(msg: &str) -> String {
-    // Replace the "middle" part of the pattern "<prefix><middle><suffix>"
-    let path_replacement = "<redacted path>";
-    let path_redactions = [
-        // prefix, suffix
-        ["Working directory: ", ""],
-        ["Logging text to file ", " with log level trace"],
-        ["Logging JSON to file ", " with log level trace"],
-        ["Archived log file path: ", ""],
-        ["Archived JSON log file path: ", ""],
-        ["Output base path: ", ""],
-    ];
-    for [prefix, suffix] in path_redactions {
-        if let Some(replaced) = replace_middle(msg, prefix, suffix, path_replacement) {
-            return replaced;
-        }
-    }
-
-    msg.to_string()
-}
+/// Prefixes and suffixes of messages that contain paths, which vary between runs.
+const PATH_REDACTIONS: [(&str, &str); 6] = [
+    ("Working directory: ", ""),
+    ("Logging text to file ", " with log level trace"),
+    ("Logging JSON to file ", " with log level trace"),
+    ("Archived log file path: ", ""),
+    ("Archived JSON log file path: ", ""),
+    ("Output base path: ", ""),
+];
 
 fn redact_records(records: &[Record]) -> Vec<Record> {
     let arbitrary_timestamp = Date::from_calendar_date(2000, Month::November, 14)
@@ -40,18 +23,12 @@ fn redact_records(records: &[Record]) -> Vec<Record> {
     records
         .iter()
         .cloned()
+        .map(|record| redact(record, &PATH_REDACTIONS, "<redacted path>"))
         .map(|record| {
-            let message_override = record.message().map(|message| redact_message(message));
-
-            let mut builder = RecordBuilder::from_record(record)
+            RecordBuilder::from_record(record)
                 .timestamp(arbitrary_timestamp)
-                .thread_id("ThreadId(0)");
-
-            if let Some(msg) = message_override {
-                builder = builder.message(msg);
-            }
-
-            builder.build()
+                .thread_id("ThreadId(0)")
+                .build()
         })
         .collect()
 }
@@ -1,7 +1,12 @@
-use dynamecs_analyze::{iterate_records_from_reader, write_records, Level, Record, RecordBuilder, RecordKind, Span};
+use dynamecs_analyze::{
+    iterate_binary_records_from_reader, iterate_records, iterate_records_from_reader, redact, write_binary_records,
+    write_event_fields, write_records, AnalyzeError, Level, Record, RecordBuilder, RecordIter, RecordKind, Span,
+};
 use serde_json::json;
 use serde_json::Value::Object;
 use std::error::Error;
+use std::io;
+use std::io::Read;
 use time::format_description::well_known::Iso8601;
 use time::Month::February;
 use time::{Date, Duration, OffsetDateTime, UtcOffset};
@@ -16,6 +21,7 @@ macro_rules! span_path {
 mod span_path;
 mod span_tree;
 mod timing;
+mod timing_history;
 
 #[test]
 fn test_basic_records_iteration() {
@@ -26,7 +32,7 @@ fn test_basic_records_iteration() {
         {"timestamp":"2023-03-29T12:48:51.441519Z","level":"DEBUG","fields":{"message":"enter"},"target":"dynsys::backward_euler","span":{"name":"solve_linear_system"},"spans":[{"name":"run"},{"step_index":16,"name":"step"},{"name":"Backward Euler"},{"name":"Backward Euler"},{"hessian_mod":"NoModification","k":8,"name":"Newton iteration"},{"name":"solve_linear_system"}], "threadId": "ThreadId(0)"}
     "###;
     let records: Vec<Record> = iterate_records_from_reader(log_data.as_bytes())
-        .collect::<eyre::Result<_>>()
+        .collect::<Result<_, AnalyzeError>>()
         .unwrap();
 
     assert_eq!(records.len(), 4);
@@ -211,3 +217,248 @@ fn test_write_records() -> Result<(), Box<dyn Error>> {
 
     Ok(())
 }
+
+#[test]
+fn test_map_records_redacts_paths() -> Result<(), Box<dyn Error>> {
+    let mut next_date = IncrementalTimestamp::default();
+    let records = vec![
+        RecordBuilder::event()
+            .info()
+            .target("a")
+            .message("Output base path: /home/user/output")
+            .thread_id("0")
+            .timestamp(next_date.current())
+            .build(),
+        RecordBuilder::event()
+            .info()
+            .target("a")
+            .message("Logging to file /tmp/log.txt now")
+            .thread_id("0")
+            .timestamp(next_date.advance_by(Duration::seconds(1)))
+            .build(),
+        RecordBuilder::event()
+            .info()
+            .target("a")
+            .message("Unrelated message")
+            .thread_id("0")
+            .timestamp(next_date.advance_by(Duration::seconds(1)))
+            .build(),
+    ];
+
+    let mut bytes: Vec<u8> = Vec::new();
+    write_records(&mut bytes, records.into_iter())?;
+
+    let patterns = [("Output base path: ", ""), ("Logging to file ", " now")];
+    let messages: Vec<_> = iterate_records_from_reader(bytes.as_slice())
+        .map_records(|record| redact(record, &patterns, "<redacted>"))
+        .map(|record| record.map(|record| record.message().unwrap().to_string()))
+        .collect::<Result<_, _>>()?;
+    assert_eq!(
+        messages,
+        vec![
+            "Output base path: <redacted>",
+            "Logging to file <redacted> now",
+            "Unrelated message"
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_records_iteration_with_level_and_target_filters() {
+    let log_data = r###"
+        {"timestamp":"2023-03-29T12:48:50.213348Z","level":"TRACE","fields":{"message":"enter"},"target":"dynsys::backward_euler","span":{"name":"assemble"},"spans":[{"name":"run"},{"name":"assemble"}], "threadId": "ThreadId(0)"}
+        {"timestamp":"2023-03-29T12:48:50.213400Z","level":"INFO","fields":{"message":"Starting step"},"target":"dynamecs_app","threadId": "ThreadId(0)"}
+        {"timestamp":"2023-03-29T12:48:50.213500Z","level":"WARN","fields":{"message":"Newton iteration did not converge"},"target":"dynsys::backward_euler","threadId": "ThreadId(0)"}
+        {"timestamp":"2023-03-29T12:48:50.213600Z","level":"DEBUG","fields":{"message":"Residual norm"},"target":"dynsys::backward_euler","threadId": "ThreadId(0)"}
+        {"timestamp":"2023-03-29T12:48:50.213700Z","level":"ERROR","fields":{"message":"Failed to write output"},"target":"dynsys::output","threadId": "ThreadId(0)"}
+        {"timestamp":"2023-03-29T12:48:50.213800Z","level":"INFO","fields":{"message":"Wrote checkpoint"},"target":"dynamecs_app::checkpointing","threadId": "ThreadId(0)"}
+    "###;
+    let collect = |iter: RecordIter| iter.collect::<Result<Vec<_>, AnalyzeError>>().unwrap();
+
+    let records = collect(iterate_records_from_reader(log_data.as_bytes()).with_min_level(Level::Info));
+    let levels: Vec<_> = records.iter().map(Record::level).collect();
+    assert_eq!(levels, [Level::Info, Level::Warn, Level::Error, Level::Info]);
+
+    let records = collect(iterate_records_from_reader(log_data.as_bytes()).with_min_level(Level::Warn));
+    assert_eq!(records.len(), 2);
+
+    let records = collect(iterate_records_from_reader(log_data.as_bytes()).with_target_prefix("dynsys"));
+    assert_eq!(records.len(), 4);
+    assert!(records
+        .iter()
+        .all(|record| record.target().starts_with("dynsys")));
+
+    let records = collect(
+        iterate_records_from_reader(log_data.as_bytes())
+            .with_min_level(Level::Info)
+            .with_target_prefix("dynamecs_app"),
+    );
+    let messages: Vec<_> = records.iter().filter_map(Record::message).collect();
+    assert_eq!(messages, ["Starting step", "Wrote checkpoint"]);
+
+    // Lines that cannot be parsed are still reported when filtering
+    let log_data = r###"{"timestamp":"2023-03-29T12:48:51.440914Z","level":"###;
+    let results: Vec<_> = iterate_records_from_reader(log_data.as_bytes())
+        .with_min_level(Level::Error)
+        .collect();
+    assert!(matches!(results[..], [Err(AnalyzeError::Parse { line: 1, .. })]));
+}
+
+#[test]
+fn test_binary_records_are_smaller_than_json_records() -> Result<(), Box<dyn Error>> {
+    let log_path = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../dynamecs-tool/tests/test_logs/dynamecs_app.jsonlog"
+    );
+    let records: Vec<_> = iterate_records(log_path)?.collect::<Result<_, _>>()?;
+
+    let mut json_bytes: Vec<u8> = Vec::new();
+    write_records(&mut json_bytes, records.clone().into_iter())?;
+    let mut binary_bytes: Vec<u8> = Vec::new();
+    write_binary_records(&mut binary_bytes, records.into_iter())?;
+    assert!(
+        binary_bytes.len() < json_bytes.len(),
+        "binary log has {} bytes, JSON log has {} bytes",
+        binary_bytes.len(),
+        json_bytes.len()
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_binary_records_round_trip() -> Result<(), Box<dyn Error>> {
+    let log_data = r###"
+        {"timestamp":"2023-03-29T12:48:50.213348Z","level":"TRACE","fields":{"message":"enter"},"target":"dynsys::backward_euler","span":{"name":"Newton iteration","k":8},"spans":[{"name":"run"},{"step_index":16,"name":"step"},{"hessian_mod":"NoModification","k":8,"name":"Newton iteration"}], "threadId": "ThreadId(0)"}
+        {"timestamp":"2023-03-29T12:48:50.213400+02:00","level":"INFO","fields":{"message":"Starting step"},"target":"dynamecs_app","threadId": "ThreadId(0)"}
+        {"timestamp":"2023-03-29T12:48:50.213500Z","level":"WARN","fields":{"message":"Newton iteration did not converge","residual":1.5e-3,"converged":false},"target":"dynsys::backward_euler","threadId": "ThreadId(1)"}
+        {"timestamp":"2023-03-29T12:48:50.213600Z","level":"DEBUG","fields":{"message":"exit"},"target":"dynsys::backward_euler","span":{"name":"Newton iteration","k":8},"spans":[{"name":"run"},{"step_index":16,"name":"step"}], "threadId": "ThreadId(0)"}
+    "###;
+    let json_records: Vec<_> = iterate_records_from_reader(log_data.as_bytes()).collect::<Result<_, _>>()?;
+
+    let mut bytes: Vec<u8> = Vec::new();
+    write_binary_records(&mut bytes, json_records.clone().into_iter())?;
+    let binary_records: Vec<_> = iterate_binary_records_from_reader(bytes.as_slice()).collect::<Result<_, _>>()?;
+    assert_eq!(binary_records, json_records);
+
+    // Files with the .reclog extension are read as binary records
+    let temp_dir = tempfile::tempdir()?;
+    let path = temp_dir.path().join("dynamecs_app.reclog");
+    std::fs::write(&path, &bytes)?;
+    let file_records: Vec<_> = iterate_records(&path)?.collect::<Result<_, _>>()?;
+    assert_eq!(file_records, json_records);
+
+    let warnings: Vec<_> = iterate_binary_records_from_reader(bytes.as_slice())
+        .with_min_level(Level::Warn)
+        .collect::<Result<_, _>>()?;
+    assert_eq!(warnings, json_records[2..3]);
+
+    // A truncated record is reported as an IO error
+    let results: Vec<_> = iterate_binary_records_from_reader(&bytes[..bytes.len() - 1]).collect();
+    assert_eq!(results.len(), 4);
+    assert!(results[..3].iter().all(Result::is_ok));
+    assert!(matches!(&results[3], Err(AnalyzeError::Io(err)) if err.kind() == io::ErrorKind::UnexpectedEof));
+
+    Ok(())
+}
+
+#[test]
+fn test_write_event_fields_newton_iterations() -> Result<(), Box<dyn Error>> {
+    let log_data = r###"
+        {"timestamp":"2023-03-29T12:48:50.213348Z","level":"TRACE","fields":{"message":"enter"},"target":"dynsys::backward_euler","span":{"hessian_mod":"NoModification","k":7,"name":"Newton iteration"},"spans":[{"name":"run"},{"step_index":16,"name":"step"},{"hessian_mod":"NoModification","k":7,"name":"Newton iteration"}], "threadId": "ThreadId(0)"}
+        {"timestamp":"2023-03-29T12:48:50.313348Z","level":"DEBUG","fields":{"message":"Residual","residual":0.5},"target":"dynsys::backward_euler","span":{"hessian_mod":"NoModification","k":7,"name":"Newton iteration"},"spans":[{"name":"run"},{"step_index":16,"name":"step"},{"hessian_mod":"NoModification","k":7,"name":"Newton iteration"}], "threadId": "ThreadId(0)"}
+        {"timestamp":"2023-03-29T12:48:50.413348Z","level":"TRACE","fields":{"message":"exit"},"target":"dynsys::backward_euler","span":{"hessian_mod":"NoModification","k":7,"name":"Newton iteration"},"spans":[{"name":"run"},{"step_index":16,"name":"step"}], "threadId": "ThreadId(0)"}
+        {"timestamp":"2023-03-29T12:48:50.513348Z","level":"DEBUG","fields":{"message":"Residual","residual":0.01,"k":8},"target":"dynsys::backward_euler","span":{"hessian_mod":"NoModification","k":7,"name":"Newton iteration"},"spans":[{"name":"run"},{"step_index":16,"name":"step"},{"hessian_mod":"NoModification","k":7,"name":"Newton iteration"}], "threadId": "ThreadId(0)"}
+        {"timestamp":"2023-03-29T12:48:50.613348Z","level":"INFO","fields":{"message":"Converged"},"target":"dynsys::backward_euler","span":{"step_index":16,"name":"step"},"spans":[{"name":"run"},{"step_index":16,"name":"step"}], "threadId": "ThreadId(0)"}
+    "###;
+    let records: Vec<_> = iterate_records_from_reader(log_data.as_bytes()).collect::<Result<_, _>>()?;
+
+    let mut output = Vec::new();
+    write_event_fields(&mut output, records, &["k", "residual", "/hessian_mod"])?;
+    let output = String::from_utf8(output)?;
+
+    // Fields of the event take precedence over fields of enclosing spans
+    let expected = "\
+timestamp\tspan_path\tk\tresidual\t/hessian_mod
+2023-03-29T12:48:50.313348Z\trun>step>Newton iteration\t7\t0.5\tNoModification
+2023-03-29T12:48:50.513348Z\trun>step>Newton iteration\t8\t0.01\tNoModification
+2023-03-29T12:48:50.613348Z\trun>step\t\t\t
+";
+    assert_eq!(output, expected);
+
+    Ok(())
+}
+
+#[test]
+fn test_records_iteration_malformed_json_reports_line_number() {
+    let log_data = r###"{"timestamp":"2023-03-29T12:48:50.213348Z","level":"TRACE","fields":{"message":"enter"},"target":"dynsys","span":{"name":"run"},"spans":[{"name":"run"}], "threadId": "ThreadId(0)"}
+
+{"timestamp":"2023-03-29T12:48:51.440914Z","level":"###;
+    let results: Vec<_> = iterate_records_from_reader(log_data.as_bytes()).collect();
+    assert_eq!(results.len(), 2);
+    assert!(results[0].is_ok());
+    match &results[1] {
+        Err(AnalyzeError::Parse { line, .. }) => assert_eq!(*line, 3),
+        other => panic!("expected parse error, got {other:?}"),
+    }
+}
+
+#[test]
+fn test_records_iteration_io_error() {
+    struct FailingReader;
+
+    impl Read for FailingReader {
+        fn read(&mut self, _buf: &mut [u8]) -> io::Result<usize> {
+            Err(io::Error::other("read failed"))
+        }
+    }
+
+    let mut iter = iterate_records_from_reader(FailingReader);
+    match iter.next() {
+        Some(Err(AnalyzeError::Io(err))) => assert_eq!(err.kind(), io::ErrorKind::Other),
+        other => panic!("expected IO error, got {other:?}"),
+    }
+
+    match iterate_records("this/path/does/not/exist.jsonlog") {
+        Err(AnalyzeError::Io(err)) => assert_eq!(err.kind(), io::ErrorKind::NotFound),
+        Err(err) => panic!("expected IO error, got {err:?}"),
+        Ok(_) => panic!("expected IO error, got records"),
+    }
+}
+
+#[test]
+fn test_record_step_index() {
+    let run = || Span::from_name_and_fields("run", json!({}));
+    let step = || Span::from_name_and_fields("step", json!({ "step_index": 3 }));
+    let solve = || Span::from_name_and_fields("solve", json!({}));
+    let build = |builder: RecordBuilder| {
+        builder
+            .info()
+            .target("target")
+            .timestamp(OffsetDateTime::UNIX_EPOCH)
+            .thread_id("ThreadId(0)")
+            .build()
+    };
+
+    let event_in_step = build(
+        RecordBuilder::event()
+            .message("msg")
+            .span(solve())
+            .spans(vec![run(), step(), solve()]),
+    );
+    assert_eq!(event_in_step.step_index(), Some(3));
+
+    // The exit record of the step span itself does not list the step among its spans
+    let step_exit = build(RecordBuilder::span_exit().span(step()).spans(vec![run()]));
+    assert_eq!(step_exit.step_index(), Some(3));
+
+    let event_outside_step = build(
+        RecordBuilder::event()
+            .message("msg")
+            .span(run())
+            .spans(vec![run()]),
+    );
+    assert_eq!(event_outside_step.step_index(), None);
+}
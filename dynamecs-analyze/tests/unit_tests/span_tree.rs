@@ -1,4 +1,4 @@
-use dynamecs_analyze::{SpanPath, SpanTree};
+use dynamecs_analyze::{SpanPath, SpanTree, StructuralChange};
 
 #[test]
 fn span_tree_valid_trees() -> Result<(), Box<dyn std::error::Error>> {
@@ -93,3 +93,64 @@ fn span_tree_invalid_trees() {
         assert!(SpanTree::try_from_depth_first_ordering(paths, payloads).is_err());
     }
 }
+
+#[test]
+fn span_tree_structural_diff() -> Result<(), Box<dyn std::error::Error>> {
+    let paths = vec![span_path!("a"), span_path!("a", "b"), span_path!("a", "c")];
+    let tree = SpanTree::try_from_depth_first_ordering(paths, vec![1, 2, 3])?;
+
+    let other_paths = vec![
+        span_path!("a"),
+        span_path!("a", "b"),
+        span_path!("a", "b", "d"),
+        span_path!("a", "c"),
+    ];
+    let other = SpanTree::try_from_depth_first_ordering(other_paths, vec!["a", "ab", "abd", "ac"])?;
+
+    assert!(tree.structural_diff(&tree).is_empty());
+    assert_eq!(
+        tree.structural_diff(&other),
+        vec![StructuralChange::Added(span_path!("a", "b", "d"))]
+    );
+    assert_eq!(
+        other.structural_diff(&tree),
+        vec![StructuralChange::Removed(span_path!("a", "b", "d"))]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn span_tree_iter_depth_first() -> Result<(), Box<dyn std::error::Error>> {
+    let paths = vec![
+        span_path!("run"),
+        span_path!("run", "step"),
+        span_path!("run", "step", "assemble"),
+        span_path!("run", "step", "solve"),
+        span_path!("run", "step", "solve", "cg"),
+        span_path!("run", "checkpoint"),
+    ];
+    let tree = SpanTree::try_from_depth_first_ordering(paths.clone(), (0..6).collect())?;
+
+    let items: Vec<_> = tree.iter_depth_first().collect();
+    let depths: Vec<_> = items.iter().map(|&(depth, _, _)| depth).collect();
+    let item_paths: Vec<_> = items.iter().map(|&(_, path, _)| path.clone()).collect();
+    let payloads: Vec<_> = items.iter().map(|&(_, _, &payload)| payload).collect();
+    assert_eq!(depths, vec![0, 1, 2, 2, 3, 1]);
+    assert_eq!(item_paths, paths);
+    assert_eq!(payloads, vec![0, 1, 2, 3, 4, 5]);
+
+    // Depths are relative to the root, even if the root is not a top-level span
+    let subtree_paths = vec![span_path!("run", "step"), span_path!("run", "step", "solve")];
+    let subtree = SpanTree::try_from_depth_first_ordering(subtree_paths, vec![(), ()])?;
+    let depths: Vec<_> = subtree
+        .iter_depth_first()
+        .map(|(depth, _, _)| depth)
+        .collect();
+    assert_eq!(depths, vec![0, 1]);
+
+    let empty = SpanTree::<()>::try_from_depth_first_ordering(vec![], vec![])?;
+    assert_eq!(empty.iter_depth_first().count(), 0);
+
+    Ok(())
+}
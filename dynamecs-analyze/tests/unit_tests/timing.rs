@@ -1,8 +1,19 @@
 use crate::unit_tests::IncrementalTimestamp;
-use dynamecs_analyze::timing::{extract_step_timings, format_timing_tree};
-use dynamecs_analyze::{Record, RecordBuilder, Span};
+use dynamecs_analyze::timing::{
+    extract_single_step_timings, extract_step_timings, extract_step_timings_from_many, extract_step_timings_with_unit,
+    extract_timing_summary, format_timing_tree, format_timing_tree_colored, format_timing_tree_tsv,
+    group_records_by_step, timing_tree_to_json, visit_step_timings, write_chrome_trace, write_folded_stacks,
+    write_timings_csv, ColorChoice, DerivedStats, DurationDistribution, StepUnit, TimingTree, TimingTreeBuilder,
+    TimingTreeJsonNode,
+};
+use dynamecs_analyze::{
+    collect_span_paths, filter_records_in_range, Record, RecordBuilder, RecordKind, Span, SpanPath, SpanTreeNode,
+};
 use serde_json::json;
+use std::cell::Cell;
+use std::collections::BTreeSet;
 use std::error::Error;
+use std::iter;
 use time::Duration;
 
 fn synthetic_records1() -> Vec<Record> {
@@ -235,6 +246,69 @@ fn test_extract_step_timings_synthetic1() -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
+#[test]
+fn test_extract_step_timings_with_custom_frame_unit() -> Result<(), Box<dyn Error>> {
+    let mut next_date = IncrementalTimestamp::default();
+    let run = || Span::from_name_and_fields("run", json!({}));
+    let frame = |i: i64| Span::from_name_and_fields("frame", json!({ "frame_index": i }));
+    let render = || Span::from_name_and_fields("render", json!({}));
+
+    let mut builders = vec![RecordBuilder::span_enter()
+        .timestamp(next_date.current())
+        .span(run())
+        .spans(vec![run()])
+        .target("dynamecs_app")];
+    for frame_index in [5, 6] {
+        builders.extend([
+            RecordBuilder::span_enter()
+                .timestamp(next_date.advance_by(Duration::seconds(1)))
+                .span(frame(frame_index))
+                .spans(vec![run(), frame(frame_index)])
+                .target("my_app"),
+            RecordBuilder::span_enter()
+                .timestamp(next_date.advance_by(Duration::seconds(1)))
+                .span(render())
+                .spans(vec![run(), frame(frame_index), render()])
+                .target("my_app"),
+            RecordBuilder::span_exit()
+                .timestamp(next_date.advance_by(Duration::seconds(2)))
+                .span(render())
+                .spans(vec![run(), frame(frame_index)])
+                .target("my_app"),
+            RecordBuilder::span_exit()
+                .timestamp(next_date.advance_by(Duration::seconds(1)))
+                .span(frame(frame_index))
+                .spans(vec![run()])
+                .target("my_app"),
+        ]);
+    }
+    builders.push(
+        RecordBuilder::span_exit()
+            .timestamp(next_date.advance_by(Duration::seconds(1)))
+            .span(run())
+            .target("dynamecs_app"),
+    );
+    let records: Vec<_> = builders
+        .into_iter()
+        .map(|builder| builder.info().thread_id("ThreadId(0)").build())
+        .collect();
+
+    // The default step unit does not find any steps
+    assert!(extract_step_timings(records.clone())?.steps().is_empty());
+
+    let unit = StepUnit::new("frame", "my_app", "frame_index");
+    let timings = extract_step_timings_with_unit(records, &unit)?;
+    let frame_indices: Vec<_> = timings.steps().iter().map(|step| step.step_index).collect();
+    assert_eq!(frame_indices, [5, 6]);
+    for step in timings.steps() {
+        let tree = step.timings.create_timing_tree();
+        assert_eq!(tree.root().unwrap().path().span_name(), Some("frame"));
+        assert_eq!(tree.root_duration(), Some(std::time::Duration::from_secs(4)));
+    }
+
+    Ok(())
+}
+
 #[test]
 fn test_extract_step_timings_synthetic1_incomplete() -> Result<(), Box<dyn Error>> {
     // Make the test set incomplete by cutting off records somewhere after
@@ -252,3 +326,750 @@ fn test_extract_step_timings_synthetic1_incomplete() -> Result<(), Box<dyn Error
 
     Ok(())
 }
+
+#[test]
+fn test_format_timing_tree_tsv_synthetic1() -> Result<(), Box<dyn Error>> {
+    let records = synthetic_records1();
+    let timings = extract_step_timings(records)?;
+    let summary = timings.summarize().create_timing_tree();
+    insta::assert_snapshot!(format_timing_tree_tsv(&summary));
+    Ok(())
+}
+
+#[test]
+fn test_write_timings_csv_synthetic1() -> Result<(), Box<dyn Error>> {
+    let summary = extract_step_timings(synthetic_records1())?.summarize();
+    let mut output = Vec::new();
+    write_timings_csv(&mut output, &summary)?;
+    let csv = String::from_utf8(output)?;
+    insta::assert_snapshot!(csv);
+    Ok(())
+}
+
+#[test]
+fn test_write_folded_stacks_synthetic1() -> Result<(), Box<dyn Error>> {
+    let summary = extract_step_timings(synthetic_records1())?.summarize();
+    let mut output = Vec::new();
+    write_folded_stacks(&mut output, &summary)?;
+    let folded = String::from_utf8(output)?;
+    let lines: Vec<_> = folded.lines().collect();
+
+    // Leaf spans have no children, so their self time is their total duration
+    assert!(lines.contains(&"run;step;simulate;solve 4000000"));
+    assert!(lines.contains(&"run;step;simulate;assemble 8000000"));
+    // The simulate span takes 18 s, of which 16 s are spent in its children
+    assert!(lines.contains(&"run;step;simulate 2000000"));
+    // The init span has zero duration and is therefore omitted
+    assert!(lines.iter().all(|line| !line.starts_with("run;init")));
+
+    // The self times add up to the total duration of the root
+    let total_micros: u64 = lines
+        .iter()
+        .map(|line| line.rsplit_once(' ').unwrap().1.parse::<u64>().unwrap())
+        .sum();
+    assert_eq!(total_micros, 25_000_000);
+    Ok(())
+}
+
+#[test]
+fn test_timing_tree_to_json_synthetic1() -> Result<(), Box<dyn Error>> {
+    let summary = extract_step_timings(synthetic_records1())?
+        .summarize()
+        .create_timing_tree();
+    let json = timing_tree_to_json(&summary);
+
+    assert_eq!(json["name"], "run");
+    assert_eq!(json["total_ns"], 25_000_000_000u64);
+    assert_eq!(json["count"], 1);
+    let step = &json["children"][1];
+    assert_eq!(step["name"], "step");
+    assert_eq!(step["total_ns"], 23_000_000_000u64);
+    assert_eq!(step["count"], 2);
+    let assemble = &step["children"][0]["children"][0];
+    assert_eq!(assemble["name"], "assemble");
+    assert_eq!(assemble["total_ns"], 8_000_000_000u64);
+    assert_eq!(assemble["count"], 3);
+    assert_eq!(assemble["children"], json!([]));
+
+    // The JSON can be deserialized back into the typed representation
+    let root: TimingTreeJsonNode = serde_json::from_value(json)?;
+    assert_eq!(root.children.len(), 2);
+    assert_eq!(
+        timing_tree_to_json(&TimingTree::try_from_depth_first_ordering(vec![], vec![])?),
+        serde_json::Value::Null
+    );
+    Ok(())
+}
+
+#[test]
+fn test_extract_step_timings_overlapping_spans_with_ids() -> Result<(), Box<dyn Error>> {
+    let mut next_date = IncrementalTimestamp::default();
+    let run = || Span::from_name_and_fields("run", json!({}));
+    let step = || Span::from_name_and_fields("step", json!({ "step_index": 0 }));
+    let request = |id: u64| Span::from_name_and_fields("request", json!({ "span_id": id }));
+    let enter_request = |id: u64, timestamp| {
+        RecordBuilder::span_enter()
+            .info()
+            .timestamp(timestamp)
+            .span(request(id))
+            .spans(vec![run(), step(), request(id)])
+            .target("requests")
+    };
+    let exit_request = |id: u64, timestamp| {
+        RecordBuilder::span_exit()
+            .info()
+            .timestamp(timestamp)
+            .span(request(id))
+            .spans(vec![run(), step()])
+            .target("requests")
+    };
+
+    let records: Vec<_> = vec![
+        RecordBuilder::span_enter()
+            .info()
+            .timestamp(next_date.current())
+            .span(run())
+            .spans(vec![run()])
+            .target("dynamecs_app"),
+        RecordBuilder::span_enter()
+            .info()
+            .timestamp(next_date.current())
+            .span(step())
+            .spans(vec![run(), step()])
+            .target("dynamecs_app"),
+        // The two requests overlap in time, but are distinguished by their span ids
+        enter_request(1, next_date.advance_by(Duration::seconds(1))),
+        enter_request(2, next_date.advance_by(Duration::seconds(1))),
+        exit_request(1, next_date.advance_by(Duration::seconds(2))),
+        exit_request(2, next_date.advance_by(Duration::seconds(3))),
+        RecordBuilder::span_exit()
+            .info()
+            .timestamp(next_date.advance_by(Duration::seconds(1)))
+            .span(step())
+            .spans(vec![run()])
+            .target("dynamecs_app"),
+        RecordBuilder::span_exit()
+            .info()
+            .timestamp(next_date.current())
+            .span(run())
+            .spans(vec![])
+            .target("dynamecs_app"),
+    ]
+    .into_iter()
+    .map(|builder| builder.thread_id("ThreadId(0)").build())
+    .collect();
+
+    let timings = extract_step_timings(records)?;
+    assert_eq!(timings.steps().len(), 1);
+    let tree = timings.steps()[0].timings.create_timing_tree();
+    let (_, _, request_stats) = tree
+        .iter_depth_first()
+        .find(|(_, path, _)| path.span_name() == Some("request"))
+        .expect("request span must be present");
+    let request_stats = request_stats.as_ref().unwrap();
+    // Request 1 takes 3 seconds and request 2 takes 5 seconds
+    assert_eq!(request_stats.count, 2);
+    assert_eq!(request_stats.duration, std::time::Duration::from_secs(8));
+    assert_eq!(tree.root_duration(), Some(std::time::Duration::from_secs(8)));
+
+    // Without ids, the second activation of the same span path is an error
+    let request = || Span::from_name_and_fields("request", json!({}));
+    let records: Vec<_> = vec![
+        RecordBuilder::span_enter()
+            .info()
+            .timestamp(next_date.current())
+            .span(run())
+            .spans(vec![run()])
+            .target("dynamecs_app"),
+        RecordBuilder::span_enter()
+            .info()
+            .timestamp(next_date.current())
+            .span(request())
+            .spans(vec![run(), request()])
+            .target("requests"),
+        RecordBuilder::span_enter()
+            .info()
+            .timestamp(next_date.current())
+            .span(request())
+            .spans(vec![run(), request()])
+            .target("requests"),
+    ]
+    .into_iter()
+    .map(|builder| builder.thread_id("ThreadId(0)").build())
+    .collect();
+    assert!(extract_step_timings(records).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_extract_step_timings_multiple_threads() -> Result<(), Box<dyn Error>> {
+    let mut next_date = IncrementalTimestamp::default();
+    let run = || Span::from_name_and_fields("run", json!({}));
+    let step = || Span::from_name_and_fields("step", json!({ "step_index": 0 }));
+    let simulate = || Span::from_name_and_fields("simulate", json!({}));
+    let chunk = || Span::from_name_and_fields("chunk", json!({}));
+    let reduce = || Span::from_name_and_fields("reduce", json!({}));
+    let background = || Span::from_name_and_fields("background", json!({}));
+    let enter = |span: Span, spans: Vec<Span>, timestamp, thread_id: &str| {
+        RecordBuilder::span_enter()
+            .info()
+            .timestamp(timestamp)
+            .span(span)
+            .spans(spans)
+            .target("dynamecs_app")
+            .thread_id(thread_id)
+    };
+    let exit = |span: Span, spans: Vec<Span>, timestamp, thread_id: &str| {
+        RecordBuilder::span_exit()
+            .info()
+            .timestamp(timestamp)
+            .span(span)
+            .spans(spans)
+            .target("dynamecs_app")
+            .thread_id(thread_id)
+    };
+    let main = "ThreadId(0)";
+    let worker = "ThreadId(1)";
+
+    let records: Vec<_> = vec![
+        enter(run(), vec![run()], next_date.current(), main),
+        enter(step(), vec![run(), step()], next_date.current(), main),
+        enter(simulate(), vec![run(), step(), simulate()], next_date.current(), main),
+        // Both threads work on a chunk at the same time. Since the spans are attributed to their
+        // own thread, the identical span paths do not conflict
+        enter(
+            chunk(),
+            vec![run(), step(), simulate(), chunk()],
+            next_date.advance_by(Duration::seconds(1)),
+            main,
+        ),
+        enter(
+            chunk(),
+            vec![run(), step(), simulate(), chunk()],
+            next_date.current(),
+            worker,
+        ),
+        // Spans on other threads that are not nested inside the run span are ignored
+        enter(background(), vec![background()], next_date.current(), "ThreadId(2)"),
+        exit(
+            chunk(),
+            vec![run(), step(), simulate()],
+            next_date.advance_by(Duration::seconds(2)),
+            main,
+        ),
+        exit(
+            chunk(),
+            vec![run(), step(), simulate()],
+            next_date.advance_by(Duration::seconds(1)),
+            worker,
+        ),
+        enter(
+            reduce(),
+            vec![run(), step(), simulate(), reduce()],
+            next_date.current(),
+            worker,
+        ),
+        exit(
+            reduce(),
+            vec![run(), step(), simulate()],
+            next_date.advance_by(Duration::seconds(1)),
+            worker,
+        ),
+        exit(simulate(), vec![run(), step()], next_date.current(), main),
+        exit(step(), vec![run()], next_date.current(), main),
+        exit(background(), vec![], next_date.current(), "ThreadId(2)"),
+        exit(run(), vec![], next_date.current(), main),
+    ]
+    .into_iter()
+    .map(|builder| builder.build())
+    .collect();
+
+    let timings = extract_step_timings(records)?;
+    assert_eq!(timings.steps().len(), 1);
+    let tree = timings.steps()[0].timings.create_timing_tree();
+    let stats = |path: SpanPath| {
+        tree.iter_depth_first()
+            .find(|(_, node_path, _)| *node_path == &path)
+            .and_then(|(_, _, stats)| stats.clone())
+            .expect("span must be present with statistics")
+    };
+    let seconds = std::time::Duration::from_secs;
+
+    assert_eq!(stats(span_path!("run", "step")).duration, seconds(5));
+    assert_eq!(stats(span_path!("run", "step", "simulate")).duration, seconds(5));
+    // The chunk takes 2 seconds on the main thread and 3 seconds on the worker thread
+    let chunk_stats = stats(span_path!("run", "step", "simulate", "chunk"));
+    assert_eq!(chunk_stats.count, 2);
+    assert_eq!(chunk_stats.duration, seconds(5));
+    let reduce_stats = stats(span_path!("run", "step", "simulate", "reduce"));
+    assert_eq!(reduce_stats.count, 1);
+    assert_eq!(reduce_stats.duration, seconds(1));
+    // Children of simulate run in parallel and take longer than simulate itself
+    assert_eq!(
+        stats(span_path!("run", "step", "simulate")).self_duration,
+        Some(seconds(0))
+    );
+    assert!(tree
+        .iter_depth_first()
+        .all(|(_, path, _)| path.span_name() != Some("background")));
+
+    Ok(())
+}
+
+#[test]
+fn test_extract_step_timings_from_many_prefers_later_steps() -> Result<(), Box<dyn Error>> {
+    // The first log has three steps, after which steps 0 and 1 are re-run in the second log
+    let num_produced = Cell::new(0);
+    let timings = extract_step_timings_from_many([
+        generate_step_records(3, &num_produced).collect::<Vec<_>>(),
+        synthetic_records1(),
+    ])?;
+
+    let step_indices: Vec<_> = timings.steps().iter().map(|step| step.step_index).collect();
+    assert_eq!(step_indices, [0, 1, 2]);
+    let simulate = span_path!("run", "step", "simulate");
+    let seconds = std::time::Duration::from_secs;
+    assert_eq!(
+        timings.per_step_span_durations(&simulate),
+        [
+            (0, seconds(6)),
+            (1, seconds(12)),
+            (2, std::time::Duration::from_millis(1))
+        ]
+    );
+
+    // The intransient timings of both logs are combined
+    let summary = timings.summarize().create_timing_tree();
+    assert_eq!(summary.total_count(), Some(2));
+    let init_stats = summary
+        .iter_depth_first()
+        .find(|(_, path, _)| path.span_name() == Some("init"))
+        .and_then(|(_, _, stats)| stats.clone())
+        .expect("init span must be present");
+    assert_eq!(init_stats.count, 1);
+
+    Ok(())
+}
+
+#[test]
+fn test_per_step_span_durations_synthetic1() -> Result<(), Box<dyn Error>> {
+    let timings = extract_step_timings(synthetic_records1())?;
+    let seconds = std::time::Duration::from_secs;
+
+    let simulate = span_path!("run", "step", "simulate");
+    assert_eq!(
+        timings.per_step_span_durations(&simulate),
+        vec![(0, seconds(6)), (1, seconds(12))]
+    );
+    let distribution = timings.span_duration_distribution(&simulate).unwrap();
+    assert_eq!(
+        distribution,
+        DurationDistribution {
+            count: 2,
+            min: seconds(6),
+            max: seconds(12),
+            mean: seconds(9),
+            median: seconds(9),
+            p95: seconds(12),
+        }
+    );
+
+    // The occasional span is only present in one of the steps
+    let occasional = span_path!("run", "step", "simulate", "occasional");
+    assert_eq!(timings.per_step_span_durations(&occasional), vec![(1, seconds(4))]);
+    let missing = span_path!("run", "step", "missing");
+    assert!(timings.per_step_span_durations(&missing).is_empty());
+    assert_eq!(timings.span_duration_distribution(&missing), None);
+
+    let distribution = DurationDistribution::from_durations((1..=20).rev().map(seconds).collect()).unwrap();
+    assert_eq!(distribution.min, seconds(1));
+    assert_eq!(distribution.max, seconds(20));
+    assert_eq!(distribution.median, std::time::Duration::from_millis(10_500));
+    assert_eq!(distribution.p95, seconds(19));
+
+    Ok(())
+}
+
+#[test]
+fn test_timing_tree_builder_two_levels() {
+    let seconds = std::time::Duration::from_secs;
+    let tree = TimingTreeBuilder::new()
+        .add_span(span_path!("run"), seconds(10), 1)
+        .add_span(span_path!("run", "step"), seconds(9), 3)
+        .add_span(span_path!("run", "step", "assemble"), seconds(4), 3)
+        .add_span(span_path!("run", "step", "solve"), seconds(3), 3)
+        .build();
+    assert_eq!(tree.root_duration(), Some(seconds(10)));
+    insta::assert_snapshot!(format_timing_tree(&tree));
+}
+
+#[test]
+fn test_timing_tree_root_accessors_synthetic1() -> Result<(), Box<dyn Error>> {
+    let records = synthetic_records1();
+
+    // Compute the duration of the run span directly from the records
+    let run_timestamp = |kind| {
+        records
+            .iter()
+            .find(|record| record.kind() == kind && record.span().map(|span| span.name()) == Some("run"))
+            .map(|record| *record.timestamp())
+            .unwrap()
+    };
+    let run_duration = (run_timestamp(RecordKind::SpanExit) - run_timestamp(RecordKind::SpanEnter)).unsigned_abs();
+
+    let timings = extract_step_timings(records.clone())?;
+    let summary = timings.summarize().create_timing_tree();
+    assert_eq!(summary.root_duration(), Some(run_duration));
+    assert_eq!(summary.total_count(), Some(1));
+
+    let step_trees: Vec<_> = timings
+        .steps()
+        .iter()
+        .map(|step| step.timings.create_timing_tree())
+        .collect();
+    let step_durations_sum: std::time::Duration = step_trees
+        .iter()
+        .map(|tree| tree.root_duration().unwrap())
+        .sum();
+    let summary_step_duration = summary
+        .root()
+        .unwrap()
+        .visit_children()
+        .find(|child| child.path().span_name() == Some("step"))
+        .and_then(|step| step.payload().as_ref().map(|stats| stats.duration));
+    assert_eq!(summary_step_duration, Some(step_durations_sum));
+    assert!(step_trees.iter().all(|tree| tree.total_count() == Some(1)));
+
+    assert_eq!(
+        TimingTree::try_from_depth_first_ordering(vec![], vec![])?.root_duration(),
+        None
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_timing_tree_node_self_duration_synthetic1() -> Result<(), Box<dyn Error>> {
+    let timings = extract_step_timings(synthetic_records1())?;
+    let summary = timings.summarize().create_timing_tree();
+
+    fn check_self_duration(node: SpanTreeNode<Option<DerivedStats>>, num_checked: &mut usize) {
+        let duration = node.payload().as_ref().unwrap().duration;
+        let children_duration: std::time::Duration = node
+            .visit_children()
+            .map(|child| child.payload().as_ref().unwrap().duration)
+            .sum();
+        assert_eq!(node.self_duration(), Some(duration - children_duration));
+        assert_eq!(node.self_duration(), node.payload().as_ref().unwrap().self_duration);
+        *num_checked += 1;
+        for child in node.visit_children() {
+            check_self_duration(child, num_checked);
+        }
+    }
+
+    let mut num_checked = 0;
+    check_self_duration(summary.root().unwrap(), &mut num_checked);
+    assert!(num_checked > 1);
+
+    Ok(())
+}
+
+#[test]
+fn test_to_duration_tree_synthetic1() -> Result<(), Box<dyn Error>> {
+    let summary = extract_step_timings(synthetic_records1())?.summarize();
+    let duration_tree = summary.to_duration_tree();
+
+    let seconds = std::time::Duration::from_secs;
+    let durations: Vec<_> = duration_tree
+        .iter_depth_first()
+        .map(|(depth, path, &duration)| (depth, path.clone(), duration))
+        .collect();
+    // init is entered and exited at the same timestamp
+    let expected = vec![
+        (0, span_path!("run"), seconds(25)),
+        (1, span_path!("run", "init"), seconds(0)),
+        (1, span_path!("run", "step"), seconds(23)),
+        (2, span_path!("run", "step", "simulate"), seconds(18)),
+        (3, span_path!("run", "step", "simulate", "assemble"), seconds(8)),
+        (3, span_path!("run", "step", "simulate", "occasional"), seconds(4)),
+        (3, span_path!("run", "step", "simulate", "solve"), seconds(4)),
+    ];
+    assert_eq!(durations, expected);
+
+    Ok(())
+}
+
+#[test]
+fn test_format_timing_tree_colored_synthetic1() -> Result<(), Box<dyn Error>> {
+    let timings = extract_step_timings(synthetic_records1())?;
+    let summary = timings.summarize().create_timing_tree();
+
+    let colored = format_timing_tree_colored(&summary, ColorChoice::Always);
+    // run (100 %) and step (92 %) are red, assemble (32 %) is yellow
+    assert!(colored.contains("\x1b[31m"));
+    assert!(colored.contains("\x1b[33m"));
+    assert!(colored.contains("\x1b[0m"));
+    // The structure is unchanged once color codes are stripped
+    let stripped = colored
+        .replace("\x1b[31m", "")
+        .replace("\x1b[33m", "")
+        .replace("\x1b[0m", "");
+    assert_eq!(stripped, format_timing_tree(&summary));
+
+    let uncolored = format_timing_tree_colored(&summary, ColorChoice::Never);
+    assert!(!uncolored.contains('\x1b'));
+    assert_eq!(uncolored, format_timing_tree(&summary));
+
+    Ok(())
+}
+
+#[test]
+fn test_group_records_by_step_synthetic1() {
+    let groups: Vec<_> = group_records_by_step(synthetic_records1()).collect();
+    assert_eq!(groups.len(), 2);
+
+    let (index0, records0) = &groups[0];
+    assert_eq!(*index0, 0);
+    assert_eq!(records0.len(), 9);
+    assert_eq!(records0.first().unwrap().kind(), RecordKind::SpanEnter);
+    assert_eq!(records0.last().unwrap().kind(), RecordKind::SpanExit);
+
+    let (index1, records1) = &groups[1];
+    assert_eq!(*index1, 1);
+    assert_eq!(records1.len(), 13);
+    assert!(records1.iter().all(|record| record.step_index() == Some(1)));
+}
+
+#[test]
+fn test_filter_records_in_range_synthetic1() -> Result<(), Box<dyn Error>> {
+    let records = synthetic_records1();
+    let step_enter_timestamp = |step_index| {
+        records
+            .iter()
+            .find(|record| record.kind() == RecordKind::SpanEnter && record.step_index() == Some(step_index))
+            .map(|record| *record.timestamp())
+            .unwrap()
+    };
+    let start = step_enter_timestamp(1);
+    let end = *records.last().unwrap().timestamp();
+
+    let filtered: Vec<_> = filter_records_in_range(records.clone(), start, end).collect();
+    assert!(filtered
+        .iter()
+        .all(|record| (start..=end).contains(record.timestamp())));
+
+    let steps = group_records_by_step(filtered)
+        .map(|(_, step_records)| extract_single_step_timings(step_records))
+        .collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(steps.len(), 1);
+    let step = steps[0].as_ref().expect("step 1 must be complete");
+    assert_eq!(step.step_index, 1);
+
+    let expected = extract_step_timings(synthetic_records1())?.steps()[1]
+        .timings
+        .create_timing_tree();
+    assert_eq!(
+        format_timing_tree(&step.timings.create_timing_tree()),
+        format_timing_tree(&expected)
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_collect_span_paths_synthetic1() {
+    let paths = collect_span_paths(synthetic_records1());
+    let expected: BTreeSet<_> = [
+        span_path!("run"),
+        span_path!("run", "init"),
+        span_path!("run", "step"),
+        span_path!("run", "step", "simulate"),
+        span_path!("run", "step", "simulate", "assemble"),
+        span_path!("run", "step", "simulate", "solve"),
+        span_path!("run", "step", "simulate", "occasional"),
+    ]
+    .into_iter()
+    .collect();
+    assert_eq!(paths, expected);
+}
+
+#[test]
+fn test_filter_by_min_count_synthetic1() -> Result<(), Box<dyn Error>> {
+    let timings = extract_step_timings(synthetic_records1())?;
+    let summary = timings.summarize();
+    let filtered = summary.filter_by_min_count(2).create_timing_tree();
+
+    fn collect_span_names(node: SpanTreeNode<Option<DerivedStats>>, names: &mut Vec<String>) {
+        names.push(node.path().span_name().unwrap().to_string());
+        for child in node.visit_children() {
+            collect_span_names(child, names);
+        }
+    }
+
+    // The root span `run` is retained even though it was only completed once
+    let mut names = Vec::new();
+    collect_span_names(filtered.root().unwrap(), &mut names);
+    assert_eq!(names, vec!["run", "step", "simulate", "assemble", "solve"]);
+
+    // Totals are preserved, and the time of the removed `occasional` span is accounted for
+    // in the self time of `simulate`
+    assert_eq!(filtered.root_duration(), summary.create_timing_tree().root_duration());
+    let simulate = filtered
+        .root()
+        .unwrap()
+        .visit_children()
+        .next()
+        .unwrap()
+        .visit_children()
+        .next()
+        .unwrap();
+    assert_eq!(simulate.path().span_name(), Some("simulate"));
+    assert_eq!(simulate.payload().as_ref().unwrap().duration.as_secs(), 18);
+    assert_eq!(simulate.self_duration().unwrap().as_secs(), 6);
+
+    Ok(())
+}
+
+#[test]
+fn test_write_chrome_trace_synthetic1() -> Result<(), Box<dyn Error>> {
+    let mut output = Vec::new();
+    write_chrome_trace(&mut output, synthetic_records1())?;
+    let trace: serde_json::Value = serde_json::from_slice(&output)?;
+
+    let events: Vec<_> = trace["traceEvents"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|event| {
+            (
+                event["ph"].as_str().unwrap().to_string(),
+                event["name"].as_str().unwrap().to_string(),
+                event["ts"].as_i64().unwrap(),
+            )
+        })
+        .collect();
+    let event = |ph: &str, name: &str, seconds: i64| (ph.to_string(), name.to_string(), seconds * 1_000_000);
+    let expected = vec![
+        event("B", "run", 0),
+        event("B", "init", 0),
+        event("E", "init", 0),
+        event("B", "step", 1),
+        event("B", "simulate", 3),
+        event("B", "assemble", 3),
+        event("E", "assemble", 6),
+        event("B", "solve", 6),
+        event("E", "solve", 8),
+        event("E", "simulate", 9),
+        event("E", "step", 9),
+        event("B", "step", 9),
+        event("B", "simulate", 12),
+        event("B", "assemble", 12),
+        event("E", "assemble", 14),
+        event("B", "assemble", 14),
+        event("E", "assemble", 17),
+        event("B", "solve", 17),
+        event("E", "solve", 19),
+        event("B", "occasional", 19),
+        event("E", "occasional", 23),
+        event("E", "simulate", 24),
+        event("E", "step", 24),
+        event("E", "run", 25),
+    ];
+    assert_eq!(events, expected);
+
+    let first_step = &trace["traceEvents"][3];
+    assert_eq!(first_step["args"]["step_index"], 0);
+    assert_eq!(first_step["cat"], "dynamecs_app");
+    assert_eq!(first_step["pid"], 1);
+    assert_eq!(first_step["tid"], 0);
+    Ok(())
+}
+
+/// Lazily generates the records of a run with the given number of steps, each with a nested span.
+///
+/// The number of records produced so far is tracked in `num_produced`.
+fn generate_step_records(num_steps: u64, num_produced: &Cell<u64>) -> impl Iterator<Item = Record> + '_ {
+    let mut next_date = IncrementalTimestamp::default();
+    let obj = serde_json::Value::Object(Default::default());
+    let run = Span::from_name_and_fields("run", obj.clone());
+    let simulate = Span::from_name_and_fields("simulate", obj);
+
+    let run_enter = RecordBuilder::span_enter()
+        .span(run.clone())
+        .spans(vec![run.clone()]);
+    let run_exit = RecordBuilder::span_exit().span(run.clone()).spans(vec![]);
+    let steps = (0..num_steps).flat_map(move |i| {
+        let step = Span::from_name_and_fields("step", json!({ "step_index": i }));
+        let step_spans = vec![run.clone(), step.clone()];
+        let simulate_spans = vec![run.clone(), step.clone(), simulate.clone()];
+        [
+            RecordBuilder::span_enter()
+                .span(step.clone())
+                .spans(step_spans.clone()),
+            RecordBuilder::span_enter()
+                .span(simulate.clone())
+                .spans(simulate_spans),
+            RecordBuilder::span_exit()
+                .span(simulate.clone())
+                .spans(step_spans),
+            RecordBuilder::span_exit()
+                .span(step)
+                .spans(vec![run.clone()]),
+        ]
+    });
+
+    iter::once(run_enter)
+        .chain(steps)
+        .chain(iter::once(run_exit))
+        .map(move |builder| {
+            num_produced.set(num_produced.get() + 1);
+            builder
+                .info()
+                .target("dynamecs_app")
+                .thread_id("ThreadId(0)")
+                .timestamp(next_date.advance_by(Duration::milliseconds(1)))
+                .build()
+        })
+}
+
+#[test]
+fn test_visit_step_timings_streams_records() -> Result<(), Box<dyn Error>> {
+    let num_steps = 20_000;
+    let num_produced = Cell::new(0);
+    let mut num_visited = 0;
+    let intransient_timings = visit_step_timings(
+        generate_step_records(num_steps, &num_produced),
+        &StepUnit::default(),
+        |step| {
+            assert_eq!(step.step_index, num_visited);
+            // Each step must be visited as soon as its records have been read: the run enter record
+            // followed by four records per step
+            assert_eq!(num_produced.get(), 1 + 4 * (num_visited + 1));
+            num_visited += 1;
+        },
+    )?;
+    assert_eq!(num_visited, num_steps);
+    assert_eq!(num_produced.get(), 4 * num_steps + 2);
+
+    let run_stats = intransient_timings.create_timing_tree();
+    let root = run_stats.root().unwrap();
+    assert_eq!(root.path(), span_path!("run"));
+    assert_eq!(root.payload().as_ref().unwrap().count, 1);
+    Ok(())
+}
+
+#[test]
+fn test_extract_timing_summary_streams_records() -> Result<(), Box<dyn Error>> {
+    let num_steps = 20_000;
+    let num_produced = Cell::new(0);
+    let summary = extract_timing_summary(generate_step_records(num_steps, &num_produced))?;
+    let tree = summary.create_timing_tree();
+    let step = tree.root().unwrap().visit_children().next().unwrap();
+    assert_eq!(step.path(), span_path!("run", "step"));
+    assert_eq!(step.payload().as_ref().unwrap().count, num_steps);
+    let simulate = step.visit_children().next().unwrap();
+    assert_eq!(simulate.path(), span_path!("run", "step", "simulate"));
+    assert_eq!(simulate.payload().as_ref().unwrap().count, num_steps);
+    Ok(())
+}
@@ -0,0 +1,58 @@
+use crate::unit_tests::IncrementalTimestamp;
+use dynamecs_analyze::timing::TimingTreeBuilder;
+use dynamecs_analyze::timing_history::{
+    append_timing_history, group_timing_history_by_commit, read_timing_history, TimingHistoryEntry, TimingHistorySpan,
+};
+use dynamecs_analyze::SpanPath;
+use std::error::Error;
+use std::time::Duration;
+
+fn entry_with_step_duration(commit: &str, timestamp: time::OffsetDateTime, step_millis: u64) -> TimingHistoryEntry {
+    let tree = TimingTreeBuilder::new()
+        .add_span(span_path!("run"), Duration::from_millis(step_millis + 100), 1)
+        .add_span(span_path!("run", "step"), Duration::from_millis(step_millis), 2)
+        .build();
+    TimingHistoryEntry::from_timing_tree(commit, timestamp, &tree)
+}
+
+#[test]
+fn test_timing_history_roundtrip_grouped_by_commit() -> Result<(), Box<dyn Error>> {
+    let mut next_date = IncrementalTimestamp::default();
+    let entries = [
+        entry_with_step_duration("abc123", next_date.current(), 500),
+        entry_with_step_duration("def456", next_date.advance_by(time::Duration::hours(1)), 400),
+        entry_with_step_duration("abc123", next_date.advance_by(time::Duration::hours(1)), 520),
+    ];
+    assert_eq!(
+        entries[0].spans,
+        [
+            TimingHistorySpan {
+                path: "run".to_string(),
+                total_ns: 600_000_000,
+                count: 1,
+            },
+            TimingHistorySpan {
+                path: "run>step".to_string(),
+                total_ns: 500_000_000,
+                count: 2,
+            },
+        ]
+    );
+
+    let mut history = Vec::new();
+    for entry in &entries {
+        append_timing_history(&mut history, entry)?;
+    }
+    assert_eq!(String::from_utf8(history.clone())?.lines().count(), 3);
+
+    let read_entries = read_timing_history(history.as_slice())?;
+    assert_eq!(read_entries, entries);
+
+    let groups = group_timing_history_by_commit(read_entries);
+    let commits: Vec<_> = groups.iter().map(|(commit, _)| commit.as_str()).collect();
+    assert_eq!(commits, ["abc123", "def456"]);
+    let (_, abc_entries) = &groups[0];
+    assert_eq!(abc_entries, &[entries[0].clone(), entries[2].clone()]);
+
+    Ok(())
+}
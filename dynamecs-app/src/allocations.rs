@@ -0,0 +1,175 @@
+//! Counting of heap allocations, for finding systems that allocate excessively in the simulation loop.
+//!
+//! Allocations are only counted if the application opts in by installing a [`CountingAllocator`]
+//! as the global allocator:
+//!
+//! ```ignore
+//! use dynamecs_app::allocations::CountingAllocator;
+//!
+//! #[global_allocator]
+//! static ALLOCATOR: CountingAllocator = CountingAllocator::system();
+//! ```
+//!
+//! The per-step allocations can then be logged by adding an [`allocation_observer`] to the post systems.
+use dynamecs::components::get_step_index;
+use dynamecs::{ObserverSystem, Universe};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicU64, Ordering};
+use tracing::info;
+
+static ALLOCATION_COUNT: AtomicU64 = AtomicU64::new(0);
+static ALLOCATED_BYTES: AtomicU64 = AtomicU64::new(0);
+
+/// A global allocator that counts the allocations made through the wrapped allocator.
+///
+/// Reallocations are counted as allocations of the new size. Deallocations are not tracked.
+#[derive(Debug, Default)]
+pub struct CountingAllocator<A = System> {
+    allocator: A,
+}
+
+impl CountingAllocator<System> {
+    /// Wraps the system allocator.
+    pub const fn system() -> Self {
+        Self::new(System)
+    }
+}
+
+impl<A> CountingAllocator<A> {
+    pub const fn new(allocator: A) -> Self {
+        Self { allocator }
+    }
+
+    fn count(size: usize) {
+        ALLOCATION_COUNT.fetch_add(1, Ordering::Relaxed);
+        ALLOCATED_BYTES.fetch_add(size as u64, Ordering::Relaxed);
+    }
+}
+
+unsafe impl<A: GlobalAlloc> GlobalAlloc for CountingAllocator<A> {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        Self::count(layout.size());
+        self.allocator.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        self.allocator.dealloc(ptr, layout)
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        Self::count(layout.size());
+        self.allocator.alloc_zeroed(layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        Self::count(new_size);
+        self.allocator.realloc(ptr, layout, new_size)
+    }
+}
+
+/// The number of allocations and allocated bytes counted by [`CountingAllocator`].
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct AllocationStats {
+    pub count: u64,
+    pub bytes: u64,
+}
+
+impl AllocationStats {
+    /// The allocations counted since `earlier`.
+    pub fn since(&self, earlier: &AllocationStats) -> AllocationStats {
+        AllocationStats {
+            count: self.count - earlier.count,
+            bytes: self.bytes - earlier.bytes,
+        }
+    }
+}
+
+/// Returns the allocations counted so far by all threads.
+///
+/// The counts remain zero unless a [`CountingAllocator`] is installed as the global allocator.
+pub fn allocation_stats() -> AllocationStats {
+    AllocationStats {
+        count: ALLOCATION_COUNT.load(Ordering::Relaxed),
+        bytes: ALLOCATED_BYTES.load(Ordering::Relaxed),
+    }
+}
+
+/// Returns an observer that logs the number of allocations and allocated bytes since its previous run.
+///
+/// When added to the post systems, this reports the allocations made in each step. The first run
+/// reports the allocations made since the observer was created.
+pub fn allocation_observer() -> impl ObserverSystem {
+    AllocationObserver {
+        previous_stats: allocation_stats(),
+    }
+}
+
+#[derive(Debug)]
+struct AllocationObserver {
+    previous_stats: AllocationStats,
+}
+
+impl ObserverSystem for AllocationObserver {
+    fn name(&self) -> String {
+        "AllocationObserver".to_string()
+    }
+
+    fn run(&mut self, universe: &Universe) -> eyre::Result<()> {
+        let stats = allocation_stats();
+        let AllocationStats { count, bytes } = stats.since(&self.previous_stats);
+        self.previous_stats = stats;
+        let step_index = get_step_index(universe).0;
+        info!(
+            step_index,
+            allocations = count,
+            allocated_bytes = bytes,
+            "Step {step_index} made {count} allocations ({bytes} bytes)"
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{allocation_observer, allocation_stats, CountingAllocator};
+    use crate::record_channel_layer;
+    use dynamecs::{ObserverSystem, Universe};
+    use std::hint::black_box;
+    use std::sync::mpsc::channel;
+    use tracing_subscriber::prelude::*;
+    use tracing_subscriber::Registry;
+
+    #[global_allocator]
+    static ALLOCATOR: CountingAllocator = CountingAllocator::system();
+
+    #[test]
+    fn allocations_are_counted_and_reported_per_step() {
+        let before = allocation_stats();
+        let buffer = black_box(Vec::<u8>::with_capacity(1000));
+        let after = allocation_stats().since(&before);
+        assert!(after.count >= 1);
+        assert!(after.bytes >= 1000);
+        drop(buffer);
+
+        let universe = Universe::default();
+        let mut observer = allocation_observer();
+        let (sender, receiver) = channel();
+        let subscriber = Registry::default().with(record_channel_layer(sender));
+        tracing::subscriber::with_default(subscriber, || -> eyre::Result<()> {
+            observer.run(&universe)?;
+            let buffer = black_box(vec![0u64; 1000]);
+            observer.run(&universe)?;
+            drop(buffer);
+            Ok(())
+        })
+        .unwrap();
+
+        let reports: Vec<_> = receiver
+            .try_iter()
+            .filter(|record| record.fields().get("allocations").is_some())
+            .collect();
+        assert_eq!(reports.len(), 2);
+        let bytes = reports[1].fields()["allocated_bytes"].as_u64().unwrap();
+        assert!(bytes >= 8000, "expected at least 8000 allocated bytes, got {bytes}");
+    }
+}
@@ -1,13 +1,18 @@
 use eyre::eyre;
 use eyre::Context;
+use std::collections::{BTreeSet, VecDeque};
 use std::ffi::OsStr;
 use std::fmt::Debug;
-use std::path::Path;
-use std::{fmt, fs};
-use tracing::info;
+use std::io::{BufRead, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::{fmt, fs, thread};
+use tracing::{debug, info, warn};
 
-use dynamecs::components::{get_step_index, try_get_settings};
-use dynamecs::{ObserverSystem, Universe};
+use dynamecs::components::{get_step_index, try_get_settings, StepIndex};
+use dynamecs::storages::SingularStorage;
+use dynamecs::{ObserverSystem, System, Universe};
 
 /// Tries to deserialize a [`dynamecs::Universe`] from the specified file path.
 ///
@@ -28,6 +33,7 @@ pub fn restore_checkpoint_file<P: AsRef<Path>>(checkpoint_path: P) -> eyre::Resu
     // Call the right deserializer depending on the file extension
     match extension.to_lowercase().as_str() {
         "bin" => restore_compressed_binary_checkpoint_file(checkpoint_path),
+        "json" => restore_json_checkpoint_file(checkpoint_path),
         _ => {
             return Err(eyre!(
                 "Unsupported file extension \"{}\" of checkpoint file \"{}\"",
@@ -44,101 +50,1014 @@ pub fn restore_checkpoint_file<P: AsRef<Path>>(checkpoint_path: P) -> eyre::Resu
     })
 }
 
+/// The stream identifier chunk that starts every snap frame stream.
+const SNAP_MAGIC: &[u8] = b"\xff\x06\x00\x00sNaPpY";
+/// The magic number that starts every zstd frame.
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+
+/// The file format of checkpoints written during the simulation.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum CheckpointFormat {
+    /// Serialized with `bincode` and compressed with `snap`, stored in `.bin` files.
+    #[default]
+    CompressedBinary,
+    /// Human-readable, pretty-printed JSON without compression, stored in `.json` files.
+    Json,
+}
+
+impl CheckpointFormat {
+    /// The file extension of checkpoint files in this format.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            CheckpointFormat::CompressedBinary => "bin",
+            CheckpointFormat::Json => "json",
+        }
+    }
+
+    /// The function that serializes checkpoints in this format.
+    fn serializer(&self) -> CheckpointSerializer {
+        match self {
+            CheckpointFormat::CompressedBinary => serialize_compressed_binary_checkpoint,
+            CheckpointFormat::Json => serialize_json_checkpoint,
+        }
+    }
+}
+
+/// Returns a checkpointing system that writes checkpoints in the given format.
+pub(crate) fn checkpointing_system(format: CheckpointFormat) -> Box<dyn CheckpointSystem> {
+    match format {
+        CheckpointFormat::CompressedBinary => Box::new(compressed_binary_checkpointing_system()),
+        CheckpointFormat::Json => Box::new(json_checkpointing_system()),
+    }
+}
+
+/// Parses the step index from a checkpoint file name of the form `checkpoint_{n}.bin`.
+fn step_index_from_checkpoint_path(checkpoint_path: &Path) -> Option<usize> {
+    checkpoint_path
+        .file_stem()
+        .and_then(OsStr::to_str)
+        .and_then(|stem| stem.strip_prefix("checkpoint_"))
+        .and_then(|index| index.parse().ok())
+}
+
+/// Determines the step index of a universe restored from the given checkpoint file, and stores it in the universe.
+///
+/// The step index stored in the universe is used if present. Otherwise, the step index is parsed
+/// from a file name of the form `checkpoint_{n}.bin`, if possible. If both are available but disagree,
+/// a warning is logged and the stored step index is used, unless `prefer_file_name` is `true`.
+pub fn resolve_restored_step_index(universe: &mut Universe, checkpoint_path: &Path, prefer_file_name: bool) -> usize {
+    let stored_step_index = universe
+        .try_get_component_storage::<StepIndex>()
+        .map(|storage| storage.get_component().0);
+    let file_name_step_index = step_index_from_checkpoint_path(checkpoint_path);
+
+    let step_index = match (stored_step_index, file_name_step_index) {
+        (Some(stored), Some(from_file_name)) if stored != from_file_name => {
+            let chosen = if prefer_file_name { from_file_name } else { stored };
+            warn!(
+                stored,
+                from_file_name,
+                "Step index {} stored in checkpoint \"{}\" disagrees with step index {} in its file name. Using {}.",
+                stored,
+                checkpoint_path.display(),
+                from_file_name,
+                chosen
+            );
+            chosen
+        }
+        (Some(stored), _) => stored,
+        (None, Some(from_file_name)) => {
+            info!(
+                "Checkpoint \"{}\" does not contain a step index. Using step index {} from its file name.",
+                checkpoint_path.display(),
+                from_file_name
+            );
+            from_file_name
+        }
+        (None, None) => get_step_index(universe).0,
+    };
+    universe.insert_storage(SingularStorage::new(StepIndex(step_index)));
+    step_index
+}
+
+/// Warns about storages of a universe restored from a checkpoint that do not match the current systems.
+///
+/// Storages in the checkpoint that are neither among `expected_tags` nor accessed by any system are likely
+/// dead data, for example from a checkpoint written by an older version of the scenario. Storages that are
+/// accessed by systems but absent from the checkpoint will be default-constructed when first accessed.
+/// Only accesses declared through [`System::accessed_storage_tags`] are considered.
+///
+/// Unless `all_accesses_declared` is `true`, a restored storage that appears unused may well be accessed
+/// by a system that does not declare its accesses, so unused storages are then only reported at the
+/// debug level.
+pub fn warn_on_restored_storage_mismatch(
+    universe: &Universe,
+    expected_tags: &BTreeSet<String>,
+    accessed_tags: &BTreeSet<String>,
+    all_accesses_declared: bool,
+) {
+    let restored_tags = universe.storage_tags();
+    for tag in &restored_tags {
+        if !expected_tags.contains(tag) && !accessed_tags.contains(tag) {
+            if all_accesses_declared {
+                warn!(
+                    tag,
+                    "Storage {tag} restored from checkpoint is not used by any system. The checkpoint may be stale."
+                );
+            } else {
+                debug!(
+                    tag,
+                    "Storage {tag} restored from checkpoint is not accessed by any system that declares its accesses."
+                );
+            }
+        }
+    }
+    for tag in accessed_tags {
+        if restored_tags.binary_search(tag).is_err() {
+            warn!(
+                tag,
+                "Storage {tag} is accessed by a system, but is missing from the checkpoint. \
+                 It will be default-constructed when first accessed."
+            );
+        }
+    }
+}
+
+/// Finds the checkpoint file with the highest step index in the given directory.
+///
+/// Only files with names of the form `checkpoint_{n}.bin` or `checkpoint_{n}.json` are considered.
+pub fn latest_checkpoint_file(checkpoint_dir: &Path) -> eyre::Result<PathBuf> {
+    list_checkpoint_files(checkpoint_dir)?
+        .pop()
+        .map(|(_, path)| path)
+        .ok_or_else(|| {
+            eyre!(
+                "checkpoint directory \"{}\" does not contain any checkpoint files of the form checkpoint_{{n}}.bin \
+                 or checkpoint_{{n}}.json",
+                checkpoint_dir.display()
+            )
+        })
+}
+
+/// Lists the checkpoint files of the form `checkpoint_{n}.bin` or `checkpoint_{n}.json` in the given directory,
+/// together with their step indices, sorted by increasing step index.
+fn list_checkpoint_files(checkpoint_dir: &Path) -> eyre::Result<Vec<(usize, PathBuf)>> {
+    let entries = fs::read_dir(checkpoint_dir)
+        .wrap_err_with(|| format!("failed to read checkpoint directory \"{}\"", checkpoint_dir.display()))?;
+
+    let mut checkpoint_files = Vec::new();
+    for entry in entries {
+        let path = entry
+            .wrap_err_with(|| {
+                format!(
+                    "failed to read entry of checkpoint directory \"{}\"",
+                    checkpoint_dir.display()
+                )
+            })?
+            .path();
+        let is_checkpoint = path
+            .extension()
+            .and_then(OsStr::to_str)
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("bin") || extension.eq_ignore_ascii_case("json"));
+        if let Some(step_index) = step_index_from_checkpoint_path(&path).filter(|_| is_checkpoint) {
+            checkpoint_files.push((step_index, path));
+        }
+    }
+    checkpoint_files.sort();
+    Ok(checkpoint_files)
+}
+
+/// Restores a binary checkpoint file, detecting the compression from the contents of the file.
+///
+/// Both `snap` and `zstd` compression are supported. If the compression can not be recognized
+/// from the leading bytes of the file, all known decoders are tried in turn.
 fn restore_compressed_binary_checkpoint_file<P: AsRef<Path>>(checkpoint_path: P) -> eyre::Result<Universe> {
     let checkpoint_path = checkpoint_path.as_ref();
-    let checkpoint_file = fs::OpenOptions::new()
-        .read(true)
-        .create(false)
-        .open(checkpoint_path)
-        .wrap_err("failed to open checkpoint file for reading")?;
+    let open_checkpoint_file = || {
+        fs::OpenOptions::new()
+            .read(true)
+            .create(false)
+            .open(checkpoint_path)
+            .map(BufReader::new)
+            .wrap_err("failed to open checkpoint file for reading")
+    };
+
+    let mut checkpoint_file = open_checkpoint_file()?;
+    let header = checkpoint_file
+        .fill_buf()
+        .wrap_err("failed to read checkpoint file")?;
+    if header.starts_with(SNAP_MAGIC) {
+        deserialize_snap_checkpoint(checkpoint_file)
+    } else if header.starts_with(ZSTD_MAGIC) {
+        deserialize_zstd_checkpoint(checkpoint_file)
+    } else {
+        warn!("Could not detect compression of checkpoint file from its contents. Trying all known formats");
+        deserialize_snap_checkpoint(checkpoint_file)
+            .or_else(|_| deserialize_zstd_checkpoint(open_checkpoint_file()?))
+            .wrap_err("checkpoint file is not compressed with any known compression (snap or zstd)")
+    }
+}
 
+fn deserialize_snap_checkpoint(checkpoint_file: impl Read) -> eyre::Result<Universe> {
     let uncompressed_file_stream = snap::read::FrameDecoder::new(checkpoint_file);
     bincode::deserialize_from(uncompressed_file_stream).wrap_err("error during deserialization of checkpoint file")
 }
 
-/// Returns a checkpointing system that serializes the [`dynamecs::Universe`] at every timestep using `bincode` and compressed with `snap`.
-pub fn compressed_binary_checkpointing_system() -> impl ObserverSystem {
-    CheckpointingSystem::new(|file, universe| {
-        let compressed_file_stream = snap::write::FrameEncoder::new(file);
-        bincode::serialize_into(compressed_file_stream, universe)?;
+fn deserialize_zstd_checkpoint(checkpoint_file: impl BufRead) -> eyre::Result<Universe> {
+    let uncompressed_file_stream =
+        zstd::Decoder::with_buffer(checkpoint_file).wrap_err("failed to initialize zstd decoder")?;
+    bincode::deserialize_from(uncompressed_file_stream).wrap_err("error during deserialization of checkpoint file")
+}
+
+fn restore_json_checkpoint_file(checkpoint_path: &Path) -> eyre::Result<Universe> {
+    let checkpoint_file = fs::File::open(checkpoint_path).wrap_err("failed to open checkpoint file for reading")?;
+    serde_json::from_reader(BufReader::new(checkpoint_file)).wrap_err("error during deserialization of checkpoint file")
+}
+
+fn serialize_compressed_binary_checkpoint(file: fs::File, universe: &Universe) -> eyre::Result<()> {
+    let compressed_file_stream = snap::write::FrameEncoder::new(file);
+    bincode::serialize_into(compressed_file_stream, universe)?;
+    Ok(())
+}
+
+fn serialize_json_checkpoint(file: fs::File, universe: &Universe) -> eyre::Result<()> {
+    let mut writer = BufWriter::new(file);
+    serde_json::to_writer_pretty(&mut writer, universe)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Returns a checkpointing system that serializes the [`dynamecs::Universe`] using `bincode` and compressed with `snap`.
+///
+/// The system writes a checkpoint every time it runs. The app wraps it in an [`IntervalCheckpointingSystem`],
+/// so that checkpoints are only written at the steps selected by `--checkpoint-interval`.
+pub(crate) fn compressed_binary_checkpointing_system() -> CheckpointingSystem {
+    CheckpointingSystem::new(CheckpointFormat::CompressedBinary)
+}
+
+/// Returns a checkpointing system that serializes the [`dynamecs::Universe`] as pretty-printed JSON.
+///
+/// Like [`compressed_binary_checkpointing_system`], it is wrapped in an [`IntervalCheckpointingSystem`] by the app.
+pub(crate) fn json_checkpointing_system() -> CheckpointingSystem {
+    CheckpointingSystem::new(CheckpointFormat::Json)
+}
+
+/// Writes the universe to the checkpoint file `final.bin` or `final.json`, depending on the given format.
+pub fn write_final_checkpoint(universe: &Universe, format: CheckpointFormat) -> eyre::Result<()> {
+    let checkpoint_file_name = format!("final.{}", format.extension());
+    let checkpoint_file_path = prepare_checkpoint_dir(universe)?.join(checkpoint_file_name);
+    let checkpoint_file = create_checkpoint_file(&checkpoint_file_path)?;
+
+    info!(
+        "Writing final checkpoint to file \"{}\"...",
+        checkpoint_file_path.display()
+    );
+    (format.serializer())(checkpoint_file, universe).wrap_err("error during serialization for checkpoint")
+}
+
+/// A system that writes checkpoints of the universe, as used by the app.
+///
+/// In contrast to an [`ObserverSystem`], a checkpoint system reports which checkpoint file it wrote,
+/// and can be asked to wait for checkpoints that are still being written.
+pub(crate) trait CheckpointSystem: Debug {
+    /// Writes a checkpoint of the universe for the current step.
+    ///
+    /// Returns the path of the checkpoint file, or `None` if the checkpoint was skipped.
+    fn write_checkpoint(&mut self, universe: &Universe) -> eyre::Result<Option<PathBuf>>;
+
+    /// Waits until all checkpoints are completely written.
+    fn finish(&mut self) -> eyre::Result<()> {
         Ok(())
-    })
+    }
 }
 
+type CheckpointSerializer = fn(fs::File, &Universe) -> eyre::Result<()>;
+
 /// Generic checkpointing system independent from the serialization file format.
-struct CheckpointingSystem<SerializeFn> {
-    serializer: SerializeFn,
+pub(crate) struct CheckpointingSystem {
+    format: CheckpointFormat,
+    serializer: CheckpointSerializer,
 }
 
-impl<SerializeFn> Debug for CheckpointingSystem<SerializeFn> {
+impl Debug for CheckpointingSystem {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "CheckpointingSystem")
+        write!(f, "CheckpointingSystem({:?})", self.format)
     }
 }
 
-impl<SerializeFn> CheckpointingSystem<SerializeFn>
-where
-    SerializeFn: FnMut(fs::File, &Universe) -> eyre::Result<()>,
-{
-    /// Constructs a checkpointing system from the given `FnMut(fs::File, &Universe) -> eyre::Result<()>` serialization closure.
-    fn new(serializer: SerializeFn) -> Self {
-        Self { serializer }
+impl CheckpointingSystem {
+    /// Constructs a checkpointing system that writes checkpoints in the given format.
+    fn new(format: CheckpointFormat) -> Self {
+        Self {
+            format,
+            serializer: format.serializer(),
+        }
     }
 }
 
-impl<SerializeFn> ObserverSystem for CheckpointingSystem<SerializeFn>
-where
-    SerializeFn: FnMut(fs::File, &Universe) -> eyre::Result<()>,
-{
+impl CheckpointSystem for CheckpointingSystem {
+    fn write_checkpoint(&mut self, universe: &Universe) -> eyre::Result<Option<PathBuf>> {
+        let checkpoint_file_path = prepare_checkpoint_file_path(universe, self.format)?;
+
+        // Open checkpoint file for writing
+        let checkpoint_file = create_checkpoint_file(&checkpoint_file_path)?;
+
+        // Run the serializer
+        info!("Writing checkpoint to file \"{}\"...", checkpoint_file_path.display());
+        (self.serializer)(checkpoint_file, universe).wrap_err("error during serialization for checkpoint")?;
+
+        Ok(Some(checkpoint_file_path))
+    }
+}
+
+impl ObserverSystem for CheckpointingSystem {
     fn name(&self) -> String {
         "CheckpointingSystem".to_string()
     }
 
     fn run(&mut self, universe: &Universe) -> eyre::Result<()> {
-        // Ensure that all components in the universe are registered
-        let unregistered_components = universe.unregistered_components();
-        if !unregistered_components.is_empty() {
-            return Err(eyre!(
-                "the following components are not registered: {:?}",
-                &unregistered_components
-            ));
+        self.write_checkpoint(universe).map(|_| ())
+    }
+}
+
+/// Wraps a checkpointing system so that checkpoints are only written when the step index is a
+/// multiple of the checkpoint interval.
+///
+/// If `keep_last` is set, all but the given number of most recent checkpoint files written by this
+/// system are removed after each checkpoint is written. Other files in the checkpoint folder, such as
+/// checkpoints of a previous run, are never removed.
+pub(crate) struct IntervalCheckpointingSystem {
+    system: Box<dyn CheckpointSystem>,
+    interval: usize,
+    keep_last: Option<usize>,
+    /// The step index of the most recent checkpoint that was not skipped
+    last_checkpoint_step: Option<usize>,
+    /// The checkpoint files written by this system that have not yet been removed, oldest first
+    written_checkpoints: VecDeque<PathBuf>,
+}
+
+impl Debug for IntervalCheckpointingSystem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "IntervalCheckpointingSystem(interval: {}, keep_last: {:?}, {:?})",
+            self.interval, self.keep_last, self.system
+        )
+    }
+}
+
+impl IntervalCheckpointingSystem {
+    pub(crate) fn new(system: Box<dyn CheckpointSystem>, interval: usize, keep_last: Option<usize>) -> Self {
+        assert!(interval > 0, "checkpoint interval must be positive");
+        assert!(keep_last != Some(0), "number of checkpoints to keep must be positive");
+        Self {
+            system,
+            interval,
+            keep_last,
+            last_checkpoint_step: None,
+            written_checkpoints: VecDeque::new(),
+        }
+    }
+
+    fn write_checkpoint(&mut self, universe: &Universe) -> eyre::Result<()> {
+        if let Some(checkpoint_path) = self.system.write_checkpoint(universe)? {
+            self.last_checkpoint_step = Some(get_step_index(universe).0);
+            self.written_checkpoints.push_back(checkpoint_path);
+            if let Some(keep_last) = self.keep_last {
+                self.remove_old_checkpoints(keep_last);
+            }
+        }
+        Ok(())
+    }
+
+    /// Removes all but the `keep_last` most recent checkpoint files written by this system.
+    ///
+    /// Only the most recent checkpoint may still be written in the background, since background systems
+    /// write a single checkpoint at a time. As `keep_last` is positive, it is never removed.
+    /// Failures are logged as warnings, since they should not abort the simulation.
+    fn remove_old_checkpoints(&mut self, keep_last: usize) {
+        let num_old_checkpoints = self.written_checkpoints.len().saturating_sub(keep_last);
+        for path in self.written_checkpoints.drain(..num_old_checkpoints) {
+            debug!("Removing old checkpoint file \"{}\"", path.display());
+            if let Err(err) = fs::remove_file(&path) {
+                warn!("Failed to remove old checkpoint file \"{}\": {err}", path.display());
+            }
         }
+    }
+
+    fn is_due(&self, universe: &Universe) -> bool {
+        get_step_index(universe).0.is_multiple_of(self.interval)
+    }
+
+    /// Waits for pending checkpoints to be written, and writes a checkpoint of the final state unless
+    /// one was already written for the final step.
+    ///
+    /// Errors from checkpoints that were written in the background are returned here.
+    pub(crate) fn finish(&mut self, universe: &Universe) -> eyre::Result<()> {
+        self.system.finish()?;
+        if self.last_checkpoint_step != Some(get_step_index(universe).0) {
+            self.write_checkpoint(universe)?;
+            self.system.finish()?;
+        }
+        Ok(())
+    }
+}
+
+impl System for IntervalCheckpointingSystem {
+    fn name(&self) -> String {
+        "IntervalCheckpointingSystem".to_string()
+    }
+
+    fn run(&mut self, universe: &mut Universe) -> eyre::Result<()> {
+        if self.is_due(universe) {
+            self.write_checkpoint(universe)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Determines the path of the checkpoint file for the current step, and ensures that the checkpoint
+/// output folder exists.
+fn prepare_checkpoint_file_path(universe: &Universe, format: CheckpointFormat) -> eyre::Result<PathBuf> {
+    let step_index = get_step_index(universe).0;
+    let checkpoint_file_name = format!("checkpoint_{}.{}", step_index, format.extension());
+    Ok(prepare_checkpoint_dir(universe)?.join(checkpoint_file_name))
+}
+
+/// Returns the checkpoint output folder, after ensuring that it exists and that all components in the
+/// universe are registered.
+fn prepare_checkpoint_dir(universe: &Universe) -> eyre::Result<PathBuf> {
+    // Ensure that all components in the universe are registered
+    let unregistered_components = universe.unregistered_components();
+    if !unregistered_components.is_empty() {
+        return Err(eyre!(
+            "the following components are not registered: {:?}",
+            &unregistered_components
+        ));
+    }
+
+    let checkpoint_path = try_get_settings(universe)?
+        .scenario_output_dir
+        .join("checkpoints");
+    // Ensure that the checkpoint output folder exists
+    fs::create_dir_all(&checkpoint_path).wrap_err_with(|| {
+        format!(
+            "failed to create output directory for checkpoints \"{}\"",
+            checkpoint_path.display()
+        )
+    })?;
 
-        let checkpoint_path = &try_get_settings(universe)?
-            .scenario_output_dir
-            .join("checkpoints");
-        // Ensure that the checkpoint output folder exists
-        fs::create_dir_all(&checkpoint_path).wrap_err_with(|| {
+    Ok(checkpoint_path)
+}
+
+fn create_checkpoint_file(checkpoint_file_path: &Path) -> eyre::Result<fs::File> {
+    fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        // TODO: To append or to truncate? Return error when file exists?
+        .truncate(true)
+        .append(false)
+        .open(checkpoint_file_path)
+        .wrap_err_with(|| {
             format!(
-                "failed to create output directory for checkpoints \"{}\"",
-                checkpoint_path.display()
+                "unable to open checkpoint file '{}' for writing",
+                checkpoint_file_path.display(),
             )
-        })?;
+        })
+}
 
-        let step_index = get_step_index(universe).0;
+/// Determines what a background checkpointing system does when a new checkpoint is due,
+/// but the previous checkpoint is still being written.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CheckpointBackpressure {
+    /// Skip the new checkpoint.
+    Skip,
+    /// Wait for the previous checkpoint to be written.
+    Block,
+}
 
-        let checkpoint_file_name = format!("checkpoint_{}.bin", step_index);
-        let checkpoint_file_path = checkpoint_path.join(checkpoint_file_name);
+type CheckpointWriter = Arc<dyn Fn(&Path, &[u8]) -> eyre::Result<()> + Send + Sync>;
 
-        // Open checkpoint file for writing
-        let checkpoint_file = fs::OpenOptions::new()
-            .write(true)
-            .create(true)
-            // TODO: To append or to truncate? Return error when file exists?
-            .truncate(true)
-            .append(false)
-            .open(&checkpoint_file_path)
-            .wrap_err_with(|| {
-                format!(
-                    "unable to open checkpoint file '{}' for writing",
-                    checkpoint_file_path.display(),
-                )
-            })?;
+/// Returns a checkpointing system that writes checkpoints on a background thread.
+///
+/// The [`dynamecs::Universe`] can not be sent to another thread, so it is still serialized with `bincode`
+/// in the simulation loop, and serialization blocks the simulation step. Only compressing the serialized
+/// state with `snap` and writing it to disk happens on a background thread. The checkpoint files
+/// are identical to the ones produced by [`compressed_binary_checkpointing_system`].
+///
+/// Any pending checkpoint is completed when the system is dropped.
+pub(crate) fn background_checkpointing_system(backpressure: CheckpointBackpressure) -> BackgroundCheckpointingSystem {
+    BackgroundCheckpointingSystem::new(
+        backpressure,
+        Arc::new(|path, serialized_universe| {
+            let checkpoint_file = create_checkpoint_file(path)?;
+            let mut compressed_file_stream = snap::write::FrameEncoder::new(checkpoint_file);
+            compressed_file_stream.write_all(serialized_universe)?;
+            compressed_file_stream.flush()?;
+            Ok(())
+        }),
+    )
+}
 
-        // Run the serializer
-        info!("Writing checkpoint to file \"{}\"...", checkpoint_file_path.display());
-        (self.serializer)(checkpoint_file, universe).wrap_err("error during serialization for checkpoint")?;
+pub(crate) struct BackgroundCheckpointingSystem {
+    backpressure: CheckpointBackpressure,
+    writer: CheckpointWriter,
+    pending_write: Option<JoinHandle<eyre::Result<()>>>,
+}
 
-        Ok(())
+impl Debug for BackgroundCheckpointingSystem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("BackgroundCheckpointingSystem")
+            .field("backpressure", &self.backpressure)
+            .finish_non_exhaustive()
+    }
+}
+
+impl BackgroundCheckpointingSystem {
+    fn new(backpressure: CheckpointBackpressure, writer: CheckpointWriter) -> Self {
+        Self {
+            backpressure,
+            writer,
+            pending_write: None,
+        }
+    }
+
+    /// Waits for the pending write to finish, if any, and returns its result.
+    fn finish_pending_write(&mut self) -> eyre::Result<()> {
+        match self.pending_write.take() {
+            Some(handle) => handle
+                .join()
+                .map_err(|_| eyre!("checkpoint writer thread panicked"))?
+                .wrap_err("failed to write checkpoint in the background"),
+            None => Ok(()),
+        }
+    }
+}
+
+impl CheckpointSystem for BackgroundCheckpointingSystem {
+    fn write_checkpoint(&mut self, universe: &Universe) -> eyre::Result<Option<PathBuf>> {
+        let write_in_progress = self
+            .pending_write
+            .as_ref()
+            .is_some_and(|handle| !handle.is_finished());
+        if write_in_progress && self.backpressure == CheckpointBackpressure::Skip {
+            let step_index = get_step_index(universe).0;
+            warn!("Skipping checkpoint for step {step_index}, since the previous checkpoint is still being written");
+            return Ok(None);
+        }
+        // Collect the result of the previous write, blocking if necessary
+        self.finish_pending_write()?;
+
+        let checkpoint_file_path = prepare_checkpoint_file_path(universe, CheckpointFormat::CompressedBinary)?;
+        let serialized_universe = bincode::serialize(universe).wrap_err("error during serialization for checkpoint")?;
+
+        info!(
+            "Writing checkpoint to file \"{}\" in the background...",
+            checkpoint_file_path.display()
+        );
+        let writer = self.writer.clone();
+        let path = checkpoint_file_path.clone();
+        self.pending_write = Some(thread::spawn(move || writer(&path, &serialized_universe)));
+
+        Ok(Some(checkpoint_file_path))
+    }
+
+    fn finish(&mut self) -> eyre::Result<()> {
+        self.finish_pending_write()
+    }
+}
+
+impl ObserverSystem for BackgroundCheckpointingSystem {
+    fn name(&self) -> String {
+        "BackgroundCheckpointingSystem".to_string()
+    }
+
+    fn run(&mut self, universe: &Universe) -> eyre::Result<()> {
+        self.write_checkpoint(universe).map(|_| ())
+    }
+}
+
+impl Drop for BackgroundCheckpointingSystem {
+    fn drop(&mut self) {
+        if let Err(err) = self.finish_pending_write() {
+            warn!("{err:?}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        background_checkpointing_system, compressed_binary_checkpointing_system, json_checkpointing_system,
+        latest_checkpoint_file, resolve_restored_step_index, restore_checkpoint_file, BackgroundCheckpointingSystem,
+        CheckpointBackpressure, CheckpointFormat, IntervalCheckpointingSystem,
+    };
+    use crate::record_channel_layer;
+    use crate::{DynamecsApp, Scenario};
+    use dynamecs::adapters::FnSystem;
+    use dynamecs::components::{get_step_index, register_default_components, DynamecsAppSettings, StepIndex, TimeStep};
+    use dynamecs::storages::{ImmutableSingularStorage, SingularStorage};
+    use dynamecs::{register_component, Component, ObserverSystem, Storage, Universe};
+    use dynamecs_analyze::Level;
+    use serde::{Deserialize, Serialize};
+    use serde_json::json;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::mpsc::channel;
+    use std::sync::{Arc, Mutex};
+    use tempfile::TempDir;
+    use tracing_subscriber::prelude::*;
+    use tracing_subscriber::Registry;
+
+    fn universe_with_output_dir(name: &str) -> (Universe, TempDir) {
+        register_default_components();
+        register_component::<DynamecsAppSettings>();
+        let output_dir = tempfile::tempdir().unwrap();
+        let mut universe = Universe::default();
+        universe.insert_storage(ImmutableSingularStorage::new(DynamecsAppSettings {
+            scenario_output_dir: output_dir.path().to_path_buf(),
+            scenario_name: name.to_string(),
+        }));
+        (universe, output_dir)
+    }
+
+    #[test]
+    fn restore_detects_checkpoint_compression_from_contents() {
+        let (mut universe, output_dir) = universe_with_output_dir("compression_sniffing");
+        universe.insert_storage(SingularStorage::new(StepIndex(7)));
+
+        let snap_path = output_dir.path().join("snap.bin");
+        let snap_writer = snap::write::FrameEncoder::new(std::fs::File::create(&snap_path).unwrap());
+        bincode::serialize_into(snap_writer, &universe).unwrap();
+
+        let zstd_path = output_dir.path().join("zstd.bin");
+        let zstd_writer = zstd::Encoder::new(std::fs::File::create(&zstd_path).unwrap(), 0)
+            .unwrap()
+            .auto_finish();
+        bincode::serialize_into(zstd_writer, &universe).unwrap();
+
+        for path in [snap_path, zstd_path] {
+            let restored = restore_checkpoint_file(&path).unwrap();
+            assert_eq!(get_step_index(&restored).0, 7);
+        }
+
+        let garbage_path = output_dir.path().join("garbage.bin");
+        std::fs::write(&garbage_path, b"not a checkpoint").unwrap();
+        assert!(restore_checkpoint_file(&garbage_path).is_err());
+    }
+
+    #[test]
+    fn restored_step_index_falls_back_to_file_name() {
+        let (mut universe, output_dir) = universe_with_output_dir("step_index_from_file_name");
+        universe.insert_storage(SingularStorage::new(StepIndex(7)));
+        let checkpoint_path = output_dir.path().join("checkpoint_12.bin");
+        let writer = snap::write::FrameEncoder::new(std::fs::File::create(&checkpoint_path).unwrap());
+        bincode::serialize_into(writer, &universe).unwrap();
+
+        for (prefer_file_name, expected_step_index) in [(false, 7), (true, 12)] {
+            let mut restored = restore_checkpoint_file(&checkpoint_path).unwrap();
+            let (sender, receiver) = channel();
+            let subscriber = Registry::default().with(record_channel_layer(sender));
+            let step_index = tracing::subscriber::with_default(subscriber, || {
+                resolve_restored_step_index(&mut restored, &checkpoint_path, prefer_file_name)
+            });
+            assert_eq!(step_index, expected_step_index);
+            assert_eq!(get_step_index(&restored).0, expected_step_index);
+
+            let warnings: Vec<_> = receiver
+                .try_iter()
+                .filter(|record| record.level() == Level::Warn)
+                .collect();
+            assert_eq!(warnings.len(), 1);
+            assert_eq!(warnings[0].fields()["stored"], json!(7));
+            assert_eq!(warnings[0].fields()["from_file_name"], json!(12));
+        }
+
+        // Without a stored step index, the file name is used
+        let mut restored = Universe::default();
+        assert_eq!(resolve_restored_step_index(&mut restored, &checkpoint_path, false), 12);
+        assert_eq!(get_step_index(&restored).0, 12);
+    }
+
+    #[test]
+    fn latest_checkpoint_file_picks_highest_step_index() {
+        let (mut universe, output_dir) = universe_with_output_dir("latest_checkpoint");
+        assert!(latest_checkpoint_file(output_dir.path()).is_err());
+
+        for step_index in [5, 12, 9] {
+            universe.insert_storage(SingularStorage::new(StepIndex(step_index)));
+            let path = output_dir
+                .path()
+                .join(format!("checkpoint_{step_index}.bin"));
+            let writer = snap::write::FrameEncoder::new(std::fs::File::create(&path).unwrap());
+            bincode::serialize_into(writer, &universe).unwrap();
+        }
+        std::fs::write(output_dir.path().join("final.bin"), b"").unwrap();
+        std::fs::write(output_dir.path().join("checkpoint_100.txt"), b"").unwrap();
+
+        let latest = latest_checkpoint_file(output_dir.path()).unwrap();
+        assert_eq!(latest, output_dir.path().join("checkpoint_12.bin"));
+        let restored = restore_checkpoint_file(&latest).unwrap();
+        assert_eq!(get_step_index(&restored).0, 12);
+    }
+
+    #[test]
+    fn json_checkpoint_roundtrip() {
+        let (mut universe, output_dir) = universe_with_output_dir("json_checkpoint");
+        universe.insert_storage(SingularStorage::new(StepIndex(4)));
+        universe.insert_storage(SingularStorage::new(TimeStep(0.25)));
+        let mut system = json_checkpointing_system();
+        system.run(&universe).unwrap();
+
+        let checkpoint_path = output_dir.path().join("checkpoints/checkpoint_4.json");
+        let json: serde_json::Value = serde_json::from_slice(&std::fs::read(&checkpoint_path).unwrap()).unwrap();
+        assert!(json.is_object());
+
+        let restored = restore_checkpoint_file(&checkpoint_path).unwrap();
+        assert_eq!(get_step_index(&restored).0, 4);
+        assert_eq!(
+            restored
+                .get_component_storage::<TimeStep>()
+                .get_component()
+                .0,
+            0.25
+        );
+        let settings = restored
+            .try_get_component_storage::<DynamecsAppSettings>()
+            .unwrap()
+            .get_component();
+        assert_eq!(settings.scenario_name, "json_checkpoint");
+        assert_eq!(settings.scenario_output_dir, output_dir.path());
+        assert_eq!(
+            latest_checkpoint_file(&output_dir.path().join("checkpoints")).unwrap(),
+            checkpoint_path
+        );
+    }
+
+    #[test]
+    fn only_last_checkpoints_are_kept() {
+        let (mut universe, output_dir) = universe_with_output_dir("keep_last_checkpoints");
+        let mut system =
+            IntervalCheckpointingSystem::new(Box::new(compressed_binary_checkpointing_system()), 1, Some(2));
+        for step_index in 0..5 {
+            universe.insert_storage(SingularStorage::new(StepIndex(step_index)));
+            dynamecs::System::run(&mut system, &mut universe).unwrap();
+        }
+
+        let mut checkpoint_files: Vec<_> = std::fs::read_dir(output_dir.path().join("checkpoints"))
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        checkpoint_files.sort();
+        assert_eq!(checkpoint_files, ["checkpoint_3.bin", "checkpoint_4.bin"]);
+    }
+
+    #[test]
+    fn checkpoints_not_written_by_system_are_kept() {
+        let (mut universe, output_dir) = universe_with_output_dir("keep_foreign_checkpoints");
+        let checkpoint_dir = output_dir.path().join("checkpoints");
+        std::fs::create_dir_all(&checkpoint_dir).unwrap();
+        // For example written by a previous run, or by the user
+        std::fs::write(checkpoint_dir.join("checkpoint_10.bin"), b"previous run").unwrap();
+
+        let mut system =
+            IntervalCheckpointingSystem::new(Box::new(compressed_binary_checkpointing_system()), 1, Some(1));
+        for step_index in 0..3 {
+            universe.insert_storage(SingularStorage::new(StepIndex(step_index)));
+            dynamecs::System::run(&mut system, &mut universe).unwrap();
+        }
+
+        let mut checkpoint_files: Vec<_> = std::fs::read_dir(&checkpoint_dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        checkpoint_files.sort();
+        assert_eq!(checkpoint_files, ["checkpoint_10.bin", "checkpoint_2.bin"]);
+    }
+
+    #[test]
+    fn background_checkpoints_are_eventually_written() {
+        let (mut universe, output_dir) = universe_with_output_dir("background_checkpoints");
+        let mut system = background_checkpointing_system(CheckpointBackpressure::Block);
+        for step_index in 0..3 {
+            universe.insert_storage(SingularStorage::new(StepIndex(step_index)));
+            system.run(&universe).unwrap();
+        }
+        // Dropping the system waits for the last checkpoint to be written
+        drop(system);
+
+        for step_index in 0..3 {
+            let checkpoint_path = output_dir
+                .path()
+                .join(format!("checkpoints/checkpoint_{step_index}.bin"));
+            let restored = restore_checkpoint_file(&checkpoint_path).unwrap();
+            assert_eq!(get_step_index(&restored).0, step_index);
+        }
+    }
+
+    #[test]
+    fn background_checkpointing_does_not_block_simulation() {
+        let (mut universe, _output_dir) = universe_with_output_dir("background_checkpoints_nonblocking");
+        let (release_sender, release_receiver) = channel::<()>();
+        let release_receiver = Mutex::new(release_receiver);
+        let num_writes = Arc::new(AtomicUsize::new(0));
+        let writer_num_writes = num_writes.clone();
+        let mut system = BackgroundCheckpointingSystem::new(
+            CheckpointBackpressure::Skip,
+            Arc::new(move |_, _| {
+                // Block until the test releases the writer
+                release_receiver.lock().unwrap().recv().unwrap();
+                writer_num_writes.fetch_add(1, Ordering::SeqCst);
+                Ok(())
+            }),
+        );
+
+        // Both calls return while the first write is blocked, and the second checkpoint is skipped
+        universe.insert_storage(SingularStorage::new(StepIndex(0)));
+        system.run(&universe).unwrap();
+        universe.insert_storage(SingularStorage::new(StepIndex(1)));
+        system.run(&universe).unwrap();
+        assert_eq!(num_writes.load(Ordering::SeqCst), 0);
+
+        release_sender.send(()).unwrap();
+        drop(system);
+        assert_eq!(num_writes.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn skipped_final_background_checkpoint_is_written_on_finish() {
+        let (mut universe, _output_dir) = universe_with_output_dir("background_checkpoints_finish");
+        let (release_sender, release_receiver) = channel::<()>();
+        let release_receiver = Mutex::new(release_receiver);
+        let written_paths = Arc::new(Mutex::new(Vec::new()));
+        let writer_paths = written_paths.clone();
+        let background_system = BackgroundCheckpointingSystem::new(
+            CheckpointBackpressure::Skip,
+            Arc::new(move |path, _| {
+                // Only the first write blocks until the test releases the writer
+                let mut paths = writer_paths.lock().unwrap();
+                if paths.is_empty() {
+                    release_receiver.lock().unwrap().recv().unwrap();
+                }
+                paths.push(path.file_name().unwrap().to_owned());
+                Ok(())
+            }),
+        );
+        let mut system = IntervalCheckpointingSystem::new(Box::new(background_system), 1, None);
+
+        for step_index in 0..2 {
+            universe.insert_storage(SingularStorage::new(StepIndex(step_index)));
+            dynamecs::System::run(&mut system, &mut universe).unwrap();
+        }
+        release_sender.send(()).unwrap();
+        system.finish(&universe).unwrap();
+        assert_eq!(*written_paths.lock().unwrap(), ["checkpoint_0.bin", "checkpoint_1.bin"]);
+    }
+
+    #[test]
+    fn finish_returns_error_of_background_checkpoint() {
+        let (mut universe, _output_dir) = universe_with_output_dir("background_checkpoints_error");
+        let background_system = BackgroundCheckpointingSystem::new(
+            CheckpointBackpressure::Block,
+            Arc::new(|_, _| Err(eyre::eyre!("disk full"))),
+        );
+        let mut system = IntervalCheckpointingSystem::new(Box::new(background_system), 1, None);
+
+        universe.insert_storage(SingularStorage::new(StepIndex(0)));
+        dynamecs::System::run(&mut system, &mut universe).unwrap();
+        let err = system.finish(&universe).unwrap_err();
+        assert!(format!("{err:?}").contains("disk full"));
+    }
+
+    #[test]
+    fn final_checkpoint_is_written_once_after_bounded_run() {
+        let (universe, output_dir) = universe_with_output_dir("final_checkpoint");
+        let mut scenario = Scenario::default_with_name("final_checkpoint");
+        scenario.state = universe;
+
+        let mut app = DynamecsApp::from_config_and_app_settings(()).write_final_checkpoint(true);
+        app.scenario = Some(scenario);
+        app.max_steps = Some(2);
+        app.run().unwrap();
+
+        let checkpoint_files: Vec<_> = std::fs::read_dir(output_dir.path().join("checkpoints"))
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        assert_eq!(checkpoint_files, ["final.bin"]);
+        let restored = restore_checkpoint_file(output_dir.path().join("checkpoints/final.bin")).unwrap();
+        assert_eq!(get_step_index(&restored).0, 3);
+    }
+
+    #[test]
+    fn final_checkpoint_is_written_in_configured_format() {
+        let (universe, output_dir) = universe_with_output_dir("final_checkpoint_json");
+        let mut scenario = Scenario::default_with_name("final_checkpoint_json");
+        scenario.state = universe;
+
+        let mut app = DynamecsApp::from_config_and_app_settings(()).write_final_checkpoint(true);
+        app.scenario = Some(scenario);
+        app.checkpoint_format = CheckpointFormat::Json;
+        app.max_steps = Some(2);
+        app.run().unwrap();
+
+        let checkpoint_files: Vec<_> = std::fs::read_dir(output_dir.path().join("checkpoints"))
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        assert_eq!(checkpoint_files, ["final.json"]);
+        let restored = restore_checkpoint_file(output_dir.path().join("checkpoints/final.json")).unwrap();
+        assert_eq!(get_step_index(&restored).0, 3);
+    }
+
+    #[test]
+    fn checkpoints_are_written_on_interval_and_for_final_step() {
+        let (universe, output_dir) = universe_with_output_dir("checkpoint_interval");
+        let mut scenario = Scenario::default_with_name("checkpoint_interval");
+        scenario.state = universe;
+
+        let mut app = DynamecsApp::from_config_and_app_settings(())
+            .write_checkpoints(true)
+            .checkpoint_interval(3);
+        app.scenario = Some(scenario);
+        app.max_steps = Some(6);
+        app.run().unwrap();
+
+        let mut checkpoint_files: Vec<_> = std::fs::read_dir(output_dir.path().join("checkpoints"))
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        checkpoint_files.sort();
+        assert_eq!(
+            checkpoint_files,
+            ["checkpoint_3.bin", "checkpoint_6.bin", "checkpoint_7.bin"]
+        );
+        let restored = restore_checkpoint_file(output_dir.path().join("checkpoints/checkpoint_7.bin")).unwrap();
+        assert_eq!(get_step_index(&restored).0, 7);
+    }
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    struct StaleComponent(usize);
+
+    impl Component for StaleComponent {
+        type Storage = SingularStorage<Self>;
+    }
+
+    #[derive(Debug)]
+    struct TimeStepObserver;
+
+    impl ObserverSystem for TimeStepObserver {
+        fn accessed_storage_tags(&self) -> Vec<String> {
+            vec![<TimeStep as Component>::Storage::tag()]
+        }
+
+        fn run(&mut self, _data: &Universe) -> eyre::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn restore_warns_about_storages_not_matching_systems() {
+        let (mut universe, output_dir) = universe_with_output_dir("restore_storage_mismatch");
+        register_component::<StaleComponent>();
+        universe.insert_storage(SingularStorage::new(StepIndex(2)));
+        universe.insert_storage(SingularStorage::new(StaleComponent(3)));
+        let checkpoint_path = output_dir.path().join("checkpoint_2.bin");
+        let writer = snap::write::FrameEncoder::new(std::fs::File::create(&checkpoint_path).unwrap());
+        bincode::serialize_into(writer, &universe).unwrap();
+
+        let stale_tag = json!(<StaleComponent as Component>::Storage::tag());
+        let time_step_tag = json!(<TimeStep as Component>::Storage::tag());
+        for with_undeclared_system in [false, true] {
+            let mut scenario = Scenario::default_with_name("restore_storage_mismatch");
+            scenario.simulation_systems.add_system(TimeStepObserver);
+            if with_undeclared_system {
+                scenario
+                    .simulation_systems
+                    .add_system(FnSystem::new("undeclared", |_| Ok(())));
+            }
+            let mut app = DynamecsApp::from_config_and_app_settings(()).restore_checkpoint(&checkpoint_path);
+            app.scenario = Some(scenario);
+            app.max_steps = Some(3);
+
+            let (sender, receiver) = channel();
+            let subscriber = Registry::default().with(record_channel_layer(sender));
+            tracing::subscriber::with_default(subscriber, || app.run()).unwrap();
+
+            let tagged_records: Vec<_> = receiver
+                .try_iter()
+                .filter_map(|record| Some((record.level(), record.fields().get("tag").cloned()?)))
+                .collect();
+            // The stale storage may be accessed by the undeclared system, so it is not warned about
+            let stale_level = if with_undeclared_system {
+                Level::Debug
+            } else {
+                Level::Warn
+            };
+            assert_eq!(
+                tagged_records,
+                [(stale_level, stale_tag.clone()), (Level::Warn, time_step_tag.clone())]
+            );
+        }
     }
 }
@@ -1,17 +1,26 @@
-use crate::get_default_output_dir;
-use clap::Parser;
+use crate::{get_default_output_dir, CheckpointFormat};
+use clap::{Parser, ValueEnum};
 use std::path::PathBuf;
 use tracing_subscriber::filter::LevelFilter;
 
 #[derive(Parser)]
 pub struct CliOptions {
     #[arg(
-        short,
+        short = 'c',
+        long = "config-file",
+        help = "The path (relative or absolute) to a scenario-specific JSON5 configuration file. \
+                May be given multiple times, in which case the files are merged from left to right: \
+                objects are merged recursively, and other values in later files take precedence. \
+                String values may reference environment variables as ${VAR} or ${VAR:-default}, \
+                and $${ denotes a literal ${."
+    )]
+    pub config_files: Vec<PathBuf>,
+    #[arg(
         long,
-        help = "The path (relative or absolute) to a scenario-specific JSON5 configuration file."
+        help = "A scenario configuration as a JSON5 string. \
+                String values may reference environment variables as ${VAR} or ${VAR:-default}, \
+                and $${ denotes a literal ${."
     )]
-    pub config_file: Option<PathBuf>,
-    #[arg(long, help = "A scenario configuration as a JSON5 string.")]
     pub config_string: Option<String>,
     #[arg(
         short = 'o',
@@ -27,16 +36,68 @@ pub struct CliOptions {
         help = "Maximum number of simulation steps to take (by default infinite)"
     )]
     pub max_steps: Option<usize>,
+    #[arg(
+        long = "max-wall-time",
+        help = "Maximum wall-clock time in seconds. The simulation stops after the step that is in progress \
+                when the time limit is reached"
+    )]
+    pub max_wall_time: Option<f64>,
+    #[arg(
+        long = "seed",
+        help = "Seed for the global random number generator, for reproducible simulations. \
+                When restoring from a checkpoint, the random number generator stored in the checkpoint is used instead"
+    )]
+    pub seed: Option<u64>,
     #[arg(
         long = "write-checkpoints",
-        help = "Write a checkpoint file to disk after every timestep"
+        help = "Write checkpoint files to disk during the simulation, at the steps selected by --checkpoint-interval"
     )]
     pub write_checkpoints: bool,
+    #[arg(
+        long = "checkpoint-format",
+        value_enum,
+        default_value_t = CheckpointFormatArg::Bin,
+        help = "The file format of checkpoints written with --write-checkpoints, --write-final-checkpoint \
+                and --checkpoint-on-termination"
+    )]
+    pub checkpoint_format: CheckpointFormatArg,
+    #[arg(
+        long = "checkpoint-interval",
+        default_value_t = 1,
+        help = "Only write checkpoints when the step index is a multiple of the given interval. \
+                The state after the final step is always checkpointed."
+    )]
+    pub checkpoint_interval: usize,
+    #[arg(
+        long = "keep-last-checkpoints",
+        help = "Only keep the given number of most recent checkpoint files written by this run, removing older ones. \
+                Must be positive"
+    )]
+    pub keep_last_checkpoints: Option<usize>,
+    #[arg(
+        long = "checkpoint-on-termination",
+        help = "When the application receives a signal to terminate, such as Ctrl+C, stop the simulation \
+                after the current step and write a checkpoint before exiting"
+    )]
+    pub checkpoint_on_termination: bool,
+    #[arg(
+        long = "write-final-checkpoint",
+        help = "Write a single checkpoint file final.bin, or final.json with --checkpoint-format json, \
+                to disk after the simulation has ended"
+    )]
+    pub write_final_checkpoint: bool,
     #[arg(
         long = "restore-checkpoint",
-        help = "Restore the simulation state from a checkpoint file and continue the simulation"
+        help = "Restore the simulation state from a checkpoint file and continue the simulation. \
+                If a directory is given, the checkpoint with the highest step index in the directory is restored"
     )]
     pub restore_checkpoint: Option<PathBuf>,
+    #[arg(
+        long = "prefer-checkpoint-file-name-step-index",
+        help = "When restoring a checkpoint whose file name contains a step index that disagrees with \
+                the step index stored in the checkpoint, use the step index from the file name"
+    )]
+    pub prefer_checkpoint_file_name_step_index: bool,
     #[arg(
         long,
         default_value = "info",
@@ -51,19 +112,82 @@ pub struct CliOptions {
                 Possible values: off, error, warn, info, debug, trace."
     )]
     pub file_log_level: LevelFilter,
+    #[arg(
+        long = "log-target-filter",
+        help = "Per-target log levels for text and JSON log files, given as comma-separated directives \
+                in the syntax of tracing's EnvFilter, for example dynamecs_app=debug,my_sim=trace,noisy_dep=off. \
+                Targets without a matching directive are logged with --file-log-level."
+    )]
+    pub log_target_filter: Option<String>,
     #[arg(
         long = "override",
         help = "Override a configuration option using the syntax <path.in.json>=<new value>. \
+        Use <path.to.array>.+=<new value> to append to an array. \
         Multiple overrides are applied in sequence."
     )]
     pub overrides: Vec<String>,
+    #[arg(
+        long = "overrides-file",
+        help = "Read configuration overrides from a file, one <path.in.json>=<new value> per line. \
+        Blank lines and lines starting with # are ignored. Overrides from the file are applied in order \
+        before any overrides given with --override, so that the latter take precedence."
+    )]
+    pub overrides_file: Option<PathBuf>,
+    #[arg(
+        long = "stream-logs",
+        help = "Stream JSON log records over TCP to the given address, for example for live monitoring."
+    )]
+    pub stream_logs: Option<String>,
+    #[arg(
+        long = "log-buffer-size",
+        default_value_t = 0,
+        help = "Size in bytes of the write buffer for each log file. Larger buffers reduce the number of \
+        system calls for high-throughput logging. By default, log files are not buffered."
+    )]
+    pub log_buffer_size: usize,
+    #[arg(
+        long = "binary-logs",
+        help = "Additionally write log records in the compact binary .reclog format, which is smaller than \
+        the JSON log. The binary log uses the file log level and is never compressed."
+    )]
+    pub binary_logs: bool,
     #[arg(long = "compress-logs", help = "Compress logs with gzip compression.")]
     pub compress_logs: bool,
     #[arg(long = "no-archive", help = "Disable timestamped archive logs.", action = clap::ArgAction::SetFalse)]
     pub archive_logs: bool,
+    #[arg(
+        long = "archive-timestamp-format",
+        help = "strftime format string for the timestamp in archive log file names, for example \
+        %Y%m%d_%H%M%S. The formatted timestamp must not contain colons or path separators. \
+        By default, an RFC 3339 timestamp with colons replaced by dots is used."
+    )]
+    pub archive_timestamp_format: Option<String>,
     #[arg(
         long = "allow-unknown-config",
         help = "Allow unknown fields in scenario configuration. This is disabled by default in order to prevent ignoring misspelled keys or similar mistakes."
     )]
     pub allow_unknown_config: bool,
+    #[cfg(feature = "schemars")]
+    #[arg(
+        long = "print-config-schema",
+        help = "Print the JSON Schema of the scenario configuration and exit."
+    )]
+    pub print_config_schema: bool,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum CheckpointFormatArg {
+    /// Binary checkpoints compressed with snap.
+    Bin,
+    /// Human-readable JSON checkpoints.
+    Json,
+}
+
+impl From<CheckpointFormatArg> for CheckpointFormat {
+    fn from(arg: CheckpointFormatArg) -> Self {
+        match arg {
+            CheckpointFormatArg::Bin => CheckpointFormat::CompressedBinary,
+            CheckpointFormatArg::Json => CheckpointFormat::Json,
+        }
+    }
 }
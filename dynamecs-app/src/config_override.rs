@@ -1,14 +1,36 @@
 use eyre::{eyre, WrapErr};
 use serde_json::{Map, Value};
+use std::fs::read_to_string;
+use std::path::{Path, PathBuf};
 use tracing::info;
 
-struct InvalidOverride;
+struct InvalidOverride {
+    reason: String,
+}
+
+impl InvalidOverride {
+    fn new(reason: impl Into<String>) -> Self {
+        Self { reason: reason.into() }
+    }
+}
+
+/// The path segment used to denote appending to an array, e.g. `solvers.+=<value>`.
+const APPEND_SEGMENT: &str = "+";
 
 fn recursively_apply_config_override(
     config_part: &mut serde_json::Value,
     path: &str,
     value: serde_json::Value,
 ) -> Result<(), InvalidOverride> {
+    if path == APPEND_SEGMENT {
+        return if let Value::Array(array) = config_part {
+            array.push(value);
+            Ok(())
+        } else {
+            Err(InvalidOverride::new("cannot append to a value that is not an array"))
+        };
+    }
+
     if let Value::Object(obj) = config_part {
         let (head, tail) = path
             .split_once(".")
@@ -25,7 +47,12 @@ fn recursively_apply_config_override(
             }
         } else {
             if let Some(tail) = tail {
-                let mut new_obj = serde_json::Value::Object(Map::new());
+                // Appending to a non-existent entry creates a new array
+                let mut new_obj = if tail == APPEND_SEGMENT {
+                    serde_json::Value::Array(Vec::new())
+                } else {
+                    serde_json::Value::Object(Map::new())
+                };
                 recursively_apply_config_override(&mut new_obj, tail, value)?;
                 obj.insert(head.to_string(), new_obj);
                 Ok(())
@@ -35,7 +62,9 @@ fn recursively_apply_config_override(
             }
         }
     } else {
-        Err(InvalidOverride)
+        Err(InvalidOverride::new(
+            "cannot access field of a value that is not an object",
+        ))
     }
 }
 
@@ -54,7 +83,7 @@ pub fn apply_config_override(config_json: &mut serde_json::Value, config_overrid
         )
     })?;
     recursively_apply_config_override(config_json, path, value_as_json)
-        .or_else(|_| Err(eyre!("invalid override {config_override} for config")))?;
+        .map_err(|InvalidOverride { reason }| eyre!("invalid override {config_override} for config: {reason}"))?;
     Ok(())
 }
 
@@ -70,9 +99,160 @@ pub fn apply_config_overrides(
     Ok(config_json)
 }
 
+/// Parses newline-separated overrides, ignoring blank lines and lines starting with `#`.
+pub fn parse_overrides(overrides_str: &str) -> Vec<String> {
+    overrides_str
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Reads newline-separated overrides from the given file, see [`parse_overrides`].
+pub fn read_overrides_file(path: &Path) -> eyre::Result<Vec<String>> {
+    info!(target: "dynamecs_app", "Reading config overrides from {}", path.display());
+    let overrides_str =
+        read_to_string(path).wrap_err_with(|| format!("failed to read overrides file at {}", path.display()))?;
+    Ok(parse_overrides(&overrides_str))
+}
+
+/// Recursively merges `config_part` into `base`.
+///
+/// Objects are merged key by key, so that keys that are only present in `base` are kept. Any other
+/// value in `config_part`, including arrays, replaces the corresponding value in `base`.
+fn recursively_merge_config(base: &mut serde_json::Value, config_part: serde_json::Value) {
+    match (base, config_part) {
+        (Value::Object(base_obj), Value::Object(obj)) => {
+            for (key, value) in obj {
+                if let Some(base_value) = base_obj.get_mut(&key) {
+                    recursively_merge_config(base_value, value);
+                } else {
+                    base_obj.insert(key, value);
+                }
+            }
+        }
+        (base, config_part) => *base = config_part,
+    }
+}
+
+/// Merges the given configurations from left to right, so that later configurations take
+/// precedence over earlier ones.
+pub fn merge_configs(configs: impl IntoIterator<Item = serde_json::Value>) -> serde_json::Value {
+    let mut merged = Value::Object(Map::new());
+    for config in configs {
+        recursively_merge_config(&mut merged, config);
+    }
+    merged
+}
+
+/// Reads the given JSON5 configuration files and merges them from left to right,
+/// see [`merge_configs`].
+pub fn read_and_merge_config_files(paths: &[PathBuf]) -> eyre::Result<serde_json::Value> {
+    let configs = paths
+        .iter()
+        .map(|path| {
+            info!(target: "dynamecs_app", "Reading config file from {}.", path.display());
+            let config_str =
+                read_to_string(path).wrap_err_with(|| format!("failed to read config file at {}", path.display()))?;
+            json5::from_str(&config_str).wrap_err_with(|| {
+                format!(
+                    "failed to deserialize supplied JSON5 configuration file at {}",
+                    path.display()
+                )
+            })
+        })
+        .collect::<eyre::Result<Vec<serde_json::Value>>>()?;
+    Ok(merge_configs(configs))
+}
+
+/// Replaces references to environment variables in all string values of the configuration,
+/// see [`interpolate_variables`].
+pub fn interpolate_env_vars(config_json: &mut serde_json::Value) -> eyre::Result<()> {
+    interpolate_variables(config_json, |name| std::env::var(name).ok())
+}
+
+/// Replaces references of the form `${VAR}` or `${VAR:-default}` in all string values of the
+/// configuration with the value of the variable given by `lookup`.
+///
+/// If the variable is not defined, the default is used if given, and otherwise an error is
+/// returned. A literal `${` can be written as `$${`. Object keys are left untouched.
+fn interpolate_variables(
+    config_json: &mut serde_json::Value,
+    lookup: impl Fn(&str) -> Option<String>,
+) -> eyre::Result<()> {
+    recursively_interpolate_variables(config_json, "", &lookup)
+}
+
+fn recursively_interpolate_variables(
+    config_part: &mut serde_json::Value,
+    path: &str,
+    lookup: &dyn Fn(&str) -> Option<String>,
+) -> eyre::Result<()> {
+    match config_part {
+        Value::String(string) => {
+            *string = interpolate_string(string, lookup)
+                .map_err(|reason| eyre!("invalid configuration value at \"{path}\": {reason}"))?;
+        }
+        Value::Array(array) => {
+            for (i, value) in array.iter_mut().enumerate() {
+                recursively_interpolate_variables(value, &join_config_path(path, &i.to_string()), lookup)?;
+            }
+        }
+        Value::Object(obj) => {
+            for (key, value) in obj.iter_mut() {
+                recursively_interpolate_variables(value, &join_config_path(path, key), lookup)?;
+            }
+        }
+        Value::Null | Value::Bool(_) | Value::Number(_) => {}
+    }
+    Ok(())
+}
+
+fn join_config_path(path: &str, segment: &str) -> String {
+    if path.is_empty() {
+        segment.to_string()
+    } else {
+        format!("{path}.{segment}")
+    }
+}
+
+fn interpolate_string(string: &str, lookup: &dyn Fn(&str) -> Option<String>) -> Result<String, String> {
+    let mut result = String::with_capacity(string.len());
+    let mut remaining = string;
+    while let Some(start) = remaining.find("${") {
+        // `$${` is an escaped, literal `${`
+        if remaining[..start].ends_with('$') {
+            result.push_str(&remaining[..start - 1]);
+            result.push_str("${");
+            remaining = &remaining[start + 2..];
+            continue;
+        }
+        result.push_str(&remaining[..start]);
+        let reference = &remaining[start + 2..];
+        let end = reference
+            .find('}')
+            .ok_or_else(|| format!("unterminated variable reference in \"{string}\""))?;
+        let (name, default) = reference[..end]
+            .split_once(":-")
+            .map(|(name, default)| (name, Some(default)))
+            .unwrap_or((&reference[..end], None));
+        let value = lookup(name)
+            .or_else(|| default.map(str::to_string))
+            .ok_or_else(|| format!("environment variable {name} is not defined"))?;
+        result.push_str(&value);
+        remaining = &reference[end + 1..];
+    }
+    result.push_str(remaining);
+    Ok(result)
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::config_override::apply_config_override;
+    use crate::config_override::{
+        apply_config_override, apply_config_overrides, interpolate_variables, read_and_merge_config_files,
+        read_overrides_file,
+    };
     use serde::{Deserialize, Serialize};
     use serde_json::json;
     use std::collections::HashMap;
@@ -233,4 +413,195 @@ mod tests {
             })
         )
     }
+
+    #[test]
+    fn apply_config_override_append_to_existing_array() {
+        let mut json = json!({
+            "solvers": [ { "method": "cg" } ],
+        });
+        apply_config_override(&mut json, "solvers.+={ method: 'direct' }").unwrap();
+
+        assert_eq!(
+            json,
+            json!({
+                "solvers": [ { "method": "cg" }, { "method": "direct" } ],
+            })
+        )
+    }
+
+    #[test]
+    fn apply_config_override_append_creates_array() {
+        let mut json = json!({
+            "settings": {}
+        });
+        apply_config_override(&mut json, "settings.solvers.+=3").unwrap();
+
+        assert_eq!(
+            json,
+            json!({
+                "settings": {
+                    "solvers": [3]
+                }
+            })
+        )
+    }
+
+    #[test]
+    fn apply_config_override_append_to_non_array_fails() {
+        let mut json = json!({
+            "solvers": { "method": "cg" },
+        });
+        assert!(apply_config_override(&mut json, "solvers.+=3").is_err());
+    }
+
+    #[test]
+    fn test_overrides_file_with_comments_and_blank_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("overrides.txt");
+        std::fs::write(
+            &path,
+            "# Resolution used for the simulation\n\
+            resolution=8\n\
+            \n\
+            \x20  # Indented comment\n\
+            name=\"Cat\"\n\
+            \n\
+            resolution=16\n",
+        )
+        .unwrap();
+        let overrides = read_overrides_file(&path).unwrap();
+        assert_eq!(overrides, vec!["resolution=8", r#"name="Cat""#, "resolution=16"]);
+
+        let input_cfg = MockConfig {
+            resolution: 4,
+            name: "Bear".to_string(),
+            stats: MeshStats {
+                num_verts: 100,
+                map: HashMap::new(),
+            },
+        };
+        let config_json = serde_json::to_value(input_cfg.clone()).unwrap();
+        let config_json = apply_config_overrides(config_json, &overrides).unwrap();
+        let config: MockConfig = serde_json::from_value(config_json).unwrap();
+        let expected = MockConfig {
+            resolution: 16,
+            name: "Cat".to_string(),
+            ..input_cfg
+        };
+        assert_eq!(config, expected);
+    }
+
+    #[test]
+    fn test_merge_config_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let base_path = dir.path().join("base.json5");
+        let scenario_path = dir.path().join("scenario.json5");
+        std::fs::write(
+            &base_path,
+            "{ resolution: 4, name: 'Bear', stats: { num_verts: 100, map: { boundary: 10, interior: 5 } } }",
+        )
+        .unwrap();
+        std::fs::write(
+            &scenario_path,
+            "// Only overrides some of the fields
+            { name: 'Cat', stats: { map: { boundary: 2, middle: 7 } } }",
+        )
+        .unwrap();
+
+        let config_json = read_and_merge_config_files(&[base_path.clone(), scenario_path.clone()]).unwrap();
+        let config: MockConfig = serde_json::from_value(config_json).unwrap();
+        let expected = MockConfig {
+            resolution: 4,
+            name: "Cat".to_string(),
+            stats: MeshStats {
+                num_verts: 100,
+                map: [("boundary", 2), ("interior", 5), ("middle", 7)]
+                    .into_iter()
+                    .map(|(key, value)| (key.to_string(), value))
+                    .collect(),
+            },
+        };
+        assert_eq!(config, expected);
+
+        // Reversing the order gives precedence to the base config
+        let config_json = read_and_merge_config_files(&[scenario_path, base_path]).unwrap();
+        let config: MockConfig = serde_json::from_value(config_json).unwrap();
+        assert_eq!(config.name, "Bear");
+        assert_eq!(config.stats.map["boundary"], 10);
+        assert_eq!(config.stats.map["middle"], 7);
+    }
+
+    fn lookup_test_variables(name: &str) -> Option<String> {
+        match name {
+            "DATA_DIR" => Some("/data".to_string()),
+            "USER" => Some("bear".to_string()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn interpolate_variables_in_string_values() {
+        let mut json = json!({
+            "name": "${USER}",
+            "paths": ["${DATA_DIR}/meshes/${USER}.msh", "no variables"],
+            "settings": { "resolution": 4, "${USER}": "key is not interpolated" },
+        });
+        interpolate_variables(&mut json, lookup_test_variables).unwrap();
+        assert_eq!(
+            json,
+            json!({
+                "name": "bear",
+                "paths": ["/data/meshes/bear.msh", "no variables"],
+                "settings": { "resolution": 4, "${USER}": "key is not interpolated" },
+            })
+        );
+    }
+
+    #[test]
+    fn interpolate_variables_uses_default_for_undefined_variable() {
+        let mut json = json!({
+            "output": "${OUTPUT_DIR:-/tmp/output}",
+            "data": "${DATA_DIR:-/unused}",
+            "empty": "${UNDEFINED:-}",
+        });
+        interpolate_variables(&mut json, lookup_test_variables).unwrap();
+        assert_eq!(
+            json,
+            json!({
+                "output": "/tmp/output",
+                "data": "/data",
+                "empty": "",
+            })
+        );
+    }
+
+    #[test]
+    fn interpolate_variables_fails_for_undefined_variable() {
+        let mut json = json!({ "settings": { "paths": ["${DATA_DIR}", "${MESH_DIR}/mesh.msh"] } });
+        let err = interpolate_variables(&mut json, lookup_test_variables).unwrap_err();
+        let message = err.to_string();
+        assert!(message.contains("MESH_DIR"), "unexpected error: {message}");
+        assert!(message.contains("settings.paths.1"), "unexpected error: {message}");
+
+        let mut json = json!({ "name": "${USER" });
+        assert!(interpolate_variables(&mut json, lookup_test_variables).is_err());
+    }
+
+    #[test]
+    fn interpolate_variables_keeps_escaped_references() {
+        let mut json = json!({
+            "template": "$${USER} is ${USER}",
+            "undefined": "$${MESH_DIR}/mesh.msh",
+            "unterminated": "cost: $$${",
+        });
+        interpolate_variables(&mut json, lookup_test_variables).unwrap();
+        assert_eq!(
+            json,
+            json!({
+                "template": "${USER} is bear",
+                "undefined": "${MESH_DIR}/mesh.msh",
+                "unterminated": "cost: $${",
+            })
+        );
+    }
 }
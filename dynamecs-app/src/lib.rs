@@ -1,39 +1,66 @@
 //! Opinionated framework for building simulation apps with `dynamecs`.
-use checkpointing::{compressed_binary_checkpointing_system, restore_checkpoint_file};
+use checkpointing::{
+    background_checkpointing_system, checkpointing_system, latest_checkpoint_file, resolve_restored_step_index,
+    restore_checkpoint_file, warn_on_restored_storage_mismatch, write_final_checkpoint, CheckpointSystem,
+    IntervalCheckpointingSystem,
+};
 use clap::Parser;
 use cli::CliOptions;
 use dynamecs::components::{
-    get_simulation_time, get_step_index, register_default_components, DynamecsAppSettings, SimulationTime, StepIndex,
-    TimeStep,
+    get_simulation_time, get_step_index, is_paused, register_default_components, try_get_settings, ConfigComponent,
+    CurrentPhase, DynamecsAppSettings, Phase, RngResource, ScenarioMetadata, SimulationTime, StepIndex, TimeStep,
 };
 use dynamecs::storages::{ImmutableSingularStorage, SingularStorage};
-use dynamecs::{register_component, Component, System, Systems, Universe};
+use dynamecs::{register_component, Component, Storage, System, Systems, Universe};
 use eyre::{eyre, Context};
+use progress::progress_file_system;
 use serde::{Deserialize, Serialize};
-use std::fs::read_to_string;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::path::{Path, PathBuf};
-use tracing::{debug, info, info_span, instrument, warn};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use termination::TerminationGuard;
+use tracing::{debug, error, info, info_span, instrument, warn};
 
 pub extern crate eyre;
+#[cfg(feature = "schemars")]
+pub extern crate schemars;
 pub extern crate serde;
 pub extern crate tracing;
 
+#[cfg(feature = "count-allocations")]
+pub mod allocations;
 mod checkpointing;
 mod cli;
 mod config_override;
+pub mod observers;
+mod progress;
+mod stop_condition;
+mod termination;
 mod tracing_impl;
 
+pub use checkpointing::CheckpointBackpressure;
+pub use checkpointing::CheckpointFormat;
+pub use stop_condition::StopCondition;
+pub use termination::TerminationRequested;
+pub use tracing_impl::record_channel_layer;
 pub use tracing_impl::register_signal_handler;
 pub use tracing_impl::setup_tracing;
+pub use tracing_impl::setup_tracing_stdout_only;
 
 #[derive(Debug)]
 pub struct Scenario {
     name: String,
+    /// The simulation time at which the simulation stops. Ignored if a stop condition is set with
+    /// [`with_stop_condition`](Self::with_stop_condition).
     pub duration: Option<f64>,
+    stop_condition: Option<StopCondition>,
     pub state: Universe,
     pub pre_systems: Systems,
     pub simulation_systems: Systems,
     pub post_systems: Systems,
+    metadata: HashMap<String, String>,
+    run_post_on_initial_state: bool,
 }
 
 impl Scenario {
@@ -41,16 +68,70 @@ impl Scenario {
         Self {
             name: name.into(),
             duration: None,
+            stop_condition: None,
             state: Default::default(),
             pre_systems: Default::default(),
             simulation_systems: Default::default(),
             post_systems: Default::default(),
+            metadata: Default::default(),
+            run_post_on_initial_state: true,
         }
     }
 
     pub fn name(&self) -> &str {
         &self.name
     }
+
+    /// Sets the condition that determines when the simulation stops.
+    ///
+    /// This takes precedence over [`duration`](Self::duration).
+    pub fn with_stop_condition(mut self, stop_condition: StopCondition) -> Self {
+        self.stop_condition = Some(stop_condition);
+        self
+    }
+
+    /// The condition that determines when the simulation stops, if any.
+    ///
+    /// If no stop condition has been set, the duration is used as a [`StopCondition::SimTime`] condition.
+    pub fn stop_condition(&self) -> Option<StopCondition> {
+        self.stop_condition
+            .clone()
+            .or_else(|| self.duration.map(StopCondition::SimTime))
+    }
+
+    /// Sets whether the post systems run on the initial state, before the first step is taken.
+    ///
+    /// This is enabled by default, so that for example systems that output data after every step also
+    /// output the initial state. Disabling it can be useful if the output is expensive.
+    pub fn run_post_on_initial_state(mut self, run_post_on_initial_state: bool) -> Self {
+        self.run_post_on_initial_state = run_post_on_initial_state;
+        self
+    }
+
+    /// Attaches the given key-value pair to the metadata of the scenario.
+    ///
+    /// The metadata is logged when the scenario starts and inserted into the state as a [`ScenarioMetadata`]
+    /// component, so that it is also stored in checkpoints.
+    pub fn with_metadata(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.metadata.insert(key.into(), value.into());
+        self
+    }
+
+    pub fn metadata(&self) -> &HashMap<String, String> {
+        &self.metadata
+    }
+
+    /// Logs the metadata and inserts it into the state.
+    fn emit_metadata(&mut self) {
+        if !self.metadata.is_empty() {
+            // Sort the entries so that the logged metadata is deterministic
+            let sorted_metadata: BTreeMap<_, _> = self.metadata.iter().collect();
+            let metadata = serde_json::to_string(&sorted_metadata).expect("string maps are always serializable");
+            info!(metadata, "Scenario metadata: {}", metadata);
+            self.state
+                .insert_storage(ImmutableSingularStorage::new(ScenarioMetadata(self.metadata.clone())));
+        }
+    }
 }
 
 pub struct DynamecsApp<Config = ()> {
@@ -61,8 +142,30 @@ pub struct DynamecsApp<Config = ()> {
     max_steps: Option<usize>,
     /// Optionally restore the simulation state from the given checkpoint file
     restore_from_checkpoint: Option<PathBuf>,
+    /// Whether to prefer the step index in the checkpoint file name over the one stored in the checkpoint
+    prefer_checkpoint_file_name_step_index: bool,
     /// Optional system for writing checkpoints
-    checkpoint_system: Option<Box<dyn System>>,
+    checkpoint_system: Option<Box<dyn CheckpointSystem>>,
+    /// The format of checkpoints written by the default checkpoint system and of the final checkpoint
+    checkpoint_format: CheckpointFormat,
+    /// Checkpoints are only written when the step index is a multiple of this interval
+    checkpoint_interval: usize,
+    /// Optionally keep only this number of most recent checkpoint files on disk
+    keep_last_checkpoints: Option<usize>,
+    /// Optionally stop the simulation once this wall-clock time has elapsed
+    max_wall_time: Option<Duration>,
+    /// Optionally seed the global random number generator with this seed
+    seed: Option<u64>,
+    /// Whether to write a checkpoint when the simulation is terminated by a signal
+    checkpoint_on_termination: bool,
+    /// Optionally inserts the configuration into the state of the scenario
+    config_inserter: Option<fn(&Config, &mut Universe)>,
+    /// Optionally serializes the configuration, which is then written to the scenario output directory
+    config_serializer: Option<fn(&Config) -> serde_json::Result<String>>,
+    /// Whether to write a single checkpoint after the simulation has ended
+    write_final_checkpoint: bool,
+    /// Optionally write the progress of the simulation to the given file after every step
+    progress_file: Option<PathBuf>,
 }
 
 impl<Config> DynamecsApp<Config> {
@@ -73,7 +176,18 @@ impl<Config> DynamecsApp<Config> {
             dt_override: None,
             max_steps: None,
             restore_from_checkpoint: None,
+            prefer_checkpoint_file_name_step_index: false,
             checkpoint_system: None,
+            checkpoint_format: CheckpointFormat::default(),
+            checkpoint_interval: 1,
+            keep_last_checkpoints: None,
+            max_wall_time: None,
+            seed: None,
+            checkpoint_on_termination: false,
+            config_inserter: None,
+            config_serializer: None,
+            write_final_checkpoint: false,
+            progress_file: None,
         }
     }
 
@@ -93,6 +207,10 @@ impl<Config> DynamecsApp<Config> {
             .state
             .insert_storage(ImmutableSingularStorage::new(app_settings));
 
+        if let Some(insert_config) = self.config_inserter {
+            insert_config(&self.config, &mut scenario.state);
+        }
+
         if let Some(dt) = self.dt_override {
             info!("Overriding time step dt = {}", dt);
             scenario
@@ -106,11 +224,129 @@ impl<Config> DynamecsApp<Config> {
 
     /// Enables or disables writing checkpoints for the app.
     pub fn write_checkpoints(mut self, enable_write_checkpoints: bool) -> Self {
-        self.checkpoint_system = enable_write_checkpoints.then(|| compressed_binary_checkpointing_system().into());
+        if enable_write_checkpoints {
+            self = self.write_checkpoints_in_format(CheckpointFormat::default());
+        } else {
+            self.checkpoint_system = None;
+        }
+        self
+    }
+
+    /// Enables writing checkpoints for the app in the given format.
+    ///
+    /// The final checkpoint written with [`write_final_checkpoint`](Self::write_final_checkpoint) uses
+    /// the same format.
+    pub fn write_checkpoints_in_format(mut self, format: CheckpointFormat) -> Self {
+        self.checkpoint_system = Some(checkpointing_system(format));
+        self.checkpoint_format = format;
+        self
+    }
+
+    /// Enables writing checkpoints for the app on a background thread, so that the simulation loop
+    /// does not have to wait for checkpoints to be written to disk. The state is still serialized in the
+    /// simulation loop.
+    ///
+    /// The backpressure determines what happens when a checkpoint is due while the previous checkpoint is
+    /// still being written.
+    pub fn write_checkpoints_in_background(mut self, backpressure: CheckpointBackpressure) -> Self {
+        self.checkpoint_system = Some(Box::new(background_checkpointing_system(backpressure)));
+        self
+    }
+
+    /// Only writes checkpoints on every `steps`-th step, that is when the step index is a multiple of `steps`.
+    ///
+    /// The state after the final step is always checkpointed, even if the final step index is not a
+    /// multiple of the interval. The default interval is 1, so that a checkpoint is written after every step.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `steps` is zero.
+    pub fn checkpoint_interval(mut self, steps: usize) -> Self {
+        assert!(steps > 0, "checkpoint interval must be positive");
+        self.checkpoint_interval = steps;
+        self
+    }
+
+    /// Only keeps the given number of most recent checkpoint files on disk, so that long simulations do
+    /// not exhaust disk space.
+    ///
+    /// Older checkpoint files of the form `checkpoint_{n}.bin` or `checkpoint_{n}.json` are removed after
+    /// each checkpoint is written. Only checkpoint files written by the current run are removed.
+    /// Failures to remove files are logged as warnings. By default, all checkpoints are kept.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `keep_last` is `Some(0)`.
+    pub fn keep_last_checkpoints(mut self, keep_last: Option<usize>) -> Self {
+        assert!(keep_last != Some(0), "number of checkpoints to keep must be positive");
+        self.keep_last_checkpoints = keep_last;
+        self
+    }
+
+    /// Stops the simulation once the given wall-clock time has elapsed since the simulation started,
+    /// for example so that a job fits in a scheduler slot.
+    ///
+    /// The limit is checked before every step, so the step in progress is always completed and the final
+    /// state is consistent. This applies in addition to any other stop condition.
+    pub fn max_wall_time(mut self, max_wall_time: Duration) -> Self {
+        self.max_wall_time = Some(max_wall_time);
+        self
+    }
+
+    /// Seeds the global random number generator [`RngResource`] with the given seed, so that systems
+    /// drawing from [`dynamecs::rng`] produce reproducible results.
+    ///
+    /// When restoring from a checkpoint that contains a random number generator, the restored
+    /// generator is used instead, so that the stream of random numbers continues where it left off.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Enables or disables writing a checkpoint when the simulation is terminated by a signal, such as Ctrl+C.
+    ///
+    /// This requires the signal handler to be registered with [`register_signal_handler`]. On the first signal,
+    /// the simulation stops after the current step, a checkpoint `checkpoint_{n}` of the current step is written
+    /// and [`run`](Self::run) returns a [`TerminationRequested`] error. If checkpoints are enabled with
+    /// [`write_checkpoints`](Self::write_checkpoints) or similar, pending checkpoints are completed and the
+    /// checkpoint is written with the configured format. Otherwise, the format given with `--checkpoint-format`
+    /// is used, or the default format if the app is not configured from the command line.
+    pub fn checkpoint_on_termination(mut self, enable: bool) -> Self {
+        self.checkpoint_on_termination = enable;
+        self
+    }
+
+    /// Enables or disables writing a single checkpoint `final.bin` after the simulation has ended.
+    ///
+    /// This is independent of the per-step checkpoints enabled by [`write_checkpoints`](Self::write_checkpoints).
+    /// If a format is set with [`write_checkpoints_in_format`](Self::write_checkpoints_in_format), the final
+    /// checkpoint is written in that format instead, for example to `final.json`.
+    pub fn write_final_checkpoint(mut self, enable_write_final_checkpoint: bool) -> Self {
+        self.write_final_checkpoint = enable_write_final_checkpoint;
+        self
+    }
+
+    /// Writes the progress of the simulation to the given file after every step, for external monitoring.
+    ///
+    /// The file holds a JSON object with the fields `step_index`, `simulation_time` and `wall_seconds`,
+    /// and is replaced atomically so that readers never observe partially written content.
+    pub fn with_progress_file<P: Into<PathBuf>>(mut self, progress_path: P) -> Self {
+        self.progress_file = Some(progress_path.into());
+        self
+    }
+
+    /// Whether to prefer the step index in the file name of a restored checkpoint, such as `checkpoint_12.bin`,
+    /// over the step index stored in the checkpoint when the two disagree.
+    pub fn prefer_checkpoint_file_name_step_index(mut self, prefer: bool) -> Self {
+        self.prefer_checkpoint_file_name_step_index = prefer;
         self
     }
 
     /// Restores a checkpoint from the given file when the app is run.
+    ///
+    /// The format of the checkpoint is inferred from the file extension, either `.bin` or `.json`.
+    /// If the path is a directory, the checkpoint file `checkpoint_{n}.bin` or `checkpoint_{n}.json`
+    /// with the highest step index in the directory is restored.
     pub fn restore_checkpoint<P: Into<PathBuf>>(mut self, checkpoint_path: P) -> Self {
         self.restore_from_checkpoint = Some(checkpoint_path.into());
         self
@@ -120,17 +356,63 @@ impl<Config> DynamecsApp<Config> {
     pub fn run(mut self) -> eyre::Result<()> {
         if let Some(scenario) = &mut self.scenario {
             // Register components of all systems
-            register_default_components();
+            let default_components = register_default_components();
             register_component::<DynamecsAppSettings>();
+            register_component::<ScenarioMetadata>();
             scenario.pre_systems.register_components();
             scenario.simulation_systems.register_components();
             scenario.post_systems.register_components();
+            for systems in [
+                &scenario.pre_systems,
+                &scenario.simulation_systems,
+                &scenario.post_systems,
+            ] {
+                for tag in systems.check_registrations() {
+                    warn!("Storage {tag} is accessed by a system, but is not registered for serialization");
+                }
+            }
 
             if let Some(checkpoint_path) = &self.restore_from_checkpoint {
-                let universe = restore_checkpoint_file(checkpoint_path)?;
+                let checkpoint_path = if checkpoint_path.is_dir() {
+                    latest_checkpoint_file(checkpoint_path)?
+                } else {
+                    checkpoint_path.clone()
+                };
+                let universe = restore_checkpoint_file(&checkpoint_path)?;
+                // Storages set up by the framework or by the scenario itself are expected regardless of the systems
+                let expected_tags: BTreeSet<_> = default_components
+                    .into_iter()
+                    .map(|(tag, _)| tag)
+                    .chain([
+                        <DynamecsAppSettings as Component>::Storage::tag(),
+                        <ScenarioMetadata as Component>::Storage::tag(),
+                    ])
+                    .chain(scenario.state.storage_tags())
+                    .collect();
+                let all_systems = [
+                    &scenario.pre_systems,
+                    &scenario.simulation_systems,
+                    &scenario.post_systems,
+                ];
+                let accessed_tags: BTreeSet<_> = all_systems
+                    .into_iter()
+                    .flat_map(|systems| systems.accessed_storage_tags())
+                    .collect();
+                let all_accesses_declared = all_systems
+                    .into_iter()
+                    .all(|systems| systems.all_declare_accesses());
+                warn_on_restored_storage_mismatch(&universe, &expected_tags, &accessed_tags, all_accesses_declared);
                 scenario.state = universe;
+                // The configuration may have changed since the checkpoint was written, so the current one takes precedence
+                if let Some(insert_config) = self.config_inserter {
+                    insert_config(&self.config, &mut scenario.state);
+                }
 
-                let step_index = get_step_index(&scenario.state).0;
+                let step_index = resolve_restored_step_index(
+                    &mut scenario.state,
+                    &checkpoint_path,
+                    self.prefer_checkpoint_file_name_step_index,
+                );
                 info!(
                     "Restored simulation state with step index {} from file \"{}\"",
                     step_index,
@@ -138,66 +420,101 @@ impl<Config> DynamecsApp<Config> {
                 );
             }
 
-            info!("Starting simulation of scenario \"{}\"", scenario.name());
-            loop {
-                let state = &mut scenario.state;
-                let SimulationTime(mut sim_time) = get_simulation_time(&*state);
-                let StepIndex(step_index) = get_step_index(&*state);
-                let TimeStep(dt) = get_time_step_or_set_default(state);
-
-                if let Some(max_steps) = self.max_steps {
-                    if step_index > max_steps {
-                        break;
-                    }
-                } else if let Some(duration) = scenario.duration {
-                    if sim_time >= duration {
-                        break;
-                    }
-                }
+            // Emit the metadata after restoring, since the metadata of this run should take precedence
+            scenario.emit_metadata();
 
-                // Note: We enter the step span *after* checking if we should abort the loop,
-                // so that we don't get an additional step span in the logs
-                let _span = info_span!("step", step_index).entered();
-
-                if step_index == 0 {
-                    // Post systems must run on the initial state in order to do post-initialization
-                    // For example, a system that outputs data after every simulation step should
-                    // also output the initial state
-                    debug!("Running post-systems for initial state");
-                    {
-                        let _span = info_span!("post_systems").entered();
-                        scenario.post_systems.run_all(state)?;
+            if let Some(rng) = scenario.state.try_get_component_storage::<RngResource>() {
+                let existing_seed = rng.get_component().seed();
+                if self.restore_from_checkpoint.is_some() {
+                    info!("Continuing random number generator with seed {existing_seed} from checkpoint");
+                    if self.seed.is_some_and(|seed| seed != existing_seed) {
+                        warn!("Ignoring seed in favor of the random number generator restored from checkpoint");
                     }
+                } else if let Some(seed) = self.seed {
+                    info!("Replacing random number generator with seed {existing_seed} inserted by the scenario");
+                    scenario
+                        .state
+                        .insert_storage(SingularStorage::new(RngResource::from_seed(seed)));
                 }
+            } else if let Some(seed) = self.seed {
+                scenario
+                    .state
+                    .insert_storage(SingularStorage::new(RngResource::from_seed(seed)));
+            }
+            if let Some(rng) = scenario.state.try_get_component_storage::<RngResource>() {
+                let seed = rng.get_component().seed();
+                info!(seed, "Using random number generator seed {seed}");
+            }
 
-                // TODO: Use some more better formatting here...
-                info!(
-                    "Starting step {} at simulation time {:3.5} (dt = {:3.5e})",
-                    step_index, sim_time, dt
-                );
-                {
-                    let _span = info_span!("pre_systems").entered();
-                    scenario.pre_systems.run_all(state)?;
-                }
-                {
-                    let _span = info_span!("simulation_systems").entered();
-                    scenario.simulation_systems.run_all(state)?;
-                }
+            if let Some(serialize_config) = self.config_serializer {
+                let config_json_str =
+                    serialize_config(&self.config).wrap_err("failed to serialize resolved configuration")?;
+                write_resolved_config(&config_json_str, &scenario.state)?;
+            }
 
-                sim_time += dt;
-                set_singular_component(state, SimulationTime(sim_time));
-                set_singular_component(state, StepIndex(step_index + 1));
+            if let Some(progress_path) = &self.progress_file {
+                scenario
+                    .post_systems
+                    .add_system(progress_file_system(progress_path));
+            }
 
-                {
-                    let _span = info_span!("post_systems").entered();
-                    scenario.post_systems.run_all(state)?;
+            info!("Starting simulation of scenario \"{}\"", scenario.name());
+            // The max steps given to the app take precedence over the stop condition of the scenario.
+            // The step with index `max_steps` is still taken. Since no step can have an index beyond
+            // `usize::MAX`, the maximum is then never reached.
+            let stop_condition = match self.max_steps {
+                Some(max_steps) => max_steps
+                    .checked_add(1)
+                    .map_or_else(StopCondition::never, StopCondition::Steps),
+                None => scenario
+                    .stop_condition()
+                    .unwrap_or_else(StopCondition::never),
+            };
+            let stop_condition = match self.max_wall_time {
+                Some(max_wall_time) => stop_condition.or(StopCondition::WallTime(max_wall_time)),
+                None => stop_condition,
+            };
+            let mut checkpoint_system = self.checkpoint_system.take().map(|system| {
+                IntervalCheckpointingSystem::new(system, self.checkpoint_interval, self.keep_last_checkpoints)
+            });
+            let termination_guard = self
+                .checkpoint_on_termination
+                .then(TerminationGuard::register);
+            let result = run_scenario_steps(
+                scenario,
+                checkpoint_system
+                    .as_mut()
+                    .map(|system| system as &mut dyn System),
+                termination_guard.as_ref().map(TerminationGuard::flag),
+                &stop_condition,
+            );
+            drop(termination_guard);
+            if let Err(err) = result {
+                if err.is::<TerminationRequested>() {
+                    // Reuse the configured checkpoint system, so that pending background writes are completed
+                    // and the checkpoint is written in the configured format
+                    let mut checkpoint_system = checkpoint_system.unwrap_or_else(|| {
+                        let system = checkpointing_system(self.checkpoint_format);
+                        IntervalCheckpointingSystem::new(system, self.checkpoint_interval, None)
+                    });
+                    match checkpoint_system.finish(&scenario.state) {
+                        Ok(()) => info!("Wrote checkpoint before terminating"),
+                        Err(checkpoint_err) => {
+                            error!("Failed to write checkpoint before terminating: {checkpoint_err:?}")
+                        }
+                    }
                 }
+                return Err(err);
+            }
+            if let Some(checkpoint_system) = &mut checkpoint_system {
+                checkpoint_system
+                    .finish(&scenario.state)
+                    .wrap_err("failed to write checkpoint for final step")?;
+            }
 
-                if let Some(checkpoint_system) = &mut self.checkpoint_system {
-                    checkpoint_system
-                        .run(state)
-                        .wrap_err("failed to run checkpointing system")?;
-                }
+            if self.write_final_checkpoint {
+                write_final_checkpoint(&scenario.state, self.checkpoint_format)
+                    .wrap_err("failed to write final checkpoint")?;
             }
 
             info!("Simulation ended");
@@ -208,6 +525,123 @@ impl<Config> DynamecsApp<Config> {
     }
 }
 
+/// Runs the simulation loop of the scenario until the stop condition is satisfied.
+///
+/// If the termination flag is set, the loop stops before the next step with a [`TerminationRequested`] error.
+fn run_scenario_steps(
+    scenario: &mut Scenario,
+    mut checkpoint_system: Option<&mut dyn System>,
+    termination_flag: Option<&AtomicBool>,
+    stop_condition: &StopCondition,
+) -> eyre::Result<()> {
+    let start = Instant::now();
+    loop {
+        let state = &mut scenario.state;
+        let SimulationTime(mut sim_time) = get_simulation_time(&*state);
+        let StepIndex(step_index) = get_step_index(&*state);
+        let TimeStep(dt) = get_time_step_or_set_default(state);
+
+        if termination_flag.is_some_and(|flag| flag.load(Ordering::SeqCst)) {
+            warn!("Terminating simulation before step {step_index}");
+            return Err(TerminationRequested.into());
+        }
+
+        if let Some(condition) = stop_condition.satisfied_condition(step_index, sim_time, start.elapsed()) {
+            info!("Stopping simulation before step {step_index}: {condition}");
+            break;
+        }
+
+        // Note: We enter the step span *after* checking if we should abort the loop,
+        // so that we don't get an additional step span in the logs
+        let _span = info_span!("step", step_index).entered();
+        // Storages that track modifications, such as StepTrackedVecStorage, record the current step index
+        state.set_current_step(step_index);
+
+        if step_index == 0 && scenario.run_post_on_initial_state {
+            // Post systems must run on the initial state in order to do post-initialization
+            // For example, a system that outputs data after every simulation step should
+            // also output the initial state
+            debug!("Running post-systems for initial state");
+            {
+                set_singular_component(state, CurrentPhase(Phase::Post));
+                let _span = info_span!("post_systems").entered();
+                scenario.post_systems.run_all(state)?;
+            }
+        }
+
+        info!(
+            step_index,
+            sim_time,
+            dt,
+            "Starting step {} at simulation time {} (dt = {})",
+            step_index,
+            format_float_adaptive(sim_time),
+            format_float_adaptive(dt)
+        );
+        {
+            set_singular_component(state, CurrentPhase(Phase::Pre));
+            let _span = info_span!("pre_systems").entered();
+            scenario.pre_systems.run_all(state)?;
+        }
+        // Pre-systems may pause or unpause the simulation, so we check only after they have run
+        if is_paused(state) {
+            debug!("Simulation is paused, skipping simulation systems");
+        } else {
+            {
+                set_singular_component(state, CurrentPhase(Phase::Simulation));
+                let _span = info_span!("simulation_systems").entered();
+                scenario.simulation_systems.run_all(state)?;
+            }
+            sim_time += dt;
+        }
+
+        set_singular_component(state, SimulationTime(sim_time));
+        set_singular_component(state, StepIndex(step_index + 1));
+        state.set_current_step(step_index + 1);
+
+        {
+            set_singular_component(state, CurrentPhase(Phase::Post));
+            let _span = info_span!("post_systems").entered();
+            scenario.post_systems.run_all(state)?;
+        }
+
+        if let Some(checkpoint_system) = &mut checkpoint_system {
+            checkpoint_system
+                .run(state)
+                .wrap_err("failed to run checkpointing system")?;
+        }
+    }
+    Ok(())
+}
+
+/// Runs the scenario for exactly the given number of steps and returns the final state.
+///
+/// This is intended for testing scenarios in-process. In contrast to [`DynamecsApp::run`], no command-line
+/// arguments are parsed, `tracing` is not set up, no checkpoints are written, and [`DynamecsAppSettings`]
+/// is not inserted into the state. The scenario duration and stop condition are ignored.
+pub fn run_scenario_for_test(mut scenario: Scenario, steps: usize) -> eyre::Result<Universe> {
+    register_default_components();
+    register_component::<ScenarioMetadata>();
+    scenario.pre_systems.register_components();
+    scenario.simulation_systems.register_components();
+    scenario.post_systems.register_components();
+
+    scenario.emit_metadata();
+    run_scenario_steps(&mut scenario, None, None, &StopCondition::Steps(steps))?;
+    Ok(scenario.state)
+}
+
+/// Formats a floating-point number in fixed-point notation, or in scientific notation if it is very small
+/// or very large.
+fn format_float_adaptive(value: f64) -> String {
+    let magnitude = value.abs();
+    if magnitude == 0.0 || (1e-2..1e6).contains(&magnitude) || !magnitude.is_finite() {
+        format!("{value:.5}")
+    } else {
+        format!("{value:.5e}")
+    }
+}
+
 fn set_singular_component<C>(state: &mut Universe, component: C)
 where
     C: Serialize + for<'de> Deserialize<'de>,
@@ -216,6 +650,18 @@ where
     state.insert_storage(SingularStorage::new(component));
 }
 
+/// Writes the resolved configuration to the scenario output directory given by the app settings.
+fn write_resolved_config(config_json_str: &str, state: &Universe) -> eyre::Result<()> {
+    let output_dir = &try_get_settings(state)?.scenario_output_dir;
+    std::fs::create_dir_all(output_dir)
+        .wrap_err_with(|| format!("failed to create output directory \"{}\"", output_dir.display()))?;
+    let path = output_dir.join("config.resolved.json");
+    std::fs::write(&path, config_json_str)
+        .wrap_err_with(|| format!("failed to write resolved configuration to \"{}\"", path.display()))?;
+    info!("Wrote resolved configuration to {}", path.display());
+    Ok(())
+}
+
 fn get_time_step_or_set_default(state: &mut Universe) -> TimeStep {
     if let Some(storage) = state.try_get_component_storage::<TimeStep>() {
         storage.get_component().clone()
@@ -234,20 +680,30 @@ impl DynamecsApp<()> {
     {
         let opt = CliOptions::parse();
 
+        #[cfg(feature = "schemars")]
+        if opt.print_config_schema {
+            return Err(eyre!(
+                "printing the configuration schema requires calling `print_config_schema_if_requested` \
+                 before configuring the app, or `dynamecs_main!(initialize_scenario, with_config_schema)`"
+            ));
+        }
+
         info!("Output base path: {}", opt.output_dir.display());
 
-        if opt.config_file.is_some() && opt.config_string.is_some() {
+        if !opt.config_files.is_empty() && opt.config_string.is_some() {
             return Err(eyre!("config file and config string are mutually exclusive"));
         }
 
-        let initial_config: Config = if let Some(path) = opt.config_file {
-            info!("Reading config file from {}.", path.display());
-            let config_str =
-                read_to_string(&path).wrap_err_with(|| format!("failed to read config file at {}", path.display()))?;
-            json5::from_str(&config_str).wrap_err("failed to deserialize supplied JSON5 configuration file")
+        let initial_config: Config = if !opt.config_files.is_empty() {
+            let mut merged_config = config_override::read_and_merge_config_files(&opt.config_files)?;
+            config_override::interpolate_env_vars(&mut merged_config)?;
+            serde_json::from_value(merged_config).wrap_err("failed to deserialize supplied JSON5 configuration file")
         } else if let Some(config_str) = opt.config_string {
             info!("Using configuration provided from CLI interface");
-            json5::from_str(&config_str).wrap_err("failed to deserialize supplied JSON5 configuration string")
+            let mut config_json: serde_json::Value =
+                json5::from_str(&config_str).wrap_err("failed to deserialize supplied JSON5 configuration string")?;
+            config_override::interpolate_env_vars(&mut config_json)?;
+            serde_json::from_value(config_json).wrap_err("failed to deserialize supplied JSON5 configuration string")
         } else {
             let default_config_str = "{}";
             info!(
@@ -264,9 +720,17 @@ impl DynamecsApp<()> {
         let mut config_json =
             serde_json::to_value(initial_config).wrap_err("failed to serialize initial config as JSON")?;
 
-        if !opt.overrides.is_empty() {
+        // Overrides from the overrides file are applied first, so that overrides given directly
+        // on the command line take precedence
+        let mut overrides = match &opt.overrides_file {
+            Some(path) => config_override::read_overrides_file(path)?,
+            None => Vec::new(),
+        };
+        overrides.extend(opt.overrides);
+
+        if !overrides.is_empty() {
             let overridden_config: serde_json::Value =
-                config_override::apply_config_overrides(config_json, &opt.overrides)?;
+                config_override::apply_config_overrides(config_json, &overrides)?;
             config_json = serde_json::from_value(overridden_config).wrap_err_with(|| {
                 "invalid config overrides: cannot deserialize configuration from \
                 overridden configuration"
@@ -307,9 +771,23 @@ impl DynamecsApp<()> {
             }
         }
 
+        let max_wall_time = opt
+            .max_wall_time
+            .map(Duration::try_from_secs_f64)
+            .transpose()
+            .wrap_err("maximum wall-clock time must be a non-negative, representable number of seconds")?;
+
+        if opt.checkpoint_interval == 0 {
+            return Err(eyre!("checkpoint interval must be positive"));
+        }
+
+        if opt.keep_last_checkpoints == Some(0) {
+            return Err(eyre!("number of checkpoints to keep must be positive"));
+        }
+
         let checkpoint_system = opt
             .write_checkpoints
-            .then(|| compressed_binary_checkpointing_system().into());
+            .then(|| checkpointing_system(opt.checkpoint_format.into()));
 
         Ok(DynamecsApp {
             config,
@@ -317,11 +795,90 @@ impl DynamecsApp<()> {
             dt_override: opt.dt,
             max_steps: opt.max_steps,
             restore_from_checkpoint: opt.restore_checkpoint,
+            prefer_checkpoint_file_name_step_index: opt.prefer_checkpoint_file_name_step_index,
             checkpoint_system,
+            checkpoint_format: opt.checkpoint_format.into(),
+            checkpoint_interval: opt.checkpoint_interval,
+            keep_last_checkpoints: opt.keep_last_checkpoints,
+            max_wall_time,
+            seed: opt.seed,
+            checkpoint_on_termination: opt.checkpoint_on_termination,
+            config_inserter: None,
+            config_serializer: None,
+            write_final_checkpoint: opt.write_final_checkpoint,
+            progress_file: None,
         })
     }
 }
 
+/// Prints the JSON Schema of the configuration to stdout if the `--print-config-schema` flag is given,
+/// and returns whether the schema was printed.
+///
+/// If the schema was printed, the application should exit without running the scenario. This must be
+/// called before [`setup_tracing`], since the schema would otherwise be preceded by log output on stdout.
+/// The schema can be used for validation and autocompletion of configuration files in editors.
+#[cfg(feature = "schemars")]
+pub fn print_config_schema_if_requested<Config: schemars::JsonSchema>() -> bool {
+    let print_config_schema = CliOptions::parse().print_config_schema;
+    if print_config_schema {
+        println!("{}", config_schema_json::<Config>());
+    }
+    print_config_schema
+}
+
+/// Same as [`print_config_schema_if_requested`], but infers the configuration from the scenario initializer.
+///
+/// This is used by [`dynamecs_main`] and is not intended to be used directly.
+#[cfg(feature = "schemars")]
+#[doc(hidden)]
+pub fn print_config_schema_of_initializer_if_requested<Config, I>(_initializer: &I) -> bool
+where
+    Config: schemars::JsonSchema,
+    I: FnOnce(&Config) -> eyre::Result<Scenario>,
+{
+    print_config_schema_if_requested::<Config>()
+}
+
+/// Returns the JSON Schema of the configuration as pretty-printed JSON.
+#[cfg(feature = "schemars")]
+pub fn config_schema_json<Config: schemars::JsonSchema>() -> String {
+    let schema = schemars::schema_for!(Config);
+    serde_json::to_string_pretty(&schema).expect("JSON schemas are always serializable")
+}
+
+impl<Config> DynamecsApp<Config>
+where
+    Config: Clone + Serialize + for<'de> Deserialize<'de> + 'static,
+{
+    /// Inserts a copy of the configuration into the state of the scenario as a [`ConfigComponent`].
+    ///
+    /// This allows systems to access the configuration with [`get_config`](dynamecs::components::get_config)
+    /// instead of capturing it in the scenario initializer. The configuration is also inserted
+    /// if the scenario has already been initialized.
+    pub fn insert_config_into_state(mut self) -> Self {
+        let insert_config: fn(&Config, &mut Universe) = |config, state| {
+            register_component::<ConfigComponent<Config>>();
+            state.insert_storage(ImmutableSingularStorage::new(ConfigComponent(config.clone())));
+        };
+        if let Some(scenario) = &mut self.scenario {
+            insert_config(&self.config, &mut scenario.state);
+        }
+        self.config_inserter = Some(insert_config);
+        self
+    }
+
+    /// Writes the configuration as pretty-printed JSON to `config.resolved.json` in the scenario
+    /// output directory when the app is run.
+    ///
+    /// This stores the effective configuration after all overrides alongside the output of the
+    /// simulation. It is disabled by default, since the resolved configuration contains the
+    /// values of any interpolated environment variables, which may hold sensitive information.
+    pub fn write_resolved_config(mut self, enable: bool) -> Self {
+        self.config_serializer = enable.then_some(serde_json::to_string_pretty as fn(&Config) -> _);
+        self
+    }
+}
+
 /// Returns the intended root directory for app output.
 ///
 /// The returned path is relative to the current working directory.
@@ -362,10 +919,18 @@ pub fn get_default_output_dir() -> &'static Path {
 ///
 /// dynamecs_main!(initialize_scenario);
 /// ```
+///
+/// With the `schemars` feature, `dynamecs_main!(initialize_scenario, with_config_schema)` additionally
+/// supports the `--print-config-schema` flag, which requires the configuration to implement
+/// `schemars::JsonSchema`. The schema is printed before logging is set up, see
+/// [`print_config_schema_if_requested`](crate::print_config_schema_if_requested).
 #[macro_export]
 macro_rules! dynamecs_main {
-    ($scenario:expr) => {
+    (@main $scenario:expr, $print_config_schema:expr) => {
         fn main() -> Result<(), Box<dyn std::error::Error>> {
+            if $print_config_schema {
+                return Ok(());
+            }
             let _tracing_guard = $crate::setup_tracing()?;
             $crate::register_signal_handler()?;
             fn main_internal() -> Result<(), Box<dyn std::error::Error>> {
@@ -386,4 +951,221 @@ macro_rules! dynamecs_main {
             })
         }
     };
+    ($scenario:expr, with_config_schema) => {
+        $crate::dynamecs_main!(@main $scenario, $crate::print_config_schema_of_initializer_if_requested(&$scenario));
+    };
+    ($scenario:expr) => {
+        $crate::dynamecs_main!(@main $scenario, false);
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{run_scenario_steps, DynamecsApp, Scenario, StopCondition};
+    use dynamecs::adapters::FnSystem;
+    use dynamecs::components::{
+        get_config, get_step_index, register_default_components, DynamecsAppSettings, RngResource, StepIndex,
+    };
+    use dynamecs::rand_core::RngCore;
+    use dynamecs::storages::{ImmutableSingularStorage, SingularStorage, StepTrackedVecStorage};
+    use dynamecs::{register_component, Component, Universe};
+    use serde::{Deserialize, Serialize};
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    struct MockConfig {
+        resolution: usize,
+        name: String,
+        stiffness: f64,
+    }
+
+    #[test]
+    fn resolved_config_is_written_to_scenario_output_dir() {
+        let output_dir = tempfile::tempdir().unwrap();
+        let mut scenario = Scenario::default_with_name("resolved_config").with_stop_condition(StopCondition::Steps(1));
+        scenario
+            .state
+            .insert_storage(ImmutableSingularStorage::new(DynamecsAppSettings {
+                scenario_output_dir: output_dir.path().to_path_buf(),
+                scenario_name: "resolved_config".to_string(),
+            }));
+
+        let config = MockConfig {
+            resolution: 8,
+            name: "Bear".to_string(),
+            stiffness: 1e4,
+        };
+        let mut app = DynamecsApp::from_config_and_app_settings(config.clone()).write_resolved_config(true);
+        app.scenario = Some(scenario);
+        app.run().unwrap();
+
+        let config_json_str = std::fs::read_to_string(output_dir.path().join("config.resolved.json")).unwrap();
+        let resolved_config: MockConfig = serde_json::from_str(&config_json_str).unwrap();
+        assert_eq!(resolved_config, config);
+    }
+
+    #[test]
+    fn config_is_inserted_into_restored_state() {
+        register_default_components();
+        register_component::<DynamecsAppSettings>();
+        let output_dir = tempfile::tempdir().unwrap();
+        let mut checkpointed_state = Universe::default();
+        checkpointed_state.insert_storage(ImmutableSingularStorage::new(DynamecsAppSettings {
+            scenario_output_dir: output_dir.path().to_path_buf(),
+            scenario_name: "restored_config".to_string(),
+        }));
+        checkpointed_state.insert_storage(SingularStorage::new(StepIndex(2)));
+        let checkpoint_path = output_dir.path().join("checkpoint_2.bin");
+        let writer = snap::write::FrameEncoder::new(std::fs::File::create(&checkpoint_path).unwrap());
+        bincode::serialize_into(writer, &checkpointed_state).unwrap();
+
+        let config = MockConfig {
+            resolution: 8,
+            name: "Bear".to_string(),
+            stiffness: 1e4,
+        };
+        let resolutions = Arc::new(Mutex::new(Vec::new()));
+        let system_resolutions = resolutions.clone();
+        let mut scenario = Scenario::default_with_name("restored_config");
+        scenario
+            .simulation_systems
+            .add_system(FnSystem::new("read_config", move |universe| {
+                let resolution = get_config::<MockConfig>(universe)?.resolution;
+                system_resolutions.lock().unwrap().push(resolution);
+                Ok(())
+            }));
+        let mut app = DynamecsApp::from_config_and_app_settings(config);
+        app.scenario = Some(scenario);
+        let mut app = app
+            .insert_config_into_state()
+            .restore_checkpoint(&checkpoint_path);
+        app.max_steps = Some(3);
+        app.run().unwrap();
+
+        assert_eq!(*resolutions.lock().unwrap(), [8, 8]);
+    }
+
+    /// Runs three steps of a scenario whose single system draws a random number in every step.
+    fn random_draws_with_seed(seed: u64) -> Vec<u64> {
+        let draws = Arc::new(Mutex::new(Vec::new()));
+        let system_draws = draws.clone();
+        let mut scenario = Scenario::default_with_name("seed").with_stop_condition(StopCondition::Steps(3));
+        scenario
+            .simulation_systems
+            .add_system(FnSystem::new("draw", move |universe| {
+                let draw = dynamecs::rng(universe).next_u64();
+                system_draws.lock().unwrap().push(draw);
+                Ok(())
+            }));
+        let mut app = DynamecsApp::from_config_and_app_settings(()).seed(seed);
+        app.scenario = Some(scenario);
+        app.run().unwrap();
+
+        let draws = draws.lock().unwrap().clone();
+        draws
+    }
+
+    #[test]
+    fn runs_with_same_seed_produce_identical_random_draws() {
+        let draws = random_draws_with_seed(42);
+        assert_eq!(draws.len(), 3);
+        assert_eq!(draws, random_draws_with_seed(42));
+        assert_ne!(draws, random_draws_with_seed(43));
+    }
+
+    #[test]
+    fn serialized_rng_continues_random_stream() {
+        let mut rng = RngResource::from_seed(42);
+        rng.rng().next_u64();
+        let mut restored: RngResource = serde_json::from_str(&serde_json::to_string(&rng).unwrap()).unwrap();
+        assert_eq!(restored.seed(), 42);
+        assert_eq!(restored.rng().next_u64(), rng.rng().next_u64());
+    }
+
+    /// Runs two steps of a scenario and returns the step indices at which the post systems ran.
+    fn post_system_step_indices(run_post_on_initial_state: bool) -> Vec<usize> {
+        let step_indices = Arc::new(Mutex::new(Vec::new()));
+        let system_step_indices = step_indices.clone();
+        let mut scenario =
+            Scenario::default_with_name("post_on_initial_state").run_post_on_initial_state(run_post_on_initial_state);
+        scenario
+            .post_systems
+            .add_system(FnSystem::new("record_step_index", move |universe| {
+                let step_index = get_step_index(universe).0;
+                system_step_indices.lock().unwrap().push(step_index);
+                Ok(())
+            }));
+        run_scenario_steps(&mut scenario, None, None, &StopCondition::Steps(2)).unwrap();
+
+        let step_indices = step_indices.lock().unwrap().clone();
+        step_indices
+    }
+
+    #[test]
+    fn post_systems_on_initial_state_can_be_disabled() {
+        assert_eq!(post_system_step_indices(true), [0, 1, 2]);
+        assert_eq!(post_system_step_indices(false), [1, 2]);
+    }
+
+    #[test]
+    fn largest_max_steps_does_not_stop_simulation() {
+        let output_dir = tempfile::tempdir().unwrap();
+        let mut scenario = Scenario::default_with_name("largest_max_steps");
+        scenario
+            .state
+            .insert_storage(ImmutableSingularStorage::new(DynamecsAppSettings {
+                scenario_output_dir: output_dir.path().to_path_buf(),
+                scenario_name: "largest_max_steps".to_string(),
+            }));
+        let num_steps = Arc::new(Mutex::new(0));
+        let system_num_steps = num_steps.clone();
+        scenario
+            .simulation_systems
+            .add_system(FnSystem::new("sleep", move |_| {
+                *system_num_steps.lock().unwrap() += 1;
+                std::thread::sleep(Duration::from_millis(1));
+                Ok(())
+            }));
+
+        // The simulation is instead stopped by the wall-clock time limit
+        let mut app = DynamecsApp::from_config_and_app_settings(()).max_wall_time(Duration::from_millis(20));
+        app.scenario = Some(scenario);
+        app.max_steps = Some(usize::MAX);
+        app.run().unwrap();
+        assert!(*num_steps.lock().unwrap() > 0);
+    }
+
+    #[derive(Debug)]
+    struct Displacement(f64);
+
+    impl Component for Displacement {
+        type Storage = StepTrackedVecStorage<Self>;
+    }
+
+    #[test]
+    fn step_tracked_storages_record_step_index_of_simulation_loop() {
+        let mut scenario = Scenario::default_with_name("step_tracked");
+        let entity = scenario.state.new_entity();
+        scenario
+            .simulation_systems
+            .add_system(FnSystem::new("displace", move |universe| {
+                let step_index = get_step_index(universe).0;
+                // Only modify the component in the first and third step
+                if step_index != 1 {
+                    universe.insert_component(entity, Displacement(step_index as f64));
+                }
+                Ok(())
+            }));
+        run_scenario_steps(&mut scenario, None, None, &StopCondition::Steps(3)).unwrap();
+
+        let storage = scenario.state.get_component_storage::<Displacement>();
+        assert_eq!(
+            storage
+                .get_component(entity)
+                .map(|displacement| displacement.0),
+            Some(2.0)
+        );
+        assert_eq!(storage.last_modified_step(entity), Some(2));
+    }
 }
@@ -0,0 +1,573 @@
+//! Reusable observer systems for diagnostics and output.
+use dynamecs::components::{get_step_index, try_get_settings};
+use dynamecs::storages::VecStorage;
+use dynamecs::{Component, ObserverSystem, Universe};
+use dynamecs_analyze::timing::{
+    extract_single_step_timings, format_slowest_systems, group_records_by_step, AccumulatedStepTimings,
+    AccumulatedTimings,
+};
+use dynamecs_analyze::{Record, RecordKind};
+use eyre::WrapErr;
+use std::collections::HashMap;
+use std::fmt;
+use std::fmt::Debug;
+use std::fs;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::marker::PhantomData;
+use std::sync::mpsc::Receiver;
+use tracing::{debug, info, warn};
+
+/// Returns an observer that logs the bounding box and centroid of the positions of all entities with
+/// the component `P`.
+///
+/// The position of each component is obtained with `extract`. The summary is logged as an event
+/// with the fields `count`, `min_x`, `min_y`, `min_z`, `max_x`, `max_y`, `max_z`,
+/// `centroid_x`, `centroid_y` and `centroid_z`.
+pub fn spatial_summary_observer<P>(extract: impl Fn(&P) -> [f64; 3] + 'static) -> impl ObserverSystem
+where
+    P: Component<Storage = VecStorage<P>>,
+{
+    SpatialSummaryObserver {
+        extract,
+        marker: PhantomData,
+    }
+}
+
+struct SpatialSummaryObserver<P, F> {
+    extract: F,
+    marker: PhantomData<fn(&P)>,
+}
+
+impl<P, F> Debug for SpatialSummaryObserver<P, F> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "SpatialSummaryObserver<{}>", std::any::type_name::<P>())
+    }
+}
+
+impl<P, F> ObserverSystem for SpatialSummaryObserver<P, F>
+where
+    P: Component<Storage = VecStorage<P>>,
+    F: Fn(&P) -> [f64; 3],
+{
+    fn name(&self) -> String {
+        format!("SpatialSummaryObserver<{}>", std::any::type_name::<P>())
+    }
+
+    fn run(&mut self, universe: &Universe) -> eyre::Result<()> {
+        let mut min = [f64::INFINITY; 3];
+        let mut max = [f64::NEG_INFINITY; 3];
+        let mut sum = [0.0; 3];
+        let mut count = 0;
+        for (_, component) in universe.join::<&P>() {
+            let position = (self.extract)(component);
+            for i in 0..3 {
+                min[i] = min[i].min(position[i]);
+                max[i] = max[i].max(position[i]);
+                sum[i] += position[i];
+            }
+            count += 1;
+        }
+
+        let component = std::any::type_name::<P>();
+        if count == 0 {
+            debug!(component, count, "No positions to summarize");
+            return Ok(());
+        }
+
+        let centroid = sum.map(|s| s / count as f64);
+        info!(
+            component,
+            count,
+            min_x = min[0],
+            min_y = min[1],
+            min_z = min[2],
+            max_x = max[0],
+            max_y = max[1],
+            max_z = max[2],
+            centroid_x = centroid[0],
+            centroid_y = centroid[1],
+            centroid_z = centroid[2],
+            "Spatial summary"
+        );
+        Ok(())
+    }
+}
+
+/// Returns an observer that logs which of the storages with the given tags changed since the
+/// observer last ran.
+///
+/// Changes are detected by comparing storage versions, see
+/// [`Universe::storage_version_by_tag`]. The storages must therefore track a version, such as
+/// [`VersionedVecStorage`](dynamecs::storages::VersionedVecStorage), and their tags are
+/// usually obtained with [`Storage::tag`](dynamecs::Storage::tag). The first run only records
+/// the versions, and logs a warning for each tag that does not correspond to a versioned storage
+/// in the universe. In subsequent runs, an event with the fields `storage`, `previous_version` and
+/// `version` is logged for each changed storage.
+pub fn changed_storages_observer<T: Into<String>>(tags: impl IntoIterator<Item = T>) -> impl ObserverSystem {
+    ChangedStoragesObserver {
+        tags: tags.into_iter().map(Into::into).collect(),
+        previous_versions: None,
+    }
+}
+
+#[derive(Debug)]
+struct ChangedStoragesObserver {
+    tags: Vec<String>,
+    previous_versions: Option<HashMap<String, Option<u64>>>,
+}
+
+impl ObserverSystem for ChangedStoragesObserver {
+    fn name(&self) -> String {
+        "ChangedStoragesObserver".to_string()
+    }
+
+    fn run(&mut self, universe: &Universe) -> eyre::Result<()> {
+        let versions: HashMap<_, _> = self
+            .tags
+            .iter()
+            .map(|tag| (tag.clone(), universe.storage_version_by_tag(tag)))
+            .collect();
+
+        if let Some(previous_versions) = &self.previous_versions {
+            let mut num_changed = 0;
+            for tag in &self.tags {
+                let previous_version = previous_versions[tag];
+                let version = versions[tag];
+                if version != previous_version {
+                    info!(storage = tag, previous_version, version, "Storage changed");
+                    num_changed += 1;
+                }
+            }
+            if num_changed == 0 {
+                debug!(num_tracked = self.tags.len(), "No tracked storages changed");
+            }
+        } else {
+            for tag in &self.tags {
+                if versions[tag].is_none() {
+                    warn!(
+                        storage = tag,
+                        "Tracked storage does not exist or does not track a version"
+                    );
+                }
+            }
+        }
+
+        self.previous_versions = Some(versions);
+        Ok(())
+    }
+}
+
+/// Returns an observer that periodically prints the `top_n` slowest systems to stdout.
+///
+/// The observer analyzes the records received from `records`, which is typically connected to a
+/// [`record_channel_layer`](crate::record_channel_layer) in the active subscriber. Every
+/// `report_interval` runs, the slowest systems of the steps completed since the previous report are
+/// printed, see [`AccumulatedTimings::slowest_systems`] for how systems are identified.
+///
+/// # Panics
+///
+/// Panics if `report_interval` is zero.
+pub fn slowest_systems_observer(
+    records: Receiver<Record>,
+    top_n: usize,
+    report_interval: usize,
+) -> impl ObserverSystem {
+    slowest_systems_observer_with_output(records, top_n, report_interval, std::io::stdout())
+}
+
+/// Same as [`slowest_systems_observer`], but writes the reports to `output` instead of stdout.
+fn slowest_systems_observer_with_output<W: Write + Debug + 'static>(
+    records: Receiver<Record>,
+    top_n: usize,
+    report_interval: usize,
+    output: W,
+) -> SlowestSystemsObserver<W> {
+    assert!(report_interval > 0, "report interval must be positive");
+    SlowestSystemsObserver {
+        records,
+        output,
+        top_n,
+        report_interval,
+        num_runs: 0,
+        pending_records: Vec::new(),
+        completed_steps: Vec::new(),
+    }
+}
+
+#[derive(Debug)]
+struct SlowestSystemsObserver<W> {
+    records: Receiver<Record>,
+    output: W,
+    top_n: usize,
+    report_interval: usize,
+    num_runs: usize,
+    /// Records of the step that is currently in progress.
+    pending_records: Vec<Record>,
+    completed_steps: Vec<AccumulatedStepTimings>,
+}
+
+impl<W> SlowestSystemsObserver<W> {
+    /// Processes the received records and returns a report if one is due.
+    fn update(&mut self) -> eyre::Result<Option<String>> {
+        self.pending_records.extend(self.records.try_iter());
+        let is_step_exit = |record: &Record| {
+            record.kind() == RecordKind::SpanExit
+                && record.target() == "dynamecs_app"
+                && record.span().map(|span| span.name()) == Some("step")
+        };
+        if let Some(last_step_exit) = self.pending_records.iter().rposition(is_step_exit) {
+            let remaining_records = self.pending_records.split_off(last_step_exit + 1);
+            let completed_records = std::mem::replace(&mut self.pending_records, remaining_records);
+            for (_, step_records) in group_records_by_step(completed_records) {
+                if let Some(step) = extract_single_step_timings(step_records)? {
+                    self.completed_steps.push(step);
+                }
+            }
+        }
+
+        self.num_runs += 1;
+        if !self.num_runs.is_multiple_of(self.report_interval) || self.completed_steps.is_empty() {
+            return Ok(None);
+        }
+
+        let steps = std::mem::take(&mut self.completed_steps);
+        let first_step_index = steps
+            .first()
+            .map(|step| step.step_index)
+            .unwrap_or_default();
+        let last_step_index = steps.last().map(|step| step.step_index).unwrap_or_default();
+        let mut timings = AccumulatedTimings::new();
+        timings.merge_with_others(steps.iter().map(|step| &step.timings));
+        Ok(Some(format!(
+            "Slowest systems in steps {first_step_index}-{last_step_index}:\n{}",
+            format_slowest_systems(&timings, self.top_n)
+        )))
+    }
+}
+
+impl<W: Write + Debug> ObserverSystem for SlowestSystemsObserver<W> {
+    fn name(&self) -> String {
+        "SlowestSystemsObserver".to_string()
+    }
+
+    fn run(&mut self, _universe: &Universe) -> eyre::Result<()> {
+        if let Some(report) = self.update()? {
+            write!(self.output, "{report}").wrap_err("failed to write slowest systems report")?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns an observer that writes one output file per step, for example for exporting meshes to VTK.
+///
+/// The files are written to `<scenario output dir>/<subdir>/frame_<step index>.<extension>`, where
+/// the scenario output directory is obtained from
+/// [`DynamecsAppSettings`](dynamecs::components::DynamecsAppSettings). The directory is created if
+/// it does not exist. The contents of each file are written by `write_fn`, which receives a
+/// buffered writer for the file and the current state.
+pub fn file_per_step_observer<F>(
+    subdir: impl Into<String>,
+    extension: impl Into<String>,
+    write_fn: F,
+) -> impl ObserverSystem
+where
+    F: FnMut(&mut dyn Write, &Universe) -> eyre::Result<()>,
+{
+    FilePerStepObserver {
+        subdir: subdir.into(),
+        extension: extension.into(),
+        write_fn,
+    }
+}
+
+struct FilePerStepObserver<F> {
+    subdir: String,
+    extension: String,
+    write_fn: F,
+}
+
+impl<F> Debug for FilePerStepObserver<F> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("FilePerStepObserver")
+            .field("subdir", &self.subdir)
+            .field("extension", &self.extension)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<F> ObserverSystem for FilePerStepObserver<F>
+where
+    F: FnMut(&mut dyn Write, &Universe) -> eyre::Result<()>,
+{
+    fn name(&self) -> String {
+        format!("FilePerStepObserver({})", self.subdir)
+    }
+
+    fn run(&mut self, universe: &Universe) -> eyre::Result<()> {
+        let dir = try_get_settings(universe)?
+            .scenario_output_dir
+            .join(&self.subdir);
+        fs::create_dir_all(&dir)
+            .wrap_err_with(|| format!("failed to create output directory \"{}\"", dir.display()))?;
+
+        let step_index = get_step_index(universe).0;
+        let path = dir.join(format!("frame_{step_index}.{}", self.extension));
+        let file = File::create(&path).wrap_err_with(|| format!("failed to create file \"{}\"", path.display()))?;
+        let mut writer = BufWriter::new(file);
+        (self.write_fn)(&mut writer, universe)
+            .wrap_err_with(|| format!("failed to write output to file \"{}\"", path.display()))?;
+        writer
+            .flush()
+            .wrap_err_with(|| format!("failed to write output to file \"{}\"", path.display()))?;
+        debug!(file = %path.display(), "Wrote output file");
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        changed_storages_observer, file_per_step_observer, slowest_systems_observer_with_output,
+        spatial_summary_observer,
+    };
+    use crate::{record_channel_layer, run_scenario_steps, Scenario, StopCondition};
+    use dynamecs::components::{get_step_index, DynamecsAppSettings};
+    use dynamecs::storages::{ImmutableSingularStorage, VecStorage, VersionedVecStorage};
+    use dynamecs::{Component, ObserverSystem, Storage, Universe};
+    use dynamecs_analyze::RecordKind;
+    use serde::{Deserialize, Serialize};
+    use serde_json::json;
+    use std::cell::RefCell;
+    use std::io::Write;
+    use std::rc::Rc;
+    use std::sync::mpsc::channel;
+    use tracing_subscriber::prelude::*;
+    use tracing_subscriber::Registry;
+
+    #[derive(Debug, Serialize, Deserialize)]
+    struct Position([f64; 3]);
+
+    impl Component for Position {
+        type Storage = VecStorage<Self>;
+    }
+
+    #[derive(Debug)]
+    struct Velocity(f64);
+
+    impl Component for Velocity {
+        type Storage = VersionedVecStorage<Self>;
+    }
+
+    #[derive(Debug)]
+    struct DampVelocities;
+
+    impl dynamecs::System for DampVelocities {
+        fn name(&self) -> String {
+            "DampVelocities".to_string()
+        }
+
+        fn run(&mut self, universe: &mut Universe) -> eyre::Result<()> {
+            for velocity in universe
+                .get_component_storage_mut::<Velocity>()
+                .components_mut()
+            {
+                velocity.0 *= 0.5;
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn changed_storages_observer_reports_changed_storage() {
+        let mut universe = Universe::default();
+        let entity = universe.new_entity();
+        universe.insert_component(entity, Velocity(1.0));
+
+        let tag = VersionedVecStorage::<Velocity>::tag();
+        let mut observer = changed_storages_observer([tag.clone()]);
+        let mut system = DampVelocities;
+        let (sender, receiver) = channel();
+        let subscriber = Registry::default().with(record_channel_layer(sender));
+        tracing::subscriber::with_default(subscriber, || -> eyre::Result<()> {
+            // The first run only records the initial versions
+            observer.run(&universe)?;
+            dynamecs::System::run(&mut system, &mut universe)?;
+            observer.run(&universe)?;
+            // Nothing changes between these two runs
+            observer.run(&universe)
+        })
+        .unwrap();
+
+        let changed: Vec<_> = receiver
+            .try_iter()
+            .filter(|record| record.kind() == RecordKind::Event)
+            .filter(|record| record.message() == Some("Storage changed"))
+            .collect();
+        assert_eq!(changed.len(), 1);
+        let fields = changed[0].fields();
+        assert_eq!(fields["storage"], json!(tag));
+        assert!(fields["version"].as_u64().unwrap() > fields["previous_version"].as_u64().unwrap());
+    }
+
+    #[test]
+    fn changed_storages_observer_warns_about_unknown_tags() {
+        let mut universe = Universe::default();
+        universe.get_component_storage_mut::<Position>();
+
+        let unversioned_tag = VecStorage::<Position>::tag();
+        let mut observer = changed_storages_observer([unversioned_tag.clone(), "missing".to_string()]);
+        let (sender, receiver) = channel();
+        let subscriber = Registry::default().with(record_channel_layer(sender));
+        tracing::subscriber::with_default(subscriber, || -> eyre::Result<()> {
+            observer.run(&universe)?;
+            // Warnings are only logged on the first run
+            observer.run(&universe)
+        })
+        .unwrap();
+
+        let warned_storages: Vec<_> = receiver
+            .try_iter()
+            .filter(|record| record.level() == dynamecs_analyze::Level::Warn)
+            .map(|record| record.fields()["storage"].clone())
+            .collect();
+        assert_eq!(warned_storages, [json!(unversioned_tag), json!("missing")]);
+    }
+
+    #[derive(Debug)]
+    struct SleepingSystem {
+        millis: u64,
+    }
+
+    impl dynamecs::System for SleepingSystem {
+        fn name(&self) -> String {
+            format!("SleepingSystem({})", self.millis)
+        }
+
+        fn run(&mut self, _universe: &mut Universe) -> eyre::Result<()> {
+            // Span names must be static, so we pick the name based on the duration
+            let _span = if self.millis > 5 {
+                tracing::info_span!("slow_system").entered()
+            } else {
+                tracing::info_span!("fast_system").entered()
+            };
+            std::thread::sleep(std::time::Duration::from_millis(self.millis));
+            Ok(())
+        }
+    }
+
+    /// Collects the output of an observer, so that it can be inspected after the observer has been
+    /// moved into a scenario.
+    #[derive(Debug, Clone, Default)]
+    struct SharedOutput(Rc<RefCell<Vec<u8>>>);
+
+    impl Write for SharedOutput {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.borrow_mut().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn slowest_systems_observer_lists_systems_by_duration() {
+        let mut scenario = Scenario::default_with_name("slowest_systems");
+        scenario
+            .simulation_systems
+            .add_system(SleepingSystem { millis: 1 });
+        scenario
+            .simulation_systems
+            .add_system(SleepingSystem { millis: 10 });
+
+        let (sender, receiver) = channel();
+        let output = SharedOutput::default();
+        scenario
+            .post_systems
+            .add_system(slowest_systems_observer_with_output(receiver, 2, 3, output.clone()));
+        let subscriber = Registry::default().with(record_channel_layer(sender));
+        tracing::subscriber::with_default(subscriber, || {
+            run_scenario_steps(&mut scenario, None, None, &StopCondition::Steps(6)).unwrap();
+        });
+
+        // The observer runs after the simulation systems but before the step ends, so each report
+        // covers the steps completed before the observer ran
+        let output = String::from_utf8(output.0.borrow().clone()).unwrap();
+        let lines: Vec<_> = output.lines().collect();
+        assert_eq!(lines.len(), 6, "unexpected output:\n{output}");
+        assert_eq!(lines[0], "Slowest systems in steps 0-0:");
+        assert!(lines[1].ends_with("1x  slow_system (simulation_systems)"));
+        assert!(lines[2].ends_with("1x  fast_system (simulation_systems)"));
+        assert_eq!(lines[3], "Slowest systems in steps 1-3:");
+        assert!(lines[4].ends_with("3x  slow_system (simulation_systems)"));
+        assert!(lines[5].ends_with("3x  fast_system (simulation_systems)"));
+    }
+
+    #[test]
+    fn file_per_step_observer_writes_file_for_every_step() {
+        let output_dir = tempfile::tempdir().unwrap();
+        let mut scenario = Scenario::default_with_name("file_per_step");
+        scenario
+            .state
+            .insert_storage(ImmutableSingularStorage::new(DynamecsAppSettings {
+                scenario_output_dir: output_dir.path().to_path_buf(),
+                scenario_name: "file_per_step".to_string(),
+            }));
+        scenario
+            .post_systems
+            .add_system(file_per_step_observer("frames", "txt", |writer, universe| {
+                writeln!(writer, "step {}", get_step_index(universe).0)?;
+                Ok(())
+            }));
+        run_scenario_steps(&mut scenario, None, None, &StopCondition::Steps(3)).unwrap();
+
+        let frames_dir = output_dir.path().join("frames");
+        let mut file_names: Vec<_> = std::fs::read_dir(&frames_dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        file_names.sort();
+        assert_eq!(file_names, ["frame_0.txt", "frame_1.txt", "frame_2.txt", "frame_3.txt"]);
+        for step_index in 0..=3 {
+            let contents = std::fs::read_to_string(frames_dir.join(format!("frame_{step_index}.txt"))).unwrap();
+            assert_eq!(contents, format!("step {step_index}\n"));
+        }
+    }
+
+    #[test]
+    fn spatial_summary_observer_logs_bounds() {
+        let mut universe = Universe::default();
+        for position in [[0.0, 1.0, -2.0], [2.0, -1.0, 0.0], [4.0, 3.0, 5.0]] {
+            let entity = universe.new_entity();
+            universe.insert_component(entity, Position(position));
+        }
+
+        let mut observer = spatial_summary_observer(|position: &Position| position.0);
+        let (sender, receiver) = channel();
+        let subscriber = Registry::default().with(record_channel_layer(sender));
+        tracing::subscriber::with_default(subscriber, || observer.run(&universe).unwrap());
+
+        let records: Vec<_> = receiver.try_iter().collect();
+        let event = records
+            .iter()
+            .find(|record| record.kind() == RecordKind::Event)
+            .expect("summary must be logged");
+        assert_eq!(event.message(), Some("Spatial summary"));
+        let fields = event.fields();
+        assert_eq!(fields["count"], json!(3));
+        assert_eq!(
+            [&fields["min_x"], &fields["min_y"], &fields["min_z"]],
+            [&json!(0.0), &json!(-1.0), &json!(-2.0)]
+        );
+        assert_eq!(
+            [&fields["max_x"], &fields["max_y"], &fields["max_z"]],
+            [&json!(4.0), &json!(3.0), &json!(5.0)]
+        );
+        assert_eq!(
+            [&fields["centroid_x"], &fields["centroid_y"], &fields["centroid_z"]],
+            [&json!(2.0), &json!(1.0), &json!(1.0)]
+        );
+    }
+}
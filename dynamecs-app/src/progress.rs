@@ -0,0 +1,96 @@
+//! Reporting of simulation progress to a file for external monitoring.
+use dynamecs::components::{get_simulation_time, get_step_index};
+use dynamecs::{ObserverSystem, Universe};
+use eyre::Context;
+use serde::Serialize;
+use std::fs;
+use std::path::PathBuf;
+use std::time::Instant;
+
+#[derive(Debug, Serialize)]
+struct Progress {
+    step_index: usize,
+    simulation_time: f64,
+    wall_seconds: f64,
+}
+
+/// An observer that writes the current progress of the simulation as JSON to a file, overwriting
+/// any previous content.
+///
+/// The file is written atomically by first writing to a temporary file and then renaming it,
+/// so that readers never observe a partially written file.
+#[derive(Debug)]
+pub(crate) struct ProgressFileSystem {
+    path: PathBuf,
+    start: Instant,
+}
+
+pub(crate) fn progress_file_system(path: impl Into<PathBuf>) -> ProgressFileSystem {
+    ProgressFileSystem {
+        path: path.into(),
+        start: Instant::now(),
+    }
+}
+
+impl ObserverSystem for ProgressFileSystem {
+    fn name(&self) -> String {
+        "ProgressFileSystem".to_string()
+    }
+
+    fn run(&mut self, universe: &Universe) -> eyre::Result<()> {
+        let progress = Progress {
+            step_index: get_step_index(universe).0,
+            simulation_time: get_simulation_time(universe).0,
+            wall_seconds: self.start.elapsed().as_secs_f64(),
+        };
+
+        if let Some(parent) = self
+            .path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+        {
+            fs::create_dir_all(parent)
+                .wrap_err_with(|| format!("failed to create directory for progress file {}", self.path.display()))?;
+        }
+        let mut temp_file_name = self.path.file_name().unwrap_or_default().to_os_string();
+        temp_file_name.push(".tmp");
+        let temp_path = self.path.with_file_name(temp_file_name);
+        let json = serde_json::to_string(&progress)?;
+        fs::write(&temp_path, json)
+            .wrap_err_with(|| format!("failed to write progress file {}", temp_path.display()))?;
+        fs::rename(&temp_path, &self.path)
+            .wrap_err_with(|| format!("failed to move progress file into place at {}", self.path.display()))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{DynamecsApp, Scenario};
+    use dynamecs::components::TimeStep;
+    use dynamecs::storages::SingularStorage;
+    use serde_json::Value;
+
+    #[test]
+    fn progress_file_reflects_latest_step() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let output_dir = temp_dir.path().join("progress");
+        let progress_path = output_dir.join("progress.json");
+
+        let mut scenario = Scenario::default_with_name("progress");
+        scenario
+            .state
+            .insert_storage(SingularStorage::new(TimeStep(0.5)));
+        let mut app = DynamecsApp::from_config_and_app_settings(()).with_progress_file(&progress_path);
+        app.scenario = Some(scenario);
+        app.max_steps = Some(2);
+        app.run().unwrap();
+
+        let progress: Value = serde_json::from_str(&std::fs::read_to_string(&progress_path).unwrap()).unwrap();
+        assert_eq!(progress["step_index"], 3);
+        assert_eq!(progress["simulation_time"], 1.5);
+        assert!(progress["wall_seconds"].as_f64().unwrap() >= 0.0);
+        // The temporary file must not be left behind
+        assert_eq!(std::fs::read_dir(&output_dir).unwrap().count(), 1);
+    }
+}
@@ -0,0 +1,186 @@
+use std::fmt;
+use std::time::Duration;
+
+/// Determines when the simulation of a scenario stops.
+///
+/// The condition is evaluated at the start of every step, before the step is taken.
+#[derive(Debug, Clone, PartialEq)]
+pub enum StopCondition {
+    /// Stop once the given number of steps have been taken, that is when the step index reaches this number.
+    Steps(usize),
+    /// Stop once the simulation time reaches the given time.
+    SimTime(f64),
+    /// Stop once the given wall-clock time has elapsed since the simulation started.
+    ///
+    /// The step that is in progress when the time limit is reached is always completed.
+    WallTime(Duration),
+    /// Stop once any of the conditions is satisfied.
+    ///
+    /// An empty list of conditions is never satisfied.
+    Any(Vec<StopCondition>),
+}
+
+impl StopCondition {
+    /// A condition that is never satisfied, so that the simulation runs indefinitely.
+    pub fn never() -> Self {
+        Self::Any(Vec::new())
+    }
+
+    /// Determines whether the simulation should stop before taking the step with the given index.
+    ///
+    /// `elapsed` is the wall-clock time that has elapsed since the simulation started.
+    pub fn is_satisfied(&self, step_index: usize, sim_time: f64, elapsed: Duration) -> bool {
+        self.satisfied_condition(step_index, sim_time, elapsed)
+            .is_some()
+    }
+
+    /// Returns the first condition that is satisfied, if any.
+    ///
+    /// For [`StopCondition::Any`], this is one of the contained conditions, which explains why the
+    /// simulation stops.
+    pub fn satisfied_condition(&self, step_index: usize, sim_time: f64, elapsed: Duration) -> Option<&StopCondition> {
+        match self {
+            Self::Steps(steps) => (step_index >= *steps).then_some(self),
+            Self::SimTime(time) => (sim_time >= *time).then_some(self),
+            Self::WallTime(limit) => (elapsed >= *limit).then_some(self),
+            Self::Any(conditions) => conditions
+                .iter()
+                .find_map(|condition| condition.satisfied_condition(step_index, sim_time, elapsed)),
+        }
+    }
+
+    /// Combines this condition with another, so that the result is satisfied when either is satisfied.
+    pub fn or(self, other: StopCondition) -> Self {
+        match self {
+            Self::Any(mut conditions) => {
+                conditions.push(other);
+                Self::Any(conditions)
+            }
+            condition => Self::Any(vec![condition, other]),
+        }
+    }
+}
+
+impl fmt::Display for StopCondition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Steps(steps) => write!(f, "number of steps reached {steps}"),
+            Self::SimTime(time) => write!(f, "simulation time reached {time}"),
+            Self::WallTime(limit) => write!(f, "wall-clock time limit of {} s reached", limit.as_secs_f64()),
+            Self::Any(conditions) => {
+                write!(f, "any of [")?;
+                for (i, condition) in conditions.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{condition}")?;
+                }
+                write!(f, "]")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::StopCondition;
+    use crate::{run_scenario_steps, DynamecsApp, Scenario};
+    use dynamecs::adapters::FnSystem;
+    use dynamecs::components::{get_simulation_time, get_step_index, TimeStep};
+    use dynamecs::storages::SingularStorage;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    /// Runs a scenario with time step 0.5 until the condition is satisfied, and returns the final
+    /// step index and simulation time.
+    fn run_until(condition: StopCondition, system_sleep: Duration) -> (usize, f64) {
+        let mut scenario = Scenario::default_with_name("stop_condition").with_stop_condition(condition);
+        scenario
+            .state
+            .insert_storage(SingularStorage::new(TimeStep(0.5)));
+        scenario
+            .simulation_systems
+            .add_system(FnSystem::new("sleep", move |_| {
+                std::thread::sleep(system_sleep);
+                Ok(())
+            }));
+        let stop_condition = scenario.stop_condition().unwrap();
+        run_scenario_steps(&mut scenario, None, None, &stop_condition).unwrap();
+        (
+            get_step_index(&scenario.state).0,
+            get_simulation_time(&scenario.state).0,
+        )
+    }
+
+    #[test]
+    fn steps_condition_stops_after_number_of_steps() {
+        assert_eq!(run_until(StopCondition::Steps(3), Duration::ZERO), (3, 1.5));
+        assert_eq!(run_until(StopCondition::Steps(0), Duration::ZERO), (0, 0.0));
+    }
+
+    #[test]
+    fn sim_time_condition_stops_at_simulation_time() {
+        assert_eq!(run_until(StopCondition::SimTime(2.0), Duration::ZERO), (4, 2.0));
+        assert_eq!(run_until(StopCondition::SimTime(1.2), Duration::ZERO), (3, 1.5));
+    }
+
+    #[test]
+    fn wall_time_condition_stops_after_elapsed_time() {
+        let condition = StopCondition::WallTime(Duration::from_millis(50));
+        let (step_index, _) = run_until(condition, Duration::from_millis(20));
+        // Every step takes at least 20 ms, so the limit is reached after at most three steps
+        assert!((1..=3).contains(&step_index), "unexpected step index {step_index}");
+    }
+
+    #[test]
+    fn any_condition_stops_at_first_satisfied_condition() {
+        let condition = StopCondition::Steps(10).or(StopCondition::SimTime(1.0));
+        assert_eq!(
+            condition,
+            StopCondition::Any(vec![StopCondition::Steps(10), StopCondition::SimTime(1.0)])
+        );
+        assert_eq!(run_until(condition, Duration::ZERO), (2, 1.0));
+
+        let condition = StopCondition::SimTime(10.0).or(StopCondition::Steps(3));
+        assert_eq!(run_until(condition, Duration::ZERO), (3, 1.5));
+
+        assert!(!StopCondition::never().is_satisfied(usize::MAX, f64::INFINITY, Duration::MAX));
+
+        let condition = StopCondition::Steps(10).or(StopCondition::WallTime(Duration::from_secs(2)));
+        let satisfied = condition.satisfied_condition(3, 0.0, Duration::from_secs(5));
+        assert_eq!(satisfied, Some(&StopCondition::WallTime(Duration::from_secs(2))));
+        assert_eq!(satisfied.unwrap().to_string(), "wall-clock time limit of 2 s reached");
+    }
+
+    #[test]
+    fn app_stops_at_step_boundary_after_max_wall_time() {
+        let num_steps = Arc::new(AtomicUsize::new(0));
+        let system_num_steps = num_steps.clone();
+        let mut scenario = Scenario::default_with_name("max_wall_time");
+        scenario
+            .simulation_systems
+            .add_system(FnSystem::new("sleep", move |_| {
+                std::thread::sleep(Duration::from_millis(20));
+                system_num_steps.fetch_add(1, Ordering::SeqCst);
+                Ok(())
+            }));
+        let mut app = DynamecsApp::from_config_and_app_settings(()).max_wall_time(Duration::from_millis(30));
+        app.scenario = Some(scenario);
+        app.run().unwrap();
+
+        // Every step takes at least 20 ms, so the limit is reached after at most two steps
+        let num_steps = num_steps.load(Ordering::SeqCst);
+        assert!((1..=2).contains(&num_steps), "unexpected number of steps {num_steps}");
+    }
+
+    #[test]
+    fn duration_is_used_as_sim_time_condition() {
+        let mut scenario = Scenario::default_with_name("duration");
+        assert_eq!(scenario.stop_condition(), None);
+        scenario.duration = Some(2.0);
+        assert_eq!(scenario.stop_condition(), Some(StopCondition::SimTime(2.0)));
+        let scenario = scenario.with_stop_condition(StopCondition::Steps(1));
+        assert_eq!(scenario.stop_condition(), Some(StopCondition::Steps(1)));
+    }
+}
@@ -0,0 +1,87 @@
+//! Graceful termination of a running simulation when a signal such as Ctrl+C is received.
+use std::error::Error;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// The termination flag of the simulation that is currently running, if any.
+static ACTIVE_RUN: Mutex<Option<Arc<AtomicBool>>> = Mutex::new(None);
+
+/// Requests the currently running simulation to terminate after the current step.
+///
+/// Returns `true` if a running simulation will handle the request, and `false` if no simulation is
+/// registered or termination has already been requested, in which case the caller should abort.
+pub(crate) fn request_graceful_termination() -> bool {
+    ACTIVE_RUN
+        .lock()
+        .ok()
+        .and_then(|active_run| {
+            active_run
+                .as_ref()
+                .map(|flag| !flag.swap(true, Ordering::SeqCst))
+        })
+        .unwrap_or(false)
+}
+
+/// Registers a termination flag for the running simulation as long as the guard is alive.
+pub(crate) struct TerminationGuard {
+    flag: Arc<AtomicBool>,
+}
+
+impl TerminationGuard {
+    pub(crate) fn register() -> Self {
+        let flag = Arc::new(AtomicBool::new(false));
+        if let Ok(mut active_run) = ACTIVE_RUN.lock() {
+            *active_run = Some(flag.clone());
+        }
+        Self { flag }
+    }
+
+    pub(crate) fn flag(&self) -> &AtomicBool {
+        &self.flag
+    }
+}
+
+impl Drop for TerminationGuard {
+    fn drop(&mut self) {
+        if let Ok(mut active_run) = ACTIVE_RUN.lock() {
+            // Another run may have registered itself in the meantime, in which case it remains registered
+            if active_run
+                .as_ref()
+                .is_some_and(|flag| Arc::ptr_eq(flag, &self.flag))
+            {
+                *active_run = None;
+            }
+        }
+    }
+}
+
+/// Error returned when the simulation was terminated by a signal before it finished.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct TerminationRequested;
+
+impl fmt::Display for TerminationRequested {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "simulation was terminated by a signal")
+    }
+}
+
+impl Error for TerminationRequested {}
+
+#[cfg(test)]
+mod tests {
+    use super::{request_graceful_termination, TerminationGuard};
+    use std::sync::atomic::Ordering;
+
+    #[test]
+    fn dropping_previous_guard_keeps_current_run_registered() {
+        let previous = TerminationGuard::register();
+        let current = TerminationGuard::register();
+        drop(previous);
+
+        assert!(request_graceful_termination());
+        assert!(current.flag().load(Ordering::SeqCst));
+        drop(current);
+        assert!(!request_graceful_termination());
+    }
+}
@@ -1,29 +1,55 @@
 use crate::cli::CliOptions;
 use crate::get_output_dir;
-use chrono::Local;
+use crate::termination::request_graceful_termination;
+use chrono::{DateTime, Local};
 use clap::Parser;
-use eyre::WrapErr;
+use dynamecs_analyze::{write_binary_records, Level, Record, RecordBuilder, Span};
+use eyre::{eyre, WrapErr};
 use flate2::write::GzEncoder;
 use flate2::Compression;
+use serde_json::{Map, Value};
 use std::cmp::min;
+use std::fmt::Write as _;
 use std::fs::{create_dir_all, File};
 use std::io::Error as IoError;
-use std::io::{ErrorKind, Write};
-use std::path::Path;
+use std::io::{BufWriter, ErrorKind, Write};
+use std::iter;
+use std::net::TcpStream;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::Sender;
 use std::sync::{Arc, Mutex};
+use time::OffsetDateTime;
+use tracing::field::{Field, Visit};
 use tracing::metadata::LevelFilter;
-use tracing::{error, info};
-use tracing_subscriber::fmt::format::{FmtSpan, Writer};
+use tracing::span::{Attributes, Id};
+use tracing::{error, info, warn, Event, Metadata, Subscriber};
+use tracing_subscriber::filter::{Directive, EnvFilter};
+use tracing_subscriber::fmt::format::{FmtSpan, Format, Json, JsonFields, Writer};
 use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::layer::Context;
 use tracing_subscriber::prelude::*;
-use tracing_subscriber::{fmt, Registry};
+use tracing_subscriber::registry::{LookupSpan, SpanRef};
+use tracing_subscriber::{fmt, Layer, Registry};
 
 static TRACING_GUARD: Mutex<Option<TracingGuard>> = Mutex::new(None);
 
 /// Registers a signal handler that tries to ensure correct termination of logging
 /// in the presence of sudden program termination.
+///
+/// If the running app writes a checkpoint on termination (see
+/// [`DynamecsApp::checkpoint_on_termination`](crate::DynamecsApp::checkpoint_on_termination)),
+/// the first signal instead stops the simulation after the current step and writes a checkpoint.
+/// A second signal aborts the application immediately.
 pub fn register_signal_handler() -> eyre::Result<()> {
     ctrlc::set_handler(|| {
+        if request_graceful_termination() {
+            warn!(
+                target: "dynamecs_app",
+                "Received signal to terminate (for example Ctrl+C). Writing checkpoint after the current step \
+                 before exiting. Send the signal again to abort immediately."
+            );
+            return;
+        }
         error!(target: "dynamecs_app", "Received signal to terminate (for example Ctrl+C). Aborting application...");
         if let Ok(mut opt) = TRACING_GUARD.lock() {
             if let Some(guard) = opt.as_mut() {
@@ -43,18 +69,25 @@ pub fn register_signal_handler() -> eyre::Result<()> {
 ///
 /// TODO: Describe the tracing setup, i.e. log to stdout, file etc.
 ///
-/// ```
+/// ```no_run
 /// use dynamecs_app::setup_tracing;
 /// use std::error::Error;
 ///
 /// fn main() -> Result<(), Box<dyn Error>> {
 ///     let _guard = setup_tracing()?;
 ///     // do something here. The guard lives until the end of the function
+///     Ok(())
 /// }
 /// ```
 #[must_use]
 pub fn setup_tracing() -> eyre::Result<TracingGuard> {
     let cli_options = CliOptions::parse();
+    let log_target_directives = cli_options
+        .log_target_filter
+        .as_deref()
+        .map(parse_log_target_filter)
+        .transpose()?
+        .unwrap_or_default();
 
     let gz_ext = match cli_options.compress_logs {
         true => ".gz",
@@ -63,16 +96,15 @@ pub fn setup_tracing() -> eyre::Result<TracingGuard> {
     let log_dir = get_output_dir().join("logs");
     let log_file_base_name = "dynamecs_app.log";
     let json_log_file_base_name = "dynamecs_app.jsonlog";
+    let binary_log_file_path = log_dir.join("dynamecs_app.reclog");
     remove_non_archive_log_files(log_dir.as_ref(), log_file_base_name, json_log_file_base_name)?;
+    remove_file_if_exists(&binary_log_file_path).wrap_err("failed to remove old binary log file")?;
     let log_file_path = log_dir.join(format!("{log_file_base_name}{gz_ext}"));
     let json_log_file_path = log_dir.join(format!("{json_log_file_base_name}{gz_ext}"));
 
-    // Use ISO 8601 / RFC 3339, but replace colons with dots, since colons are
-    // not valid in Windows filenames (and awkward on Unix)
-    let timestamp = format!("{}", Local::now().format("%+")).replace(":", ".");
+    let timestamp = archive_timestamp(Local::now(), cli_options.archive_timestamp_format.as_deref())?;
     let archive_dir = log_dir.join("archive");
-    let archive_log_file_path = archive_dir.join(format!("dynamecs_app.{timestamp}.log{gz_ext}"));
-    let archive_json_log_file_path = archive_dir.join(format!("dynamecs_app.{timestamp}.json{gz_ext}"));
+    let (archive_log_file_path, archive_json_log_file_path) = archive_log_file_paths(&archive_dir, &timestamp, gz_ext);
 
     create_dir_all(&log_dir).wrap_err("failed to create log directory")?;
     let log_file = File::create(&log_file_path).wrap_err("failed to create main log file")?;
@@ -89,10 +121,30 @@ pub fn setup_tracing() -> eyre::Result<TracingGuard> {
         json_log_files.push(archive_json_log_file);
     }
 
+    let stream = cli_options
+        .stream_logs
+        .as_ref()
+        .map(|address| {
+            TcpStream::connect(address).wrap_err_with(|| format!("failed to connect to {address} for streaming logs"))
+        })
+        .transpose()?;
+
     let mut guard = TracingGuard::new();
 
-    let log_files_writer = MultiWriter::from_writers(log_files);
-    let json_files_writer = MultiWriter::from_writers(json_log_files);
+    let binary_writer = if cli_options.binary_logs {
+        let binary_log_file = File::create(&binary_log_file_path).wrap_err("failed to create binary log file")?;
+        let writer = Arc::new(MutexWriter::new(buffered_multi_writer(
+            vec![binary_log_file],
+            cli_options.log_buffer_size,
+        )));
+        guard.binary_log_file_writer = Some(Arc::clone(&writer));
+        Some(writer)
+    } else {
+        None
+    };
+
+    let log_files_writer = buffered_multi_writer(log_files, cli_options.log_buffer_size);
+    let json_files_writer = buffered_multi_writer(json_log_files, cli_options.log_buffer_size);
     if cli_options.compress_logs {
         let log_gzip_writer = GzipLogWriter::new(log_files_writer);
         let log_writer = Arc::new(MutexWriter::new(log_gzip_writer));
@@ -105,8 +157,11 @@ pub fn setup_tracing() -> eyre::Result<TracingGuard> {
         set_global_tracing_subscriber(
             cli_options.console_log_level,
             cli_options.file_log_level,
+            &log_target_directives,
             log_writer,
             json_writer,
+            binary_writer,
+            stream,
         )?;
     } else {
         let log_writer = Arc::new(MutexWriter::new(log_files_writer));
@@ -118,8 +173,11 @@ pub fn setup_tracing() -> eyre::Result<TracingGuard> {
         set_global_tracing_subscriber(
             cli_options.console_log_level,
             cli_options.file_log_level,
+            &log_target_directives,
             log_writer,
             json_writer,
+            binary_writer,
+            stream,
         )?;
     }
 
@@ -128,6 +186,15 @@ pub fn setup_tracing() -> eyre::Result<TracingGuard> {
     info!(target: "dynamecs_app", "Logging text to stdout with log level {}", cli_options.console_log_level.to_string());
     info!(target: "dynamecs_app", "Logging text to file {} with log level {}", log_file_path.display(), cli_options.file_log_level);
     info!(target: "dynamecs_app", "Logging JSON to file {} with log level {}", json_log_file_path.display(), cli_options.file_log_level);
+    if let Some(filter) = &cli_options.log_target_filter {
+        info!(target: "dynamecs_app", "Filtering log file targets with \"{filter}\"");
+    }
+    if let Some(address) = &cli_options.stream_logs {
+        info!(target: "dynamecs_app", "Streaming JSON logs to {address} with log level {}", cli_options.file_log_level);
+    }
+    if cli_options.binary_logs {
+        info!(target: "dynamecs_app", "Logging binary records to file {} with log level {}", binary_log_file_path.display(), cli_options.file_log_level);
+    }
     if cli_options.archive_logs {
         info!(target: "dynamecs_app", "Archived log file path:  {}", archive_log_file_path.display());
         info!(target: "dynamecs_app", "Archived JSON log file path: {}", archive_json_log_file_path.display());
@@ -141,6 +208,62 @@ pub fn setup_tracing() -> eyre::Result<TracingGuard> {
     Ok(guard)
 }
 
+/// Sets up `tracing` with only a console layer that writes to stdout.
+///
+/// Unlike [`setup_tracing`], this does not parse command-line arguments, create any log files
+/// or archives, or register a signal handler. It is intended for embedding dynamecs in
+/// another application, or for tests, where only console output is desired.
+///
+/// The returned guard does not own any resources, but is returned for symmetry with
+/// [`setup_tracing`].
+///
+/// ```no_run
+/// use dynamecs_app::setup_tracing_stdout_only;
+/// use tracing::metadata::LevelFilter;
+///
+/// let _guard = setup_tracing_stdout_only(LevelFilter::INFO).unwrap();
+/// ```
+pub fn setup_tracing_stdout_only(level: LevelFilter) -> eyre::Result<TracingGuard> {
+    tracing::subscriber::set_global_default(stdout_only_subscriber(level, std::io::stdout))?;
+    info!(target: "dynamecs_app", "Logging text to stdout with log level {level}");
+    Ok(TracingGuard::new())
+}
+
+fn stdout_only_subscriber<W>(level: LevelFilter, writer: W) -> impl Subscriber + Send + Sync
+where
+    W: for<'writer> MakeWriter<'writer> + 'static + Send + Sync,
+{
+    Registry::default().with(console_layer(writer).with_filter(level))
+}
+
+/// Formats the timestamp used in the names of archive log files.
+///
+/// Without a custom format, ISO 8601 / RFC 3339 is used, but with colons replaced by dots,
+/// since colons are not valid in Windows filenames (and awkward on Unix). A custom
+/// strftime format is used verbatim, and must therefore produce a filesystem-safe name.
+fn archive_timestamp(time: DateTime<Local>, format: Option<&str>) -> eyre::Result<String> {
+    let Some(format) = format else {
+        return Ok(format!("{}", time.format("%+")).replace(":", "."));
+    };
+
+    let mut timestamp = String::new();
+    write!(timestamp, "{}", time.format(format)).map_err(|_| eyre!("invalid archive timestamp format \"{format}\""))?;
+    if timestamp.is_empty() || timestamp.contains([':', '/', '\\']) {
+        return Err(eyre!(
+            "archive timestamp format \"{format}\" produced \"{timestamp}\", \
+             which is not a filesystem-safe name (it must be non-empty and contain no colons or slashes)"
+        ));
+    }
+    Ok(timestamp)
+}
+
+fn archive_log_file_paths(archive_dir: &Path, timestamp: &str, gz_ext: &str) -> (PathBuf, PathBuf) {
+    (
+        archive_dir.join(format!("dynamecs_app.{timestamp}.log{gz_ext}")),
+        archive_dir.join(format!("dynamecs_app.{timestamp}.json{gz_ext}")),
+    )
+}
+
 /// Remove old non-archive log files so that there are no stale logs when toggling log
 /// compression.
 fn remove_non_archive_log_files(
@@ -163,9 +286,69 @@ fn remove_non_archive_log_files(
 fn set_global_tracing_subscriber(
     console_log_level: LevelFilter,
     file_log_level: LevelFilter,
+    log_target_directives: &[Directive],
     log_writer: impl for<'writer> MakeWriter<'writer> + 'static + Send + Sync,
     json_log_writer: impl for<'writer> MakeWriter<'writer> + 'static + Send + Sync,
+    binary_log_writer: Option<Arc<MutexWriter<LogFilesWriter>>>,
+    stream: Option<TcpStream>,
 ) -> eyre::Result<()> {
+    let stdout_layer = console_layer(std::io::stdout).with_filter(console_log_level);
+
+    let log_file_layer = fmt::Layer::default()
+        .with_writer(log_writer)
+        .with_filter(file_log_filter(file_log_level, log_target_directives));
+
+    let json_log_file_layer =
+        json_layer(json_log_writer).with_filter(file_log_filter(file_log_level, log_target_directives));
+
+    let binary_log_file_layer = binary_log_writer
+        .map(|writer| binary_record_layer(writer).with_filter(file_log_filter(file_log_level, log_target_directives)));
+
+    let stream_layer = stream.map(|stream| {
+        json_layer(Arc::new(MutexWriter::new(stream)))
+            .with_filter(file_log_filter(file_log_level, log_target_directives))
+    });
+
+    let subscriber = Registry::default()
+        .with(stdout_layer)
+        .with(log_file_layer)
+        .with(json_log_file_layer)
+        .with(binary_log_file_layer)
+        .with(stream_layer);
+    tracing::subscriber::set_global_default(subscriber)?;
+    Ok(())
+}
+
+/// Parses comma-separated `EnvFilter` directives, such as `dynamecs_app=debug,noisy_dep=off`.
+fn parse_log_target_filter(filter: &str) -> eyre::Result<Vec<Directive>> {
+    filter
+        .split(',')
+        .map(str::trim)
+        .filter(|directive| !directive.is_empty())
+        .map(|directive| {
+            directive
+                .parse()
+                .wrap_err_with(|| format!("invalid log target filter directive \"{directive}\""))
+        })
+        .collect()
+}
+
+/// Creates the filter for log files, which logs targets without a matching directive at the given level.
+fn file_log_filter(level: LevelFilter, directives: &[Directive]) -> EnvFilter {
+    directives.iter().cloned().fold(
+        EnvFilter::builder()
+            .with_default_directive(level.into())
+            .parse_lossy(""),
+        EnvFilter::add_directive,
+    )
+}
+
+/// Creates a layer that writes compact, human-readable text records suitable for a console.
+fn console_layer<S, W>(writer: W) -> impl Layer<S>
+where
+    S: Subscriber + for<'span> LookupSpan<'span>,
+    W: for<'writer> MakeWriter<'writer> + 'static + Send + Sync,
+{
     // Use custom timer formatting so that we only include minimal info in stdout.
     // The log files contain more accurate time stamps
     let stdout_timer = |writer: &mut Writer| -> std::fmt::Result {
@@ -176,28 +359,204 @@ fn set_global_tracing_subscriber(
         write!(writer, "{time}")
     };
 
-    let stdout_layer = fmt::Layer::default()
+    fmt::Layer::default()
         .compact()
         .with_timer(stdout_timer as fn(&mut Writer) -> std::fmt::Result)
-        .with_filter(console_log_level);
-
-    let log_file_layer = fmt::Layer::default()
-        .with_writer(log_writer)
-        .with_filter(file_log_level);
+        .with_writer(writer)
+}
 
-    let json_log_file_layer = fmt::Layer::default()
+/// Creates a layer that writes JSON records in the format expected by `dynamecs-analyze`.
+fn json_layer<S, W>(writer: W) -> fmt::Layer<S, JsonFields, Format<Json>, W>
+where
+    S: Subscriber + for<'span> LookupSpan<'span>,
+    W: for<'writer> MakeWriter<'writer> + 'static,
+{
+    fmt::Layer::default()
         .json()
         .with_thread_ids(true)
         .with_span_events(FmtSpan::ENTER | FmtSpan::EXIT)
-        .with_writer(json_log_writer)
-        .with_filter(file_log_level);
+        .with_writer(writer)
+}
 
-    let subscriber = Registry::default()
-        .with(stdout_layer)
-        .with(log_file_layer)
-        .with(json_log_file_layer);
-    tracing::subscriber::set_global_default(subscriber)?;
-    Ok(())
+/// Creates a `tracing` layer that sends every record to the given channel.
+///
+/// The records are identical to the records found in the JSON logs, which makes it possible
+/// to analyze a running application in-process, for example for live dashboards.
+/// Records are silently dropped once the receiver has been dropped.
+pub fn record_channel_layer<S>(sender: Sender<Record>) -> impl Layer<S>
+where
+    S: Subscriber + for<'span> LookupSpan<'span>,
+{
+    RecordLayer::new(move |record| {
+        // The receiver may have been dropped, in which case there's nothing left to do
+        let _ = sender.send(record);
+    })
+}
+
+/// Creates a `tracing` layer that writes every record to the given writer in the binary `.reclog` format.
+fn binary_record_layer<S>(writer: Arc<MutexWriter<LogFilesWriter>>) -> impl Layer<S>
+where
+    S: Subscriber + for<'span> LookupSpan<'span>,
+{
+    RecordLayer::new(move |record| {
+        // There is no way to report errors from a layer, so they are ignored, as for the other log files
+        let _ = write_binary_records(&*writer, iter::once(record));
+    })
+}
+
+/// A `tracing` layer that creates a [`Record`] for every event and every span enter and exit,
+/// and passes it to a handler.
+///
+/// The records are built directly from the `tracing` data in the same form as the records written by
+/// [`json_layer`], so that they need not be formatted as JSON and parsed again.
+struct RecordLayer<F> {
+    handler: F,
+}
+
+/// The fields of a span, as recorded by [`RecordLayer`].
+struct RecordSpanFields(Map<String, Value>);
+
+impl<F: Fn(Record)> RecordLayer<F> {
+    fn new(handler: F) -> Self {
+        Self { handler }
+    }
+
+    /// Completes the record with the metadata of the event or span, the span context, the time and the thread,
+    /// and passes it to the handler.
+    fn emit<S>(
+        &self,
+        builder: RecordBuilder,
+        metadata: &Metadata<'_>,
+        span: Option<&SpanRef<'_, S>>,
+        ctx: &Context<'_, S>,
+    ) where
+        S: Subscriber + for<'span> LookupSpan<'span>,
+    {
+        let mut builder = builder
+            .level(record_level(metadata.level()))
+            .target(metadata.target())
+            .timestamp(OffsetDateTime::now_utc())
+            .thread_id(format!("{:?}", std::thread::current().id()));
+        if let Some(span) = span {
+            // As in the JSON logs, the span list is the stack of currently entered spans,
+            // which no longer contains a span that is being exited
+            let spans = ctx
+                .lookup_current()
+                .map(|current| {
+                    current
+                        .scope()
+                        .from_root()
+                        .map(|span| record_span(&span))
+                        .collect()
+                })
+                .unwrap_or_default();
+            builder = builder.span(record_span(span)).spans(spans);
+        }
+        (self.handler)(builder.build());
+    }
+}
+
+impl<S, F> Layer<S> for RecordLayer<F>
+where
+    S: Subscriber + for<'span> LookupSpan<'span>,
+    F: Fn(Record) + 'static,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let span = ctx.span(id).expect("span must exist when it is created");
+        let mut fields = Map::new();
+        attrs.record(&mut JsonFieldVisitor(&mut fields));
+        span.extensions_mut().insert(RecordSpanFields(fields));
+    }
+
+    fn on_record(&self, id: &Id, values: &tracing::span::Record<'_>, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(id) {
+            if let Some(fields) = span.extensions_mut().get_mut::<RecordSpanFields>() {
+                values.record(&mut JsonFieldVisitor(&mut fields.0));
+            }
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let mut fields = Map::new();
+        event.record(&mut JsonFieldVisitor(&mut fields));
+        let message = fields
+            .get("message")
+            .and_then(Value::as_str)
+            .map(str::to_string);
+        let mut builder = RecordBuilder::event().fields(Value::Object(fields));
+        if let Some(message) = message {
+            builder = builder.message(message);
+        }
+        let span = event
+            .parent()
+            .and_then(|id| ctx.span(id))
+            .or_else(|| ctx.lookup_current());
+        self.emit(builder, event.metadata(), span.as_ref(), &ctx);
+    }
+
+    fn on_enter(&self, id: &Id, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(id) {
+            self.emit(RecordBuilder::span_enter(), span.metadata(), Some(&span), &ctx);
+        }
+    }
+
+    fn on_exit(&self, id: &Id, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(id) {
+            self.emit(RecordBuilder::span_exit(), span.metadata(), Some(&span), &ctx);
+        }
+    }
+}
+
+fn record_span<S>(span: &SpanRef<'_, S>) -> Span
+where
+    S: Subscriber + for<'span> LookupSpan<'span>,
+{
+    let fields = span
+        .extensions()
+        .get::<RecordSpanFields>()
+        .map(|fields| fields.0.clone())
+        .unwrap_or_default();
+    Span::from_name_and_fields(span.name(), Value::Object(fields))
+}
+
+fn record_level(level: &tracing::Level) -> Level {
+    match *level {
+        tracing::Level::ERROR => Level::Error,
+        tracing::Level::WARN => Level::Warn,
+        tracing::Level::INFO => Level::Info,
+        tracing::Level::DEBUG => Level::Debug,
+        _ => Level::Trace,
+    }
+}
+
+/// Records field values as JSON values in the same way as the JSON layer.
+struct JsonFieldVisitor<'a>(&'a mut Map<String, Value>);
+
+impl<'a> Visit for JsonFieldVisitor<'a> {
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.0.insert(field.name().to_string(), Value::from(value));
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.0.insert(field.name().to_string(), Value::from(value));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.0.insert(field.name().to_string(), Value::from(value));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.0.insert(field.name().to_string(), Value::from(value));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_string(), Value::from(value));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        self.0
+            .insert(field.name().to_string(), Value::from(format!("{value:?}")));
+    }
 }
 
 fn remove_file_if_exists(path: impl AsRef<Path>) -> std::io::Result<()> {
@@ -210,11 +569,15 @@ fn remove_file_if_exists(path: impl AsRef<Path>) -> std::io::Result<()> {
     }
 }
 
+/// Writer for (possibly multiple) log files.
+type LogFilesWriter = MultiWriter<BufWriter<File>>;
+
 pub struct TracingGuard {
-    log_file_writer: Option<Arc<MutexWriter<MultiWriter<File>>>>,
-    gz_log_file_writer: Option<Arc<MutexWriter<GzipLogWriter<MultiWriter<File>>>>>,
-    json_log_file_writer: Option<Arc<MutexWriter<MultiWriter<File>>>>,
-    gz_json_log_file_writer: Option<Arc<MutexWriter<GzipLogWriter<MultiWriter<File>>>>>,
+    log_file_writer: Option<Arc<MutexWriter<LogFilesWriter>>>,
+    gz_log_file_writer: Option<Arc<MutexWriter<GzipLogWriter<LogFilesWriter>>>>,
+    json_log_file_writer: Option<Arc<MutexWriter<LogFilesWriter>>>,
+    gz_json_log_file_writer: Option<Arc<MutexWriter<GzipLogWriter<LogFilesWriter>>>>,
+    binary_log_file_writer: Option<Arc<MutexWriter<LogFilesWriter>>>,
 }
 
 impl TracingGuard {
@@ -224,6 +587,7 @@ impl TracingGuard {
             gz_log_file_writer: None,
             json_log_file_writer: None,
             gz_json_log_file_writer: None,
+            binary_log_file_writer: None,
         }
     }
 
@@ -241,6 +605,11 @@ impl TracingGuard {
                 let _ = writer.flush();
             }
         }
+        if let Some(binary_log_file_writer) = &mut self.binary_log_file_writer {
+            if let Ok(mut writer) = binary_log_file_writer.0.lock() {
+                let _ = writer.flush();
+            }
+        }
         if let Some(gz_log_file_writer) = &mut self.gz_log_file_writer {
             if let Ok(mut writer) = gz_log_file_writer.0.lock() {
                 let _ = writer.finish();
@@ -259,6 +628,7 @@ impl TracingGuard {
             gz_log_file_writer: self.gz_log_file_writer.clone(),
             json_log_file_writer: self.json_log_file_writer.clone(),
             gz_json_log_file_writer: self.gz_json_log_file_writer.clone(),
+            binary_log_file_writer: self.binary_log_file_writer.clone(),
         }
     }
 }
@@ -348,6 +718,17 @@ impl<'a, W: Write> Write for &'a MutexWriter<W> {
     }
 }
 
+/// Wraps each file in a buffered writer with the given capacity, and combines them into a single writer.
+///
+/// A capacity of zero effectively disables buffering, since writes are then passed directly to the file.
+fn buffered_multi_writer(files: Vec<File>, buffer_size: usize) -> LogFilesWriter {
+    let writers = files
+        .into_iter()
+        .map(|file| BufWriter::with_capacity(buffer_size, file))
+        .collect();
+    MultiWriter::from_writers(writers)
+}
+
 /// A writer that forwards the data to multiple writers.
 struct MultiWriter<W> {
     writers: Vec<W>,
@@ -375,3 +756,211 @@ impl<W: Write> Write for MultiWriter<W> {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        archive_log_file_paths, archive_timestamp, binary_record_layer, buffered_multi_writer, file_log_filter,
+        json_layer, parse_log_target_filter, stdout_only_subscriber, MutexWriter,
+    };
+    use crate::record_channel_layer;
+    use chrono::{Local, TimeZone};
+    use dynamecs_analyze::{
+        iterate_binary_records_from_reader, iterate_records_from_reader, Level, Record, RecordBuilder, RecordKind,
+    };
+    use std::fs::File;
+    use std::io::Write;
+    use std::path::Path;
+    use std::sync::mpsc::channel;
+    use std::sync::{Arc, Mutex};
+    use tracing::metadata::LevelFilter;
+    use tracing::{debug, info, info_span, trace};
+    use tracing_subscriber::prelude::*;
+    use tracing_subscriber::Registry;
+
+    #[test]
+    fn record_channel_layer_sends_records() {
+        let (sender, receiver) = channel();
+        let subscriber = Registry::default().with(record_channel_layer(sender));
+        tracing::subscriber::with_default(subscriber, || {
+            let _span = info_span!("outer").entered();
+            info!(target: "test_target", answer = 42, "hello");
+        });
+
+        let records: Vec<_> = receiver.try_iter().collect();
+        let event = records
+            .iter()
+            .find(|record| record.kind() == RecordKind::Event)
+            .expect("event must be sent to the channel");
+        assert_eq!(event.level(), Level::Info);
+        assert_eq!(event.target(), "test_target");
+        assert_eq!(event.message(), Some("hello"));
+        assert_eq!(event.fields().pointer("/answer"), Some(&serde_json::json!(42)));
+        assert_eq!(event.span().map(|span| span.name()), Some("outer"));
+
+        let kinds: Vec<_> = records.iter().map(|record| record.kind()).collect();
+        assert_eq!(kinds, [RecordKind::SpanEnter, RecordKind::Event, RecordKind::SpanExit]);
+    }
+
+    #[test]
+    fn buffered_log_file_round_trips_records() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("buffered.jsonlog");
+        let file = File::create(&path).unwrap();
+        let writer = Arc::new(MutexWriter::new(buffered_multi_writer(vec![file], 1 << 16)));
+        let subscriber = Registry::default().with(json_layer(Arc::clone(&writer)));
+        tracing::subscriber::with_default(subscriber, || {
+            let _span = info_span!("outer").entered();
+            for i in 0..10 {
+                info!(target: "test_target", i, "record {i}");
+            }
+        });
+
+        // The records fit in the buffer, so nothing has been written to the file yet
+        assert_eq!(std::fs::metadata(&path).unwrap().len(), 0);
+
+        (&*writer).flush().unwrap();
+        let records: Vec<_> = iterate_records_from_reader(File::open(&path).unwrap())
+            .collect::<Result<_, _>>()
+            .unwrap();
+
+        let messages: Vec<_> = records
+            .iter()
+            .filter(|record| record.kind() == RecordKind::Event)
+            .filter_map(|record| record.message())
+            .collect();
+        let expected: Vec<_> = (0..10).map(|i| format!("record {i}")).collect();
+        assert_eq!(messages, expected);
+        assert_eq!(records.len(), 12);
+    }
+
+    #[test]
+    fn binary_log_file_contains_same_records_as_json_log() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("dynamecs_app.reclog");
+        let file = File::create(&path).unwrap();
+        let binary_writer = Arc::new(MutexWriter::new(buffered_multi_writer(vec![file], 0)));
+        let buffer = Arc::new(Mutex::new(Vec::new()));
+        let json_writer = {
+            let buffer = Arc::clone(&buffer);
+            move || SharedBufferWriter(Arc::clone(&buffer))
+        };
+        let subscriber = Registry::default()
+            .with(json_layer(json_writer))
+            .with(binary_record_layer(binary_writer));
+        tracing::subscriber::with_default(subscriber, || {
+            let _span = info_span!("outer", step_index = 3).entered();
+            let inner = info_span!("inner", name_hint = "solve", residual = tracing::field::Empty);
+            let _inner = inner.enter();
+            inner.record("residual", 1.5e-3);
+            info!(target: "test_target", answer = 42, negative = -1, converged = false, shape = ?[2, 3], "hello");
+            debug!("goodbye");
+        });
+
+        let json_records: Vec<_> = iterate_records_from_reader(buffer.lock().unwrap().as_slice())
+            .collect::<Result<_, _>>()
+            .unwrap();
+        let binary_records: Vec<_> = iterate_binary_records_from_reader(File::open(&path).unwrap())
+            .collect::<Result<_, _>>()
+            .unwrap();
+
+        // Each layer takes its own timestamp, so only the timestamps differ between the logs
+        let timestamp = *json_records[0].timestamp();
+        let with_same_timestamp = |records: Vec<Record>| -> Vec<Record> {
+            records
+                .into_iter()
+                .map(|record| {
+                    RecordBuilder::from_record(record)
+                        .timestamp(timestamp)
+                        .build()
+                })
+                .collect()
+        };
+        assert_eq!(json_records.len(), 6);
+        assert_eq!(with_same_timestamp(binary_records), with_same_timestamp(json_records));
+    }
+
+    #[test]
+    fn stdout_only_subscriber_writes_console_records() {
+        let buffer = Arc::new(Mutex::new(Vec::new()));
+        let writer = {
+            let buffer = Arc::clone(&buffer);
+            move || SharedBufferWriter(Arc::clone(&buffer))
+        };
+        let subscriber = stdout_only_subscriber(LevelFilter::INFO, writer);
+        tracing::subscriber::with_default(subscriber, || {
+            info!(target: "test_target", "hello from the console");
+            debug!(target: "test_target", "filtered out");
+        });
+
+        let output = String::from_utf8(buffer.lock().unwrap().clone()).unwrap();
+        assert!(output.contains("hello from the console"));
+        assert!(output.contains("test_target"));
+        assert!(!output.contains("filtered out"));
+    }
+
+    #[test]
+    fn log_target_filter_excludes_filtered_targets_from_file_output() {
+        let buffer = Arc::new(Mutex::new(Vec::new()));
+        let writer = {
+            let buffer = Arc::clone(&buffer);
+            move || SharedBufferWriter(Arc::clone(&buffer))
+        };
+        let directives = parse_log_target_filter("my_sim=trace, noisy_dep=off").unwrap();
+        let subscriber =
+            Registry::default().with(json_layer(writer).with_filter(file_log_filter(LevelFilter::DEBUG, &directives)));
+        tracing::subscriber::with_default(subscriber, || {
+            info!(target: "noisy_dep", "noisy info");
+            trace!(target: "my_sim", "simulation trace");
+            debug!(target: "other", "other debug");
+            trace!(target: "other", "other trace");
+        });
+
+        let output = buffer.lock().unwrap().clone();
+        let messages: Vec<_> = iterate_records_from_reader(output.as_slice())
+            .map(|record| record.unwrap().message().map(str::to_string))
+            .collect();
+        assert_eq!(
+            messages,
+            [Some("simulation trace".to_string()), Some("other debug".to_string())]
+        );
+
+        assert!(parse_log_target_filter("my_sim=notalevel").is_err());
+    }
+
+    #[test]
+    fn archive_file_names_use_custom_timestamp_format() {
+        let time = Local.with_ymd_and_hms(2024, 1, 2, 3, 4, 5).unwrap();
+        let timestamp = archive_timestamp(time, Some("%Y%m%d_%H%M%S")).unwrap();
+        assert_eq!(timestamp, "20240102_030405");
+
+        let (log_path, json_path) = archive_log_file_paths(Path::new("archive"), &timestamp, ".gz");
+        assert_eq!(log_path.file_name().unwrap(), "dynamecs_app.20240102_030405.log.gz");
+        assert_eq!(json_path.file_name().unwrap(), "dynamecs_app.20240102_030405.json.gz");
+
+        let default_timestamp = archive_timestamp(time, None).unwrap();
+        assert!(default_timestamp.starts_with("2024-01-02T03.04.05"));
+    }
+
+    #[test]
+    fn archive_timestamp_format_must_be_filesystem_safe() {
+        let time = Local.with_ymd_and_hms(2024, 1, 2, 3, 4, 5).unwrap();
+        assert!(archive_timestamp(time, Some("%H:%M:%S")).is_err());
+        assert!(archive_timestamp(time, Some("%Y/%m/%d")).is_err());
+        assert!(archive_timestamp(time, Some("")).is_err());
+        assert!(archive_timestamp(time, Some("%Q")).is_err());
+    }
+
+    struct SharedBufferWriter(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBufferWriter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,119 @@
+//! Terminating a simulation with a signal affects the whole process, so the simulation runs in a child process.
+//!
+//! This test does not use the default test harness: the same binary acts as the child process when the
+//! environment variable `CHILD_ENV_VAR` is set, in which case the command-line arguments are parsed by the app.
+use dynamecs::adapters::FnSystem;
+use dynamecs_app::{register_signal_handler, DynamecsApp, Scenario, StopCondition, TerminationRequested};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+const CHILD_ENV_VAR: &str = "DYNAMECS_APP_TERMINATION_TEST_CHILD";
+const SCENARIO_NAME: &str = "checkpoint_on_termination";
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Config {}
+
+fn main() {
+    if std::env::var_os(CHILD_ENV_VAR).is_some() {
+        run_child();
+    } else {
+        checkpoint_is_written_when_terminated_by_signal();
+    }
+}
+
+/// Runs a simulation until it is terminated, and exits successfully only if it was terminated by a signal.
+fn run_child() {
+    let result = register_signal_handler()
+        .and_then(|_| DynamecsApp::configure_from_cli::<Config>())
+        .and_then(|app| {
+            app.checkpoint_on_termination(true)
+                .with_scenario_initializer(|_| {
+                    let mut started = false;
+                    let mut scenario = Scenario::default_with_name(SCENARIO_NAME)
+                        // Guard against running forever if the signal is not delivered
+                        .with_stop_condition(StopCondition::WallTime(Duration::from_secs(30)));
+                    scenario
+                        .simulation_systems
+                        .add_system(FnSystem::new("sleep", move |_| {
+                            // Tell the parent process that the simulation is running
+                            if !started {
+                                println!("started");
+                                started = true;
+                            }
+                            std::thread::sleep(Duration::from_millis(5));
+                            Ok(())
+                        }));
+                    Ok(scenario)
+                })
+        })
+        .and_then(|app| app.run());
+    match result {
+        Err(err) if err.is::<TerminationRequested>() => {}
+        other => {
+            eprintln!("expected the simulation to be terminated by a signal, but got {other:?}");
+            std::process::exit(1);
+        }
+    }
+}
+
+#[cfg(unix)]
+fn checkpoint_is_written_when_terminated_by_signal() {
+    use std::io::{BufRead, BufReader};
+    use std::process::{Command, Stdio};
+
+    let output_dir = tempfile::tempdir().unwrap();
+    // Checkpoints are only written for every 1000th step, so the termination checkpoint must be written
+    // by the configured system in the configured format
+    let mut child = Command::new(std::env::current_exe().unwrap())
+        .env(CHILD_ENV_VAR, "1")
+        .arg("--output-dir")
+        .arg(output_dir.path())
+        .args([
+            "--write-checkpoints",
+            "--checkpoint-format",
+            "json",
+            "--checkpoint-interval",
+            "1000",
+        ])
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    // Only send the signal once the simulation is running, since the handler aborts otherwise
+    let mut line = String::new();
+    BufReader::new(child.stdout.take().unwrap())
+        .read_line(&mut line)
+        .unwrap();
+    assert_eq!(line.trim(), "started");
+    let status = Command::new("kill")
+        .args(["-TERM", &child.id().to_string()])
+        .status()
+        .unwrap();
+    assert!(status.success());
+    assert!(child.wait().unwrap().success());
+
+    let checkpoint_dir = output_dir.path().join(SCENARIO_NAME).join("checkpoints");
+    let step_indices: Vec<usize> = std::fs::read_dir(&checkpoint_dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .map(|file_name| {
+            file_name
+                .strip_prefix("checkpoint_")
+                .and_then(|name| name.strip_suffix(".json"))
+                .and_then(|index| index.parse().ok())
+                .unwrap_or_else(|| panic!("unexpected checkpoint file {file_name}"))
+        })
+        .collect();
+    assert_eq!(step_indices.len(), 1, "unexpected checkpoints: {step_indices:?}");
+    assert!(step_indices[0] > 0);
+
+    let checkpoint_path = checkpoint_dir.join(format!("checkpoint_{}.json", step_indices[0]));
+    let checkpoint: serde_json::Value = serde_json::from_slice(&std::fs::read(checkpoint_path).unwrap()).unwrap();
+    assert!(checkpoint.is_object());
+    println!("test checkpoint_is_written_when_terminated_by_signal ... ok");
+}
+
+#[cfg(not(unix))]
+fn checkpoint_is_written_when_terminated_by_signal() {
+    println!("test checkpoint_is_written_when_terminated_by_signal ... ignored (requires unix signals)");
+}
@@ -0,0 +1,11 @@
+//! Installing a global subscriber affects the whole process, so this test runs in its own test binary.
+use dynamecs_app::setup_tracing_stdout_only;
+use tracing::metadata::LevelFilter;
+
+#[test]
+fn setup_tracing_stdout_only_installs_global_subscriber() {
+    let _guard = setup_tracing_stdout_only(LevelFilter::INFO).unwrap();
+    assert!(tracing::dispatcher::has_been_set());
+    // A global subscriber can only be installed once
+    assert!(setup_tracing_stdout_only(LevelFilter::INFO).is_err());
+}
@@ -0,0 +1 @@
+mod unit_tests;
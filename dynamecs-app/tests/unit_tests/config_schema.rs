@@ -0,0 +1,35 @@
+use dynamecs_app::config_schema_json;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+#[allow(dead_code)]
+struct SolverConfig {
+    tolerance: f64,
+    max_iterations: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+#[allow(dead_code)]
+struct SampleConfig {
+    resolution: usize,
+    mesh_file: Option<String>,
+    solver: SolverConfig,
+}
+
+#[test]
+fn config_schema_contains_config_properties() {
+    let schema: serde_json::Value = serde_json::from_str(&config_schema_json::<SampleConfig>()).unwrap();
+    assert_eq!(schema["title"], "SampleConfig");
+
+    let properties = schema["properties"].as_object().unwrap();
+    let mut property_names: Vec<_> = properties.keys().map(String::as_str).collect();
+    property_names.sort();
+    assert_eq!(property_names, ["mesh_file", "resolution", "solver"]);
+
+    let solver_properties = schema["definitions"]["SolverConfig"]["properties"]
+        .as_object()
+        .unwrap();
+    assert!(solver_properties.contains_key("tolerance"));
+    assert!(solver_properties.contains_key("max_iterations"));
+}
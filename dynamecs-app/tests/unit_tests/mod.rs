@@ -0,0 +1,3 @@
+#[cfg(feature = "schemars")]
+mod config_schema;
+mod run_scenario;
@@ -0,0 +1,223 @@
+use dynamecs::adapters::FnSystem;
+use dynamecs::components::{
+    current_phase, get_config, get_scenario_metadata, get_simulation_time, get_step_index, ConfigComponent, Paused,
+    Phase, SimulationTime, StepIndex, TimeStep,
+};
+use dynamecs::storages::{ImmutableSingularStorage, SingularStorage};
+use dynamecs::Component;
+use dynamecs_analyze::RecordKind;
+use dynamecs_app::{record_channel_layer, run_scenario_for_test, Scenario};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::sync::mpsc::channel;
+use tracing_subscriber::prelude::*;
+use tracing_subscriber::Registry;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct SimulationRuns(usize);
+
+impl Component for SimulationRuns {
+    type Storage = SingularStorage<Self>;
+}
+
+#[test]
+fn run_scenario_for_test_runs_fixed_number_of_steps() {
+    let mut scenario = Scenario::default_with_name("tiny");
+    // The duration must not affect the number of steps
+    scenario.duration = Some(0.0);
+    scenario
+        .state
+        .insert_storage(SingularStorage::new(TimeStep(0.5)));
+    scenario
+        .simulation_systems
+        .add_system(FnSystem::new("count", |universe| {
+            universe
+                .get_component_storage_mut::<SimulationRuns>()
+                .get_component_mut()
+                .0 += 1;
+            Ok(())
+        }));
+
+    let universe = run_scenario_for_test(scenario, 3).unwrap();
+
+    let StepIndex(step_index) = get_step_index(&universe);
+    let SimulationTime(sim_time) = get_simulation_time(&universe);
+    assert_eq!(step_index, 3);
+    assert_eq!(sim_time, 1.5);
+    assert_eq!(
+        universe
+            .get_component_storage::<SimulationRuns>()
+            .get_component()
+            .0,
+        3
+    );
+}
+
+#[test]
+fn paused_scenario_skips_simulation_systems_and_time() {
+    let mut scenario = Scenario::default_with_name("pausable");
+    scenario
+        .state
+        .insert_storage(SingularStorage::new(TimeStep(0.5)));
+    // Pause during steps 1 and 2
+    scenario
+        .pre_systems
+        .add_system(FnSystem::new("toggle_pause", |universe| {
+            let StepIndex(step_index) = get_step_index(universe);
+            universe.insert_storage(SingularStorage::new(Paused((1..3).contains(&step_index))));
+            Ok(())
+        }));
+    scenario
+        .simulation_systems
+        .add_system(FnSystem::new("count", |universe| {
+            universe
+                .get_component_storage_mut::<SimulationRuns>()
+                .get_component_mut()
+                .0 += 1;
+            Ok(())
+        }));
+
+    let universe = run_scenario_for_test(scenario, 4).unwrap();
+
+    let StepIndex(step_index) = get_step_index(&universe);
+    let SimulationTime(sim_time) = get_simulation_time(&universe);
+    assert_eq!(step_index, 4);
+    assert_eq!(sim_time, 1.0);
+    assert_eq!(
+        universe
+            .get_component_storage::<SimulationRuns>()
+            .get_component()
+            .0,
+        2
+    );
+}
+
+#[test]
+fn step_start_log_has_structured_fields() {
+    let mut scenario = Scenario::default_with_name("logging");
+    scenario
+        .state
+        .insert_storage(SingularStorage::new(TimeStep(0.25)));
+
+    let (sender, receiver) = channel();
+    let subscriber = Registry::default().with(record_channel_layer(sender));
+    tracing::subscriber::with_default(subscriber, || run_scenario_for_test(scenario, 2).unwrap());
+
+    let step_records: Vec<_> = receiver
+        .try_iter()
+        .filter(|record| {
+            record.kind() == RecordKind::Event
+                && record
+                    .message()
+                    .is_some_and(|message| message.starts_with("Starting step"))
+        })
+        .collect();
+    assert_eq!(step_records.len(), 2);
+    for (i, record) in step_records.iter().enumerate() {
+        let fields = record.fields();
+        assert_eq!(fields["step_index"], json!(i));
+        assert_eq!(fields["sim_time"], json!(0.25 * i as f64));
+        assert_eq!(fields["dt"], json!(0.25));
+    }
+    assert_eq!(
+        step_records[1].message(),
+        Some("Starting step 1 at simulation time 0.25000 (dt = 0.25000)")
+    );
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct TestConfig {
+    increment: usize,
+}
+
+#[test]
+fn systems_can_read_config_from_state() {
+    let mut scenario = Scenario::default_with_name("config");
+    scenario
+        .state
+        .insert_storage(ImmutableSingularStorage::new(ConfigComponent(TestConfig {
+            increment: 5,
+        })));
+    scenario
+        .simulation_systems
+        .add_system(FnSystem::new("count_by_config", |universe| {
+            let increment = get_config::<TestConfig>(universe)?.increment;
+            universe
+                .get_component_storage_mut::<SimulationRuns>()
+                .get_component_mut()
+                .0 += increment;
+            Ok(())
+        }));
+
+    let universe = run_scenario_for_test(scenario, 2).unwrap();
+    assert_eq!(
+        universe
+            .get_component_storage::<SimulationRuns>()
+            .get_component()
+            .0,
+        10
+    );
+    assert!(get_config::<usize>(&universe).is_err());
+}
+
+#[test]
+fn systems_observe_current_phase() {
+    use std::sync::{Arc, Mutex};
+
+    let observed_phases = Arc::new(Mutex::new(Vec::new()));
+    let phase_recorder = || {
+        let observed_phases = Arc::clone(&observed_phases);
+        FnSystem::new("record_phase", move |universe| {
+            observed_phases
+                .lock()
+                .unwrap()
+                .push(current_phase(universe));
+            Ok(())
+        })
+    };
+
+    let mut scenario = Scenario::default_with_name("phases");
+    scenario.pre_systems.add_system(phase_recorder());
+    scenario.simulation_systems.add_system(phase_recorder());
+    scenario.post_systems.add_system(phase_recorder());
+    run_scenario_for_test(scenario, 1).unwrap();
+
+    // Post-systems also run once on the initial state
+    assert_eq!(
+        *observed_phases.lock().unwrap(),
+        vec![
+            Some(Phase::Post),
+            Some(Phase::Pre),
+            Some(Phase::Simulation),
+            Some(Phase::Post)
+        ]
+    );
+}
+
+#[test]
+fn scenario_metadata_is_logged_and_inserted() {
+    let scenario = Scenario::default_with_name("metadata")
+        .with_metadata("commit", "abc123")
+        .with_metadata("machine", "cluster-node-1");
+    assert_eq!(scenario.metadata().len(), 2);
+
+    let (sender, receiver) = channel();
+    let subscriber = Registry::default().with(record_channel_layer(sender));
+    let universe = tracing::subscriber::with_default(subscriber, || run_scenario_for_test(scenario, 1).unwrap());
+
+    let metadata_records: Vec<_> = receiver
+        .try_iter()
+        .filter(|record| record.kind() == RecordKind::Event && record.fields().get("metadata").is_some())
+        .collect();
+    assert_eq!(metadata_records.len(), 1);
+    let logged_metadata: serde_json::Value =
+        serde_json::from_str(metadata_records[0].fields()["metadata"].as_str().unwrap()).unwrap();
+    assert_eq!(
+        logged_metadata,
+        json!({ "commit": "abc123", "machine": "cluster-node-1" })
+    );
+
+    let metadata = get_scenario_metadata(&universe).unwrap();
+    assert_eq!(metadata["commit"], "abc123");
+    assert_eq!(metadata["machine"], "cluster-node-1");
+}
@@ -1,9 +1,16 @@
-use clap::{Parser, Subcommand};
-use dynamecs_analyze::iterate_records;
-use dynamecs_analyze::timing::{extract_step_timings, format_timing_tree};
+use clap::{Parser, Subcommand, ValueEnum};
+use dynamecs_analyze::timing::{
+    extract_timing_summary, format_span_list, format_timing_tree_colored, format_timing_tree_tsv, visit_step_timings,
+    AccumulatedTimings, ColorChoice, StepUnit,
+};
+use dynamecs_analyze::timing_history::{append_timing_history, TimingHistoryEntry};
+use dynamecs_analyze::{iterate_records, write_event_fields};
 use std::error::Error;
 use std::fmt::Write;
+use std::fs::OpenOptions;
 use std::path::PathBuf;
+use std::{io, iter};
+use time::OffsetDateTime;
 
 #[derive(Parser)]
 struct Cli {
@@ -19,41 +26,165 @@ enum Commands {
         /// Only aggregate timings across all steps in the log file will be returned.
         #[arg(short, long)]
         aggregate: bool,
+        /// The output format. The TSV format emits a single table with a leading `Step` column.
+        #[arg(short, long, value_enum, default_value_t = OutputFormat::Table)]
+        format: OutputFormat,
+        /// Whether to highlight hotspots in table output with colors.
+        #[arg(long, value_enum, default_value_t = ColorArg::Auto)]
+        color: ColorArg,
+        /// Hide spans that were completed fewer than the given number of times in the aggregate timings.
+        /// Their time is instead accounted for in the self time of their parents. Timings of individual
+        /// steps are not filtered, since most spans are only completed a few times per step.
+        #[arg(long)]
+        min_count: Option<u64>,
+        /// Append the aggregate timings, tagged with the commit given by `--commit` and the current time,
+        /// to the given JSON Lines file, for tracking performance across commits. The file is created if
+        /// it does not exist.
+        #[arg(long, requires = "commit")]
+        record_to: Option<PathBuf>,
+        /// The commit hash to tag recorded timings with, see `--record-to`.
+        #[arg(long, requires = "record_to")]
+        commit: Option<String>,
     },
+    /// Lists every span path with its count and total duration, sorted by decreasing total duration.
+    Spans {
+        #[arg(short, long)]
+        logfile: PathBuf,
+        /// Only list the given number of spans with the largest total duration.
+        #[arg(long)]
+        top: Option<usize>,
+    },
+    /// Prints the timestamp, span path and the requested fields of every event as tab-separated values.
+    Events {
+        #[arg(short, long)]
+        logfile: PathBuf,
+        /// A field to print, given as a name or a JSON pointer such as `/stats/residual`. Fields that are
+        /// missing from an event are looked up in its innermost enclosing span. May be given multiple times.
+        #[arg(long = "field")]
+        fields: Vec<String>,
+    },
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+    /// Human-readable tables.
+    Table,
+    /// Tab-separated values, intended for consumption by other tools.
+    Tsv,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+enum ColorArg {
+    /// Use colors if the output is a terminal and `NO_COLOR` is not set.
+    Auto,
+    Always,
+    Never,
+}
+
+impl From<ColorArg> for ColorChoice {
+    fn from(arg: ColorArg) -> Self {
+        match arg {
+            ColorArg::Auto => ColorChoice::Auto,
+            ColorArg::Always => ColorChoice::Always,
+            ColorArg::Never => ColorChoice::Never,
+        }
+    }
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
     let args = Cli::parse();
 
     match args.command {
-        Commands::Timing { logfile, aggregate } => {
+        Commands::Timing {
+            logfile,
+            aggregate,
+            format,
+            color,
+            min_count,
+            record_to,
+            commit,
+        } => {
             let records_result_iter = iterate_records(logfile)?;
             let records_iter = records_result_iter
                 // TODO: Use peeking_take_while or something so that we can
                 // check for errors in the remaining records in combination with .by_ref()
                 .map_while(|record| record.ok());
 
-            let timings = extract_step_timings(records_iter)?;
-            if !aggregate {
-                for step in timings.steps() {
-                    let tree = step.timings.create_timing_tree();
-                    println!("Timings for step index {}", step.step_index);
-                    println!("════════════════════════════════");
+            // Records are processed as they are read. Individual step timings are only retained
+            // if we need to display them
+            let mut steps = Vec::new();
+            let mut num_steps = 0;
+            let mut summary = AccumulatedTimings::new();
+            let intransient_timings = visit_step_timings(records_iter, &StepUnit::default(), |step| {
+                num_steps += 1;
+                summary.merge_with_others(iter::once(&step.timings));
+                if !aggregate {
+                    steps.push(step);
+                }
+            })?;
+            summary.merge_with_others(iter::once(&intransient_timings));
 
-                    let prefixed_tree = add_prefix_to_multiline_string(&format_timing_tree(&tree), "  ");
-                    println!("{prefixed_tree}");
+            let summary_tree = match min_count {
+                Some(min_count) => summary.filter_by_min_count(min_count).create_timing_tree(),
+                None => summary.create_timing_tree(),
+            };
+            if let (Some(history_path), Some(commit)) = (record_to, commit) {
+                // Record the unfiltered timings, so that entries are comparable regardless of `--min-count`
+                let entry = TimingHistoryEntry::from_timing_tree(
+                    commit,
+                    OffsetDateTime::now_utc(),
+                    &summary.create_timing_tree(),
+                );
+                let history_file = OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(history_path)?;
+                append_timing_history(history_file, &entry)?;
+            }
+
+            match format {
+                OutputFormat::Table => {
+                    for step in &steps {
+                        let tree = step.timings.create_timing_tree();
+                        println!("Timings for step index {}", step.step_index);
+                        println!("════════════════════════════════");
+
+                        let prefixed_tree =
+                            add_prefix_to_multiline_string(&format_timing_tree_colored(&tree, color.into()), "  ");
+                        println!("{prefixed_tree}");
+                        println!();
+                    }
+
+                    println!("Aggregate timings");
+                    println!("════════════════════════════════");
+                    println!();
+                    let prefixed_summary_tree =
+                        add_prefix_to_multiline_string(&format_timing_tree_colored(&summary_tree, color.into()), "  ");
+                    println!("{prefixed_summary_tree}");
                     println!();
+                    println!("Number of completed time steps: {}", num_steps);
+                }
+                OutputFormat::Tsv => {
+                    // Emit a single table, where the step column identifies which tree a row belongs to
+                    let mut output = String::new();
+                    for step in &steps {
+                        let tree = step.timings.create_timing_tree();
+                        let step_label = step.step_index.to_string();
+                        write_tsv_rows_with_step_column(&mut output, &format_timing_tree_tsv(&tree), &step_label);
+                    }
+                    write_tsv_rows_with_step_column(&mut output, &format_timing_tree_tsv(&summary_tree), "aggregate");
+                    print!("{output}");
                 }
             }
-
-            let summary_tree = timings.summarize().create_timing_tree();
-            println!("Aggregate timings");
-            println!("════════════════════════════════");
-            println!();
-            let prefixed_summary_tree = add_prefix_to_multiline_string(&format_timing_tree(&summary_tree), "  ");
-            println!("{prefixed_summary_tree}");
-            println!();
-            println!("Number of completed time steps: {}", timings.steps().len());
+        }
+        Commands::Spans { logfile, top } => {
+            let records_iter = iterate_records(logfile)?.map_while(|record| record.ok());
+            let summary = extract_timing_summary(records_iter)?;
+            print!("{}", format_span_list(&summary, top));
+        }
+        Commands::Events { logfile, fields } => {
+            let records_iter = iterate_records(logfile)?.map_while(|record| record.ok());
+            write_event_fields(io::stdout().lock(), records_iter, &fields)?;
         }
     }
 
@@ -67,3 +198,18 @@ fn add_prefix_to_multiline_string(string: &str, prefix: &str) -> String {
     }
     output
 }
+
+/// Appends the rows of the given TSV table to the output, prefixed with a `Step` column.
+///
+/// The header is only written if the output is empty.
+fn write_tsv_rows_with_step_column(output: &mut String, tsv_table: &str, step_label: &str) {
+    let mut lines = tsv_table.lines();
+    if let Some(header) = lines.next() {
+        if output.is_empty() {
+            let _ = writeln!(output, "Step\t{header}");
+        }
+    }
+    for line in lines {
+        let _ = writeln!(output, "{step_label}\t{line}");
+    }
+}
@@ -58,3 +58,51 @@ fn test_timing_basic_app1_aggregate() -> Result<(), Box<dyn Error>> {
 
     Ok(())
 }
+
+#[test]
+fn test_timing_basic_app1_tsv() -> Result<(), Box<dyn Error>> {
+    let temp_dir = tempdir()?;
+    let target_dir = temp_dir.path().join("target");
+
+    let output = CargoBuild::new()
+        .bin("dynamecs-tool")
+        .target_dir(target_dir)
+        .run()?
+        .command()
+        .arg("timing")
+        .args(["--logfile", "tests/test_logs/dynamecs_app.jsonlog"])
+        .args(["--format", "tsv"])
+        .output()?;
+
+    assert!(output.status.success());
+    assert!(output.stderr.is_empty());
+
+    let stdout_str = String::from_utf8(output.stdout)?;
+    insta::assert_snapshot!(stdout_str);
+
+    Ok(())
+}
+
+#[test]
+fn test_spans_basic_app1_top() -> Result<(), Box<dyn Error>> {
+    let temp_dir = tempdir()?;
+    let target_dir = temp_dir.path().join("target");
+
+    let output = CargoBuild::new()
+        .bin("dynamecs-tool")
+        .target_dir(target_dir)
+        .run()?
+        .command()
+        .arg("spans")
+        .args(["--logfile", "tests/test_logs/dynamecs_app.jsonlog"])
+        .args(["--top", "5"])
+        .output()?;
+
+    assert!(output.status.success());
+    assert!(output.stderr.is_empty());
+
+    let stdout_str = String::from_utf8(output.stdout)?;
+    insta::assert_snapshot!(stdout_str);
+
+    Ok(())
+}
@@ -2,9 +2,10 @@
 use eyre::eyre;
 use std::fmt;
 use std::fmt::{Debug, Display};
+use tracing::warn;
 
-use crate::components::get_simulation_time;
-use crate::{System, Universe};
+use crate::components::{get_simulation_time, get_step_index};
+use crate::{ObserverSystem, System, Universe};
 
 /// Adapts a `Fn` or `FnMut` closure as a [`System`].
 pub struct FnSystem<F>
@@ -64,6 +65,52 @@ impl<S: System> DelayedSystem<S> {
     }
 }
 
+/// Wrapper system that only runs the wrapped [`System`] on every `n`-th step.
+///
+/// The wrapped system runs whenever the [`StepIndex`](`crate::components::StepIndex`) is divisible by `n`.
+pub struct EveryNSteps<S: System> {
+    system: S,
+    n: usize,
+}
+
+/// Wrapper system that logs errors of the wrapped [`System`] as warnings instead of returning them.
+///
+/// This is intended for non-critical systems, such as observers for visualization, whose failure
+/// should not abort the simulation. The wrapped system is not re-run after a failure, and panics
+/// are not caught.
+pub struct SoftFailSystem<S: System> {
+    /// Wrapped system.
+    pub system: S,
+    failure_count: usize,
+}
+
+/// Wrapper observer that logs errors of the wrapped [`ObserverSystem`] as warnings instead of returning them.
+///
+/// This is the observer counterpart of [`SoftFailSystem`]. In contrast to [`SoftFailSystem`], the wrapper is
+/// itself an [`ObserverSystem`], so it can be used wherever an observer is expected.
+pub struct SoftFailObserver<S: ObserverSystem> {
+    /// Wrapped observer.
+    pub observer: S,
+    failure_count: usize,
+}
+
+/// Observer system that runs an expensive precomputation once and shares its result among several observers.
+///
+/// This is useful when multiple observers need the same derived quantity, such as a spatial hash of
+/// all positions. On every run, the precompute closure is run first, and the observers are then run
+/// in the order they were added with a reference to its result.
+pub struct PrecomputeThenObserve<T, P>
+where
+    P: FnMut(&Universe) -> eyre::Result<T>,
+{
+    name: String,
+    precompute: P,
+    observers: Vec<PrecomputeObserver<T>>,
+}
+
+/// An observer closure that receives the result of the precomputation of [`PrecomputeThenObserve`].
+type PrecomputeObserver<T> = Box<dyn FnMut(&T, &Universe) -> eyre::Result<()>>;
+
 /// Wrapper to store a vector of systems that are run in sequence.
 pub struct SystemCollection(pub Vec<Box<dyn System>>);
 
@@ -99,7 +146,7 @@ where
 
 impl<F> System for FnSystem<F>
 where
-    F: FnMut(&mut Universe) -> eyre::Result<()>,
+    F: FnMut(&mut Universe) -> eyre::Result<()> + 'static,
 {
     fn name(&self) -> String {
         self.name.clone()
@@ -149,7 +196,7 @@ where
 
 impl<F> System for FnOnceSystem<F>
 where
-    F: FnOnce(&mut Universe) -> eyre::Result<()>,
+    F: FnOnce(&mut Universe) -> eyre::Result<()> + 'static,
 {
     fn name(&self) -> String {
         self.name.clone()
@@ -201,6 +248,10 @@ impl<S: System> System for SingleShotSystem<S> {
         self.system.register_components();
     }
 
+    fn accessed_storage_tags(&self) -> Vec<String> {
+        self.system.accessed_storage_tags()
+    }
+
     fn run(&mut self, data: &mut Universe) -> eyre::Result<()> {
         if !self.has_run {
             let ret = self.system.run(data)?;
@@ -244,7 +295,7 @@ where
 
 impl<P, S> System for FilterSystem<P, S>
 where
-    P: FnMut(&Universe) -> eyre::Result<bool>,
+    P: FnMut(&Universe) -> eyre::Result<bool> + 'static,
     S: System,
 {
     fn name(&self) -> String {
@@ -255,6 +306,10 @@ where
         self.system.register_components();
     }
 
+    fn accessed_storage_tags(&self) -> Vec<String> {
+        self.system.accessed_storage_tags()
+    }
+
     fn run(&mut self, data: &mut Universe) -> eyre::Result<()> {
         if (self.predicate)(data)? {
             self.system.run(data)
@@ -285,6 +340,10 @@ impl<S: System> System for DelayedSystem<S> {
         self.system.register_components();
     }
 
+    fn accessed_storage_tags(&self) -> Vec<String> {
+        self.system.accessed_storage_tags()
+    }
+
     fn run(&mut self, data: &mut Universe) -> eyre::Result<()> {
         if get_simulation_time(data).0 >= self.activation_time {
             self.system.run(data)
@@ -294,6 +353,212 @@ impl<S: System> System for DelayedSystem<S> {
     }
 }
 
+impl<S: System> EveryNSteps<S> {
+    /// Wraps the system such that it only runs when the step index is divisible by `n`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is zero.
+    pub fn new(system: S, n: usize) -> Self {
+        assert!(
+            n > 0,
+            "EveryNSteps requires a positive number of steps, but n = 0 was given"
+        );
+        Self { system, n }
+    }
+}
+
+impl<S: System> Debug for EveryNSteps<S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "EveryNSteps(n: {}, {:?})", self.n, self.system)
+    }
+}
+
+impl<S: System> Display for EveryNSteps<S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "EveryNSteps(n: {}, {})", self.n, self.system.name())
+    }
+}
+
+impl<S: System> System for EveryNSteps<S> {
+    fn name(&self) -> String {
+        format!("EveryNSteps({})", self.system.name())
+    }
+
+    fn register_components(&self) {
+        self.system.register_components();
+    }
+
+    fn accessed_storage_tags(&self) -> Vec<String> {
+        self.system.accessed_storage_tags()
+    }
+
+    fn run(&mut self, data: &mut Universe) -> eyre::Result<()> {
+        if get_step_index(data).0.is_multiple_of(self.n) {
+            self.system.run(data)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl<S: System> SoftFailSystem<S> {
+    pub fn new(system: S) -> Self {
+        Self {
+            system,
+            failure_count: 0,
+        }
+    }
+
+    /// Returns the number of times the wrapped system has failed.
+    pub fn failure_count(&self) -> usize {
+        self.failure_count
+    }
+}
+
+impl<S: System> Debug for SoftFailSystem<S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "SoftFailSystem({:?})", self.system)
+    }
+}
+
+impl<S: System> Display for SoftFailSystem<S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "SoftFailSystem({})", self.system.name())
+    }
+}
+
+impl<S: System> System for SoftFailSystem<S> {
+    fn name(&self) -> String {
+        format!("SoftFailSystem({})", self.system.name())
+    }
+
+    fn register_components(&self) {
+        self.system.register_components();
+    }
+
+    fn accessed_storage_tags(&self) -> Vec<String> {
+        self.system.accessed_storage_tags()
+    }
+
+    fn run(&mut self, data: &mut Universe) -> eyre::Result<()> {
+        if let Err(err) = self.system.run(data) {
+            self.failure_count += 1;
+            warn!("System {} failed, continuing: {:?}", self.system.name(), err);
+        }
+        Ok(())
+    }
+}
+
+impl<S: ObserverSystem> SoftFailObserver<S> {
+    pub fn new(observer: S) -> Self {
+        Self {
+            observer,
+            failure_count: 0,
+        }
+    }
+
+    /// Returns the number of times the wrapped observer has failed.
+    pub fn failure_count(&self) -> usize {
+        self.failure_count
+    }
+}
+
+impl<S: ObserverSystem> Debug for SoftFailObserver<S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "SoftFailObserver({:?})", self.observer)
+    }
+}
+
+impl<S: ObserverSystem> Display for SoftFailObserver<S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "SoftFailObserver({})", self.observer.name())
+    }
+}
+
+impl<S: ObserverSystem> ObserverSystem for SoftFailObserver<S> {
+    fn name(&self) -> String {
+        format!("SoftFailObserver({})", self.observer.name())
+    }
+
+    fn register_components(&self) {
+        self.observer.register_components();
+    }
+
+    fn accessed_storage_tags(&self) -> Vec<String> {
+        self.observer.accessed_storage_tags()
+    }
+
+    fn run(&mut self, data: &Universe) -> eyre::Result<()> {
+        if let Err(err) = self.observer.run(data) {
+            self.failure_count += 1;
+            warn!("Observer {} failed, continuing: {:?}", self.observer.name(), err);
+        }
+        Ok(())
+    }
+}
+
+impl<T, P> PrecomputeThenObserve<T, P>
+where
+    P: FnMut(&Universe) -> eyre::Result<T>,
+{
+    /// Constructs a new system with the given name from the given precompute closure, without any observers.
+    pub fn new<S: Into<String>>(name: S, precompute: P) -> Self {
+        Self {
+            name: name.into(),
+            precompute,
+            observers: Vec::new(),
+        }
+    }
+
+    /// Adds an observer that receives the result of the precomputation.
+    pub fn add_observer(&mut self, observer: impl FnMut(&T, &Universe) -> eyre::Result<()> + 'static) {
+        self.observers.push(Box::new(observer));
+    }
+
+    /// Same as [`add_observer`](Self::add_observer), but returns the system for chaining.
+    pub fn with_observer(mut self, observer: impl FnMut(&T, &Universe) -> eyre::Result<()> + 'static) -> Self {
+        self.add_observer(observer);
+        self
+    }
+
+    /// Returns the number of observers.
+    pub fn num_observers(&self) -> usize {
+        self.observers.len()
+    }
+}
+
+impl<T, P> Debug for PrecomputeThenObserve<T, P>
+where
+    P: FnMut(&Universe) -> eyre::Result<T>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "PrecomputeThenObserve(name: {}, observers: {})",
+            self.name,
+            self.observers.len()
+        )
+    }
+}
+
+impl<T, P> ObserverSystem for PrecomputeThenObserve<T, P>
+where
+    P: FnMut(&Universe) -> eyre::Result<T>,
+{
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn run(&mut self, data: &Universe) -> eyre::Result<()> {
+        let precomputed = (self.precompute)(data)?;
+        for observer in &mut self.observers {
+            observer(&precomputed, data)?;
+        }
+        Ok(())
+    }
+}
+
 impl Debug for SystemCollection {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "SystemCollection({:?})", self.0)
@@ -0,0 +1,82 @@
+//! Deferred mutations of a [`Universe`].
+use crate::{Component, Entity, InsertComponentForEntity, Universe};
+use std::fmt;
+use std::fmt::Debug;
+
+type Command = Box<dyn FnOnce(&mut Universe)>;
+
+/// Records mutations of a [`Universe`] so that they can be applied later.
+///
+/// While iterating over a join, the universe is borrowed, so that entities can not be despawned
+/// and components can not be inserted. Instead, the operations can be recorded in a command buffer
+/// and applied with [`Universe::apply_commands`] once the borrow has ended. Entities are allocated
+/// immediately by [`spawn`](Self::spawn), so that they can be referred to by subsequent commands.
+///
+/// ```
+/// # use dynamecs::commands::CommandBuffer;
+/// # use dynamecs::components::Name;
+/// # use dynamecs::Universe;
+/// let mut universe = Universe::default();
+/// let mut commands = CommandBuffer::new();
+/// let entity = commands.spawn(&universe);
+/// commands.insert_component(entity, Name("spawned".to_string()));
+/// universe.apply_commands(commands);
+/// assert!(universe.get_component_storage::<Name>().get_component(entity).is_some());
+/// ```
+#[derive(Default)]
+pub struct CommandBuffer {
+    commands: Vec<Command>,
+}
+
+impl CommandBuffer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allocates a new entity in the given universe.
+    ///
+    /// The entity is allocated immediately, and can therefore be used with other commands.
+    pub fn spawn(&mut self, universe: &Universe) -> Entity {
+        universe.new_entity()
+    }
+
+    /// Records the insertion of a component for the given entity.
+    pub fn insert_component<C>(&mut self, entity: Entity, component: C)
+    where
+        C: Component,
+        C::Storage: Default + InsertComponentForEntity<C>,
+    {
+        self.commands
+            .push(Box::new(move |universe| universe.insert_component(entity, component)));
+    }
+
+    /// Records the removal of all data associated with the given entity.
+    ///
+    /// See [`Universe::despawn_entity`].
+    pub fn despawn(&mut self, entity: Entity) {
+        self.commands.push(Box::new(move |universe| {
+            universe.despawn_entity(entity);
+        }));
+    }
+
+    /// Returns the number of recorded commands.
+    pub fn len(&self) -> usize {
+        self.commands.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+
+    pub(crate) fn apply(self, universe: &mut Universe) {
+        for command in self.commands {
+            command(universe);
+        }
+    }
+}
+
+impl Debug for CommandBuffer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "CommandBuffer(len: {})", self.commands.len())
+    }
+}
@@ -1,20 +1,40 @@
 //! Predefined components commonly used by simulators.
 use crate::storages::VecStorage;
 use crate::storages::{ImmutableSingularStorage, SingularStorage};
-use crate::{register_component, Component, Universe};
+use crate::{register_component, Component, RegistrationStatus, SerializableStorage, Storage, Universe};
 use eyre::eyre;
+use rand_core::SeedableRng;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fmt;
 use std::fmt::Formatter;
 use std::ops::Deref;
 use std::path::PathBuf;
 
-/// Registers the "default" components [`Name`], [`TimeStep`], [`SimulationTime`] and [`StepIndex`].
-pub fn register_default_components() {
-    register_component::<Name>();
-    register_component::<TimeStep>();
-    register_component::<SimulationTime>();
-    register_component::<StepIndex>();
+/// Registers the "default" components [`Name`], [`TimeStep`], [`SimulationTime`], [`StepIndex`], [`Paused`],
+/// [`CurrentPhase`] and [`RngResource`].
+///
+/// Returns the storage tag of each component together with its registration status. Registering
+/// the default components more than once is harmless, but callers can use the returned statuses
+/// to detect it, since all statuses are then [`RegistrationStatus::Replaced`].
+pub fn register_default_components() -> Vec<(String, RegistrationStatus)> {
+    fn register<C>() -> (String, RegistrationStatus)
+    where
+        C: Component,
+        C::Storage: SerializableStorage,
+    {
+        (C::Storage::tag(), register_component::<C>())
+    }
+
+    vec![
+        register::<Name>(),
+        register::<TimeStep>(),
+        register::<SimulationTime>(),
+        register::<StepIndex>(),
+        register::<Paused>(),
+        register::<CurrentPhase>(),
+        register::<RngResource>(),
+    ]
 }
 
 /// Associates an entity with a name.
@@ -30,6 +50,30 @@ pub struct SimulationTime(pub f64);
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StepIndex(pub usize);
 
+/// Indicates whether the simulation is paused.
+///
+/// While paused, simulation systems are skipped and simulation time does not advance, but steps
+/// are otherwise still completed.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Paused(pub bool);
+
+/// A phase of a simulation step.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Phase {
+    /// Systems that run before the simulation systems.
+    Pre,
+    /// Systems that advance the simulation.
+    Simulation,
+    /// Systems that run after the simulation systems, for example for output.
+    Post,
+}
+
+/// The phase of the step that is currently running.
+///
+/// This allows systems that are shared across phases to behave differently depending on the phase.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CurrentPhase(pub Phase);
+
 impl Component for Name {
     type Storage = VecStorage<Self>;
 }
@@ -90,6 +134,14 @@ impl Component for StepIndex {
     type Storage = SingularStorage<Self>;
 }
 
+impl Component for Paused {
+    type Storage = SingularStorage<Self>;
+}
+
+impl Component for CurrentPhase {
+    type Storage = SingularStorage<Self>;
+}
+
 pub fn get_simulation_time(state: &Universe) -> SimulationTime {
     state
         .get_component_storage::<SimulationTime>()
@@ -104,6 +156,21 @@ pub fn get_step_index(state: &Universe) -> StepIndex {
         .clone()
 }
 
+/// Returns the phase that is currently running, or `None` if no phase has been recorded in the state.
+pub fn current_phase(state: &Universe) -> Option<Phase> {
+    state
+        .try_get_component_storage::<CurrentPhase>()
+        .map(|storage| storage.get_component().0)
+}
+
+/// Returns whether the simulation is paused, which is not the case if the [`Paused`] component is absent.
+pub fn is_paused(state: &Universe) -> bool {
+    state
+        .try_get_component_storage::<Paused>()
+        .map(|storage| storage.get_component().0)
+        .unwrap_or(false)
+}
+
 pub fn try_get_timestep(state: &Universe) -> eyre::Result<TimeStep> {
     let storage = state
         .try_get_component_storage::<TimeStep>()
@@ -127,3 +194,93 @@ pub fn try_get_settings(state: &Universe) -> eyre::Result<&DynamecsAppSettings>
         .ok_or_else(|| eyre!("component DynamecsAppSettings not found in Universe instance"))?;
     Ok(storage.get_component())
 }
+
+/// Arbitrary key-value metadata associated with a scenario, such as a git commit or machine name.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScenarioMetadata(pub HashMap<String, String>);
+
+impl Component for ScenarioMetadata {
+    type Storage = ImmutableSingularStorage<Self>;
+}
+
+/// Returns the scenario metadata stored in the universe, if any.
+pub fn get_scenario_metadata(state: &Universe) -> Option<&HashMap<String, String>> {
+    state
+        .try_get_component_storage::<ScenarioMetadata>()
+        .map(|storage| &storage.get_component().0)
+}
+
+/// Wraps the configuration of an app so that it can be stored in a [`Universe`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfigComponent<Config>(pub Config);
+
+impl<Config: 'static> Component for ConfigComponent<Config> {
+    type Storage = ImmutableSingularStorage<Self>;
+}
+
+/// Returns the configuration stored as a [`ConfigComponent`] in the universe.
+pub fn get_config<Config: 'static>(state: &Universe) -> eyre::Result<&Config> {
+    let storage = state
+        .try_get_component_storage::<ConfigComponent<Config>>()
+        .ok_or_else(|| {
+            eyre!(
+                "component ConfigComponent<{}> not found in Universe instance",
+                std::any::type_name::<Config>()
+            )
+        })?;
+    Ok(&storage.get_component().0)
+}
+
+/// The deterministic pseudo-random number generator used by [`RngResource`].
+pub type SimulationRng = rand_chacha::ChaCha8Rng;
+
+/// A global pseudo-random number generator for reproducible simulations.
+///
+/// The generator is stored in the universe and therefore also in checkpoints, so that a restored
+/// simulation continues the stream of random numbers where it left off. Systems usually access the
+/// generator through [`rng`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RngResource {
+    seed: u64,
+    rng: SimulationRng,
+}
+
+impl RngResource {
+    pub fn from_seed(seed: u64) -> Self {
+        Self {
+            seed,
+            rng: SimulationRng::seed_from_u64(seed),
+        }
+    }
+
+    /// The seed that the generator was initially created with.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn rng(&mut self) -> &mut SimulationRng {
+        &mut self.rng
+    }
+}
+
+/// The default generator is seeded with zero, so that simulations are reproducible even if no
+/// seed is given.
+impl Default for RngResource {
+    fn default() -> Self {
+        Self::from_seed(0)
+    }
+}
+
+impl Component for RngResource {
+    type Storage = SingularStorage<Self>;
+}
+
+/// Returns the global pseudo-random number generator stored in the universe.
+///
+/// If the universe holds no [`RngResource`], a generator with the default seed zero is inserted.
+pub fn rng(state: &mut Universe) -> &mut SimulationRng {
+    state
+        .get_component_storage_mut::<RngResource>()
+        .get_component_mut()
+        .rng()
+}
@@ -1,27 +1,130 @@
+use eyre::eyre;
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, VecDeque};
 use std::fmt::{Display, Formatter};
-use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
 
 #[derive(Serialize, Deserialize)]
 pub(crate) struct EntityFactory {
     next_entity: AtomicU64,
+    // The mode is only relevant for the current session, so it is not persisted
+    #[serde(skip)]
+    mode: Mutex<EntityFactoryMode>,
+    /// Whether the mode is anything other than `Normal`, so that normal allocation can skip the lock.
+    #[serde(skip)]
+    mode_is_active: AtomicBool,
+}
+
+#[derive(Debug, Default)]
+enum EntityFactoryMode {
+    #[default]
+    Normal,
+    /// Entities are allocated as usual, but every allocated entity is also recorded.
+    Recording(Vec<Entity>),
+    /// Entities are handed out from the queue until it is exhausted.
+    Replaying(VecDeque<Entity>),
 }
 
 impl Default for EntityFactory {
     fn default() -> Self {
         Self {
             next_entity: AtomicU64::new(0),
+            mode: Default::default(),
+            mode_is_active: AtomicBool::new(false),
         }
     }
 }
 
 impl EntityFactory {
     pub fn new_entity(&self) -> Entity {
+        if !self.mode_is_active.load(Ordering::Acquire) {
+            return self.allocate_entity();
+        }
+
+        let mut mode = self
+            .mode
+            .lock()
+            .expect("Internal error: Lock should never fail");
+        match &mut *mode {
+            EntityFactoryMode::Normal => self.allocate_entity(),
+            EntityFactoryMode::Recording(recorded) => {
+                let entity = self.allocate_entity();
+                recorded.push(entity);
+                entity
+            }
+            EntityFactoryMode::Replaying(queue) => match queue.pop_front() {
+                Some(entity) => {
+                    // Make sure that entities allocated after the replay can not collide with replayed entities
+                    self.next_entity.fetch_max(entity.0 + 1, Ordering::SeqCst);
+                    entity
+                }
+                None => {
+                    *mode = EntityFactoryMode::Normal;
+                    self.mode_is_active.store(false, Ordering::Release);
+                    self.allocate_entity()
+                }
+            },
+        }
+    }
+
+    fn allocate_entity(&self) -> Entity {
         Entity(self.next_entity.fetch_add(1, Ordering::SeqCst))
     }
+
+    pub fn start_recording(&self) {
+        self.set_mode(EntityFactoryMode::Recording(Vec::new()));
+    }
+
+    fn set_mode(&self, new_mode: EntityFactoryMode) {
+        let mut mode = self
+            .mode
+            .lock()
+            .expect("Internal error: Lock should never fail");
+        let is_active = !matches!(new_mode, EntityFactoryMode::Normal);
+        *mode = new_mode;
+        self.mode_is_active.store(is_active, Ordering::Release);
+    }
+
+    pub fn stop_recording(&self) -> Vec<Entity> {
+        let mut mode = self
+            .mode
+            .lock()
+            .expect("Internal error: Lock should never fail");
+        match std::mem::take(&mut *mode) {
+            EntityFactoryMode::Recording(recorded) => {
+                self.mode_is_active.store(false, Ordering::Release);
+                recorded
+            }
+            other => {
+                *mode = other;
+                Vec::new()
+            }
+        }
+    }
+
+    /// Replays the given entities, failing if any of them collide with each other or with an already
+    /// allocated entity.
+    pub fn replay(&self, entities: impl IntoIterator<Item = Entity>) -> eyre::Result<()> {
+        let queue: VecDeque<_> = entities.into_iter().collect();
+        let mut seen = BTreeSet::new();
+        let next_entity = self.next_entity.load(Ordering::SeqCst);
+        for &entity in &queue {
+            if entity.0 < next_entity {
+                return Err(eyre!(
+                    "cannot replay entity {entity}, since it has already been allocated"
+                ));
+            }
+            if !seen.insert(entity) {
+                return Err(eyre!("cannot replay entity {entity} more than once"));
+            }
+        }
+        self.set_mode(EntityFactoryMode::Replaying(queue));
+        Ok(())
+    }
 }
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub struct Entity(u64);
 
 impl Display for Entity {
@@ -29,3 +132,95 @@ impl Display for Entity {
         self.0.fmt(f)
     }
 }
+
+/// A set of entities, iterated in ascending order.
+///
+/// Useful for wiring scenarios, e.g. to find the entities that are both on the boundary and
+/// subject to a load. A set of the entities that have a given component can be obtained
+/// with [`Universe::entities_with`](crate::Universe::entities_with).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EntitySet {
+    entities: BTreeSet<Entity>,
+}
+
+impl EntitySet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Inserts the entity into the set, returning `true` if it was not already present.
+    pub fn insert(&mut self, entity: Entity) -> bool {
+        self.entities.insert(entity)
+    }
+
+    /// Removes the entity from the set, returning `true` if it was present.
+    pub fn remove(&mut self, entity: Entity) -> bool {
+        self.entities.remove(&entity)
+    }
+
+    pub fn contains(&self, entity: Entity) -> bool {
+        self.entities.contains(&entity)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entities.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.entities.iter().copied()
+    }
+
+    /// Returns the entities that are in both `self` and `other`.
+    pub fn intersection(&self, other: &EntitySet) -> EntitySet {
+        self.entities
+            .intersection(&other.entities)
+            .copied()
+            .collect()
+    }
+
+    /// Returns the entities that are in `self`, `other` or both.
+    pub fn union(&self, other: &EntitySet) -> EntitySet {
+        self.entities.union(&other.entities).copied().collect()
+    }
+
+    /// Returns the entities that are in `self`, but not in `other`.
+    pub fn difference(&self, other: &EntitySet) -> EntitySet {
+        self.entities.difference(&other.entities).copied().collect()
+    }
+}
+
+impl FromIterator<Entity> for EntitySet {
+    fn from_iter<I: IntoIterator<Item = Entity>>(iter: I) -> Self {
+        Self {
+            entities: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl Extend<Entity> for EntitySet {
+    fn extend<I: IntoIterator<Item = Entity>>(&mut self, iter: I) {
+        self.entities.extend(iter)
+    }
+}
+
+impl IntoIterator for EntitySet {
+    type Item = Entity;
+    type IntoIter = std::collections::btree_set::IntoIter<Entity>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entities.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a EntitySet {
+    type Item = Entity;
+    type IntoIter = std::iter::Copied<std::collections::btree_set::Iter<'a, Entity>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entities.iter().copied()
+    }
+}
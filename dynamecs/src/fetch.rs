@@ -1,4 +1,5 @@
 //! Helper traits to support the generic component storage "fetch" API.
+use crate::join::{Optional, Without};
 use crate::{Component, Storage, Universe};
 use std::any::TypeId;
 
@@ -47,6 +48,51 @@ impl<'a, 'b, C: Component> ComponentStorageRefMut<'a> for &'b mut C {
     }
 }
 
+/// Fetches a shared reference to a storage, possibly wrapped in [`Optional`] or [`Without`].
+///
+/// Helper trait to enable the fetch syntax used by [`Universe::get_component_storages`].
+pub trait ComponentStorageRef<'a> {
+    type Ref;
+
+    fn fetch_storage_ref(universe: &'a Universe) -> Self::Ref;
+}
+
+impl<'a, C> ComponentStorageRef<'a> for &C
+where
+    C: Component,
+    C::Storage: Default,
+{
+    type Ref = &'a C::Storage;
+
+    fn fetch_storage_ref(universe: &'a Universe) -> Self::Ref {
+        universe.get_storage::<C::Storage>()
+    }
+}
+
+impl<'a, C> ComponentStorageRef<'a> for Optional<&C>
+where
+    C: Component,
+    C::Storage: Default,
+{
+    type Ref = Optional<&'a C::Storage>;
+
+    fn fetch_storage_ref(universe: &'a Universe) -> Self::Ref {
+        Optional(universe.get_storage::<C::Storage>())
+    }
+}
+
+impl<'a, C> ComponentStorageRef<'a> for Without<&C>
+where
+    C: Component,
+    C::Storage: Default,
+{
+    type Ref = Without<&'a C::Storage>;
+
+    fn fetch_storage_ref(universe: &'a Universe) -> Self::Ref {
+        Without(universe.get_storage::<C::Storage>())
+    }
+}
+
 fn is_strictly_monotonic<T: Ord>(items: &[T]) -> bool {
     let mut iter = items.iter().peekable();
     while let Some(current) = iter.next() {
@@ -85,14 +131,11 @@ where
 
 macro_rules! impl_tuple_fetch_component_storages {
     ($($component:ident),+) => {
-        impl<'a, 'b, $($component: Component),*> FetchComponentStorages<'a> for ($(&'b $component,)*)
-        where
-            $(<$component as Component>::Storage: Default),+
-        {
-            type Storages = ($(&'a $component::Storage,)*);
+        impl<'a, $($component: ComponentStorageRef<'a>),*> FetchComponentStorages<'a> for ($($component,)*) {
+            type Storages = ($($component::Ref,)*);
 
             fn fetch_storages(universe: &'a Universe) -> Self::Storages {
-                ($(universe.get_storage::<$component::Storage>(),)*)
+                ($($component::fetch_storage_ref(universe),)*)
             }
         }
     }
@@ -106,6 +149,10 @@ impl_tuple_fetch_component_storages!(C1, C2, C3, C4, C5);
 impl_tuple_fetch_component_storages!(C1, C2, C3, C4, C5, C6);
 impl_tuple_fetch_component_storages!(C1, C2, C3, C4, C5, C6, C7);
 impl_tuple_fetch_component_storages!(C1, C2, C3, C4, C5, C6, C7, C8);
+impl_tuple_fetch_component_storages!(C1, C2, C3, C4, C5, C6, C7, C8, C9);
+impl_tuple_fetch_component_storages!(C1, C2, C3, C4, C5, C6, C7, C8, C9, C10);
+impl_tuple_fetch_component_storages!(C1, C2, C3, C4, C5, C6, C7, C8, C9, C10, C11);
+impl_tuple_fetch_component_storages!(C1, C2, C3, C4, C5, C6, C7, C8, C9, C10, C11, C12);
 
 impl<'a, 'b, C> FetchComponentStoragesMut<'a> for &'a mut C
 where
@@ -158,3 +205,7 @@ impl_tuple_fetch_component_storages_mut!(C1, C2, C3, C4, C5);
 impl_tuple_fetch_component_storages_mut!(C1, C2, C3, C4, C5, C6);
 impl_tuple_fetch_component_storages_mut!(C1, C2, C3, C4, C5, C6, C7);
 impl_tuple_fetch_component_storages_mut!(C1, C2, C3, C4, C5, C6, C7, C8);
+impl_tuple_fetch_component_storages_mut!(C1, C2, C3, C4, C5, C6, C7, C8, C9);
+impl_tuple_fetch_component_storages_mut!(C1, C2, C3, C4, C5, C6, C7, C8, C9, C10);
+impl_tuple_fetch_component_storages_mut!(C1, C2, C3, C4, C5, C6, C7, C8, C9, C10, C11);
+impl_tuple_fetch_component_storages_mut!(C1, C2, C3, C4, C5, C6, C7, C8, C9, C10, C11, C12);
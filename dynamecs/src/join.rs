@@ -1,7 +1,7 @@
 //! Functionality that enables the Join API.
 use crate::storages::{
     vec_storage::{VecStorageEntityComponentIter, VecStorageEntityComponentIterMut},
-    VecStorage, VersionedVecStorage,
+    StepTrackedVecStorage, VecStorage, VersionedVecStorage,
 };
 use crate::Entity;
 use std::ops::Deref;
@@ -23,19 +23,181 @@ pub trait Joinable<'a> {
     unsafe fn try_make_component_ref(&mut self, entity: Entity) -> Option<Self::ComponentRef>;
 }
 
+/// Wraps a joinable so that it never excludes entities from a join.
+///
+/// The component reference produced by the wrapped joinable becomes `Option<J::ComponentRef>`,
+/// which is `None` for entities that have no associated component. In fetch tuples,
+/// `Optional<&C>` requests an optional shared reference to the component `C`.
+#[derive(Debug, Copy, Clone)]
+pub struct Optional<J>(pub J);
+
+impl<'a, J: Joinable<'a>> Joinable<'a> for Optional<J> {
+    type ComponentRef = Option<J::ComponentRef>;
+
+    unsafe fn try_make_component_ref(&mut self, entity: Entity) -> Option<Self::ComponentRef> {
+        Some(self.0.try_make_component_ref(entity))
+    }
+}
+
+impl<'a, J: IntoJoinable<'a>> IntoJoinable<'a> for Optional<J> {
+    type Joinable = Optional<J::Joinable>;
+
+    fn into_joinable(self) -> Self::Joinable {
+        Optional(self.0.into_joinable())
+    }
+}
+
+/// Wraps a joinable so that the join only includes entities that have *no* associated component.
+///
+/// The component reference produced by the wrapper is `()`. In fetch tuples, `Without<&C>`
+/// excludes all entities that have the component `C`.
+#[derive(Debug, Copy, Clone)]
+pub struct Without<J>(pub J);
+
+impl<'a, J: Joinable<'a>> Joinable<'a> for Without<J> {
+    type ComponentRef = ();
+
+    unsafe fn try_make_component_ref(&mut self, entity: Entity) -> Option<Self::ComponentRef> {
+        match self.0.try_make_component_ref(entity) {
+            Some(_) => None,
+            None => Some(()),
+        }
+    }
+}
+
+impl<'a, J: IntoJoinable<'a>> IntoJoinable<'a> for Without<J> {
+    type Joinable = Without<J::Joinable>;
+
+    fn into_joinable(self) -> Self::Joinable {
+        Without(self.0.into_joinable())
+    }
+}
+
+/// Converts a tuple of [`IntoJoinable`] items into a tuple of joinables.
+pub trait IntoJoinables<'a> {
+    type Joinables;
+
+    fn into_joinables(self) -> Self::Joinables;
+}
+
+/// Joins a number of joinables over an explicitly given sequence of unique entities.
+///
+/// See [`Universe::join_driven_by`](crate::Universe::join_driven_by).
+pub struct EntityJoinIter<'a, Joinables> {
+    entities: std::slice::Iter<'a, Entity>,
+    joinables: Joinables,
+}
+
+impl<'a, Joinables> EntityJoinIter<'a, Joinables> {
+    /// Creates a new iterator driven by the given entities.
+    ///
+    /// # Safety
+    ///
+    /// The entities must be unique.
+    pub(crate) unsafe fn new(entities: &'a [Entity], joinables: Joinables) -> Self {
+        Self {
+            entities: entities.iter(),
+            joinables,
+        }
+    }
+}
+
+macro_rules! impl_entity_join_iter {
+    ($($joinables:ident),+) => {
+        #[allow(non_snake_case)]
+        #[allow(unused_parens)]
+        #[allow(irrefutable_let_patterns)]
+        impl<'a, $($joinables),+> Iterator for EntityJoinIter<'a, ($($joinables,)+)>
+        where
+            $($joinables : Joinable<'a>),+
+        {
+            type Item = (Entity, $($joinables::ComponentRef),+);
+
+            fn next(&mut self) -> Option<Self::Item> {
+                let ($($joinables,)+) = &mut self.joinables;
+                while let Some(&entity) = self.entities.next() {
+                    // SAFETY: The entities are unique by the invariant of EntityJoinIter::new
+                    $(let $joinables = unsafe { $joinables.try_make_component_ref(entity) };)+
+                    if let ($(Some($joinables)),+) = ($($joinables),+) {
+                        return Some((entity, $($joinables),+));
+                    }
+                }
+
+                None
+            }
+        }
+
+        #[allow(non_snake_case)]
+        impl<'a, $($joinables: IntoJoinable<'a>),+> IntoJoinables<'a> for ($($joinables,)+) {
+            type Joinables = ($($joinables::Joinable,)+);
+
+            fn into_joinables(self) -> Self::Joinables {
+                let ($($joinables,)+) = self;
+                ($($joinables.into_joinable(),)+)
+            }
+        }
+    }
+}
+
+impl_entity_join_iter!(J1);
+impl_entity_join_iter!(J1, J2);
+impl_entity_join_iter!(J1, J2, J3);
+impl_entity_join_iter!(J1, J2, J3, J4);
+impl_entity_join_iter!(J1, J2, J3, J4, J5);
+impl_entity_join_iter!(J1, J2, J3, J4, J5, J6);
+impl_entity_join_iter!(J1, J2, J3, J4, J5, J6, J7);
+impl_entity_join_iter!(J1, J2, J3, J4, J5, J6, J7, J8);
+impl_entity_join_iter!(J1, J2, J3, J4, J5, J6, J7, J8, J9);
+impl_entity_join_iter!(J1, J2, J3, J4, J5, J6, J7, J8, J9, J10);
+impl_entity_join_iter!(J1, J2, J3, J4, J5, J6, J7, J8, J9, J10, J11);
+impl_entity_join_iter!(J1, J2, J3, J4, J5, J6, J7, J8, J9, J10, J11, J12);
+
 pub struct JoinIter<Joinables> {
     joinables: Joinables,
 }
 
+/// Iterator over the entities and components of the storage that drives a join, which skips
+/// components that do not satisfy a predicate.
+///
+/// See [`JoinIter::filter_component`].
+pub struct FilterComponentIter<I, P> {
+    iter: I,
+    predicate: P,
+}
+
+impl<'a, C, P> Iterator for FilterComponentIter<VecStorageEntityComponentIter<'a, C>, P>
+where
+    P: FnMut(&C) -> bool,
+{
+    type Item = (Entity, &'a C);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let predicate = &mut self.predicate;
+        self.iter.find(|(_, component)| predicate(component))
+    }
+}
+
+impl<'a, C, P> Iterator for FilterComponentIter<VecStorageEntityComponentIterMut<'a, C>, P>
+where
+    P: FnMut(&C) -> bool,
+{
+    type Item = (Entity, &'a mut C);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let predicate = &mut self.predicate;
+        self.iter.find(|(_, component)| predicate(component))
+    }
+}
+
 /// Base macro for generating Iterator impls for JoinIter for various tuple combinations
 ///
 /// This is used to construct macros for the distinct immutable/mutable cases
 macro_rules! impl_join_iter_base {
-    ($iter:ty, $component_ref:ty, $($joinables:ident),*) => {
+    ([$($generics:tt)*], $iter:ty, $component_ref:ty, $($joinables:ident),*) => {
         #[allow(non_snake_case)]
         #[allow(unused_parens)]
         #[allow(irrefutable_let_patterns)]
-        impl<'a, C, $($joinables),*> Iterator for JoinIter<($iter $(, $joinables)*)>
+        impl<'a, C, $($generics)* $($joinables),*> Iterator for JoinIter<($iter $(, $joinables)*)>
         where
             $($joinables : Joinable<'a>),*
         {
@@ -44,7 +206,7 @@ macro_rules! impl_join_iter_base {
             fn next(&mut self) -> Option<Self::Item> {
                 // Re-use the type name as a variable name in order to unpack tuple
                 // (so e.g. J1 becomes the joinable v ariable associated with the J1 type)
-                let (ref mut storage $(, ref mut $joinables)*) = self.joinables;
+                let (storage $(, $joinables)*) = &mut self.joinables;
                 while let Some((entity, c0)) = storage.next() {
                     // SAFETY: VecStorageEntityComponentIter is guaranteed never to repeat an entity,
                     // so we can uphold the safety invariant of the joinable
@@ -70,13 +232,48 @@ macro_rules! impl_join_iter_base {
 /// Macro for generating JoinIter impls where
 macro_rules! impl_join_iter {
     ($($joinables:ident),*) => {
-        impl_join_iter_base!(VecStorageEntityComponentIter<'a, C>, &'a C, $($joinables),*);
+        impl_join_iter_base!([], VecStorageEntityComponentIter<'a, C>, &'a C, $($joinables),*);
+        impl_join_iter_base!([P: FnMut(&C) -> bool,],
+            FilterComponentIter<VecStorageEntityComponentIter<'a, C>, P>,
+            &'a C,
+            $($joinables),*);
+        impl_filter_component!(VecStorageEntityComponentIter<'a, C>, $($joinables),*);
     }
 }
 
 macro_rules! impl_join_iter_mut {
     ($($joinables:ident),*) => {
-        impl_join_iter_base!(VecStorageEntityComponentIterMut<'a, C>, &'a mut C, $($joinables),*);
+        impl_join_iter_base!([], VecStorageEntityComponentIterMut<'a, C>, &'a mut C, $($joinables),*);
+        impl_join_iter_base!([P: FnMut(&C) -> bool,],
+            FilterComponentIter<VecStorageEntityComponentIterMut<'a, C>, P>,
+            &'a mut C,
+            $($joinables),*);
+        impl_filter_component!(VecStorageEntityComponentIterMut<'a, C>, $($joinables),*);
+    }
+}
+
+/// Macro for generating `JoinIter::filter_component` for joins driven by the given iterator
+macro_rules! impl_filter_component {
+    ($iter:ty, $($joinables:ident),*) => {
+        #[allow(non_snake_case)]
+        #[allow(unused_parens)]
+        impl<'a, C, $($joinables),*> JoinIter<($iter $(, $joinables)*)> {
+            /// Skips entities whose component in the storage driving the join does not satisfy
+            /// the given predicate.
+            ///
+            /// In contrast to filtering the items yielded by the join, the predicate is evaluated
+            /// before the components of the other storages are looked up, so that the lookups are
+            /// skipped for entities that do not satisfy the predicate.
+            pub fn filter_component<P>(self, predicate: P) -> JoinIter<(FilterComponentIter<$iter, P> $(, $joinables)*)>
+            where
+                P: FnMut(&C) -> bool,
+            {
+                let (iter $(, $joinables)*) = self.joinables;
+                JoinIter {
+                    joinables: (FilterComponentIter { iter, predicate } $(, $joinables)*),
+                }
+            }
+        }
     }
 }
 
@@ -88,6 +285,10 @@ impl_join_iter!(J1, J2, J3, J4);
 impl_join_iter!(J1, J2, J3, J4, J5);
 impl_join_iter!(J1, J2, J3, J4, J5, J6);
 impl_join_iter!(J1, J2, J3, J4, J5, J6, J7);
+impl_join_iter!(J1, J2, J3, J4, J5, J6, J7, J8);
+impl_join_iter!(J1, J2, J3, J4, J5, J6, J7, J8, J9);
+impl_join_iter!(J1, J2, J3, J4, J5, J6, J7, J8, J9, J10);
+impl_join_iter!(J1, J2, J3, J4, J5, J6, J7, J8, J9, J10, J11);
 
 impl_join_iter_mut!();
 impl_join_iter_mut!(J1);
@@ -97,11 +298,22 @@ impl_join_iter_mut!(J1, J2, J3, J4);
 impl_join_iter_mut!(J1, J2, J3, J4, J5);
 impl_join_iter_mut!(J1, J2, J3, J4, J5, J6);
 impl_join_iter_mut!(J1, J2, J3, J4, J5, J6, J7);
+impl_join_iter_mut!(J1, J2, J3, J4, J5, J6, J7, J8);
+impl_join_iter_mut!(J1, J2, J3, J4, J5, J6, J7, J8, J9);
+impl_join_iter_mut!(J1, J2, J3, J4, J5, J6, J7, J8, J9, J10);
+impl_join_iter_mut!(J1, J2, J3, J4, J5, J6, J7, J8, J9, J10, J11);
 
 pub trait Join {
     type Iter: Iterator;
 
     fn join(self) -> Self::Iter;
+
+    /// The number of components in the storage that drives the join, if known.
+    ///
+    /// The join can not yield more items than this.
+    fn driving_len(&self) -> Option<usize> {
+        None
+    }
 }
 
 /// Common base macro for implementing Join for tuples starting with a VecStorage reference (mutable/immutable)
@@ -123,6 +335,10 @@ macro_rules! impl_vec_storage_tuple_join_base {
                     joinables: ($entity_component_expr $(, $joinables.into_joinable())*)
                 }
             }
+
+            fn driving_len(&self) -> Option<usize> {
+                Some(self.0.len())
+            }
         }
     }
 }
@@ -154,6 +370,10 @@ impl_vec_storage_tuple_join!(J1, J2, J3, J4);
 impl_vec_storage_tuple_join!(J1, J2, J3, J4, J5);
 impl_vec_storage_tuple_join!(J1, J2, J3, J4, J5, J6);
 impl_vec_storage_tuple_join!(J1, J2, J3, J4, J5, J6, J7);
+impl_vec_storage_tuple_join!(J1, J2, J3, J4, J5, J6, J7, J8);
+impl_vec_storage_tuple_join!(J1, J2, J3, J4, J5, J6, J7, J8, J9);
+impl_vec_storage_tuple_join!(J1, J2, J3, J4, J5, J6, J7, J8, J9, J10);
+impl_vec_storage_tuple_join!(J1, J2, J3, J4, J5, J6, J7, J8, J9, J10, J11);
 
 impl_vec_storage_tuple_join_mut!();
 impl_vec_storage_tuple_join_mut!(J1);
@@ -163,6 +383,10 @@ impl_vec_storage_tuple_join_mut!(J1, J2, J3, J4);
 impl_vec_storage_tuple_join_mut!(J1, J2, J3, J4, J5);
 impl_vec_storage_tuple_join_mut!(J1, J2, J3, J4, J5, J6);
 impl_vec_storage_tuple_join_mut!(J1, J2, J3, J4, J5, J6, J7);
+impl_vec_storage_tuple_join_mut!(J1, J2, J3, J4, J5, J6, J7, J8);
+impl_vec_storage_tuple_join_mut!(J1, J2, J3, J4, J5, J6, J7, J8, J9);
+impl_vec_storage_tuple_join_mut!(J1, J2, J3, J4, J5, J6, J7, J8, J9, J10);
+impl_vec_storage_tuple_join_mut!(J1, J2, J3, J4, J5, J6, J7, J8, J9, J10, J11);
 
 impl<'a, C> Join for &'a mut VecStorage<C> {
     type Iter = VecStorageEntityComponentIterMut<'a, C>;
@@ -170,6 +394,10 @@ impl<'a, C> Join for &'a mut VecStorage<C> {
     fn join(self) -> Self::Iter {
         self.entity_component_iter_mut()
     }
+
+    fn driving_len(&self) -> Option<usize> {
+        Some(self.len())
+    }
 }
 
 impl<'a, C> Join for &'a VecStorage<C> {
@@ -178,6 +406,10 @@ impl<'a, C> Join for &'a VecStorage<C> {
     fn join(self) -> Self::Iter {
         self.entity_component_iter()
     }
+
+    fn driving_len(&self) -> Option<usize> {
+        Some(self.len())
+    }
 }
 
 macro_rules! impl_versioned_vec_storage_tuple_join {
@@ -197,6 +429,10 @@ impl_versioned_vec_storage_tuple_join!(J1, J2, J3, J4);
 impl_versioned_vec_storage_tuple_join!(J1, J2, J3, J4, J5);
 impl_versioned_vec_storage_tuple_join!(J1, J2, J3, J4, J5, J6);
 impl_versioned_vec_storage_tuple_join!(J1, J2, J3, J4, J5, J6, J7);
+impl_versioned_vec_storage_tuple_join!(J1, J2, J3, J4, J5, J6, J7, J8);
+impl_versioned_vec_storage_tuple_join!(J1, J2, J3, J4, J5, J6, J7, J8, J9);
+impl_versioned_vec_storage_tuple_join!(J1, J2, J3, J4, J5, J6, J7, J8, J9, J10);
+impl_versioned_vec_storage_tuple_join!(J1, J2, J3, J4, J5, J6, J7, J8, J9, J10, J11);
 
 impl<'a, Component> Join for &'a VersionedVecStorage<Component> {
     type Iter = VecStorageEntityComponentIter<'a, Component>;
@@ -205,3 +441,33 @@ impl<'a, Component> Join for &'a VersionedVecStorage<Component> {
         self.deref().join()
     }
 }
+
+macro_rules! impl_step_tracked_vec_storage_tuple_join {
+    ($($joinables:ident),*) => {
+        impl_vec_storage_tuple_join_base!(&'a StepTrackedVecStorage<C>,
+            VecStorageEntityComponentIter<'a, C>,
+            storage => storage.deref().entity_component_iter(),
+            $($joinables),*);
+    }
+}
+
+impl_step_tracked_vec_storage_tuple_join!();
+impl_step_tracked_vec_storage_tuple_join!(J1);
+impl_step_tracked_vec_storage_tuple_join!(J1, J2);
+impl_step_tracked_vec_storage_tuple_join!(J1, J2, J3);
+impl_step_tracked_vec_storage_tuple_join!(J1, J2, J3, J4);
+impl_step_tracked_vec_storage_tuple_join!(J1, J2, J3, J4, J5);
+impl_step_tracked_vec_storage_tuple_join!(J1, J2, J3, J4, J5, J6);
+impl_step_tracked_vec_storage_tuple_join!(J1, J2, J3, J4, J5, J6, J7);
+impl_step_tracked_vec_storage_tuple_join!(J1, J2, J3, J4, J5, J6, J7, J8);
+impl_step_tracked_vec_storage_tuple_join!(J1, J2, J3, J4, J5, J6, J7, J8, J9);
+impl_step_tracked_vec_storage_tuple_join!(J1, J2, J3, J4, J5, J6, J7, J8, J9, J10);
+impl_step_tracked_vec_storage_tuple_join!(J1, J2, J3, J4, J5, J6, J7, J8, J9, J10, J11);
+
+impl<'a, Component> Join for &'a StepTrackedVecStorage<Component> {
+    type Iter = VecStorageEntityComponentIter<'a, Component>;
+
+    fn join(self) -> Self::Iter {
+        self.deref().join()
+    }
+}
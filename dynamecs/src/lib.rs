@@ -1,18 +1,24 @@
 use crate::serialization::GenericStorageSerializer;
-use adapters::{DelayedSystem, FilterSystem, SingleShotSystem};
-use eyre::Context;
+use adapters::{DelayedSystem, EveryNSteps, FilterSystem, SingleShotSystem, SoftFailObserver, SoftFailSystem};
+use eyre::{eyre, Context};
 use std::any::{Any, TypeId};
-use std::fmt::Debug;
+use std::collections::{BTreeSet, HashMap};
+use std::fmt::{Debug, Formatter};
 
+pub use components::rng;
 pub use entity::*;
+pub use rand_core;
 pub use universe::*;
 
 pub mod adapters;
 pub mod cache;
+pub mod commands;
 pub mod components;
 mod entity;
 pub mod fetch;
 pub mod join;
+#[cfg(feature = "rayon")]
+pub mod par_join;
 #[doc(hidden)]
 pub mod serialization;
 pub mod storages;
@@ -29,17 +35,85 @@ pub trait StorageSerializer: Send + Sync {
     ) -> Result<Box<dyn Any>, erased_serde::Error>;
 
     fn storage_type_id(&self) -> TypeId;
+
+    /// Returns a function that removes the data associated with an entity from a deserialized storage,
+    /// by forwarding to its [`Storage::remove_entity`] implementation.
+    ///
+    /// The default implementation behaves like the default [`Storage::remove_entity`], and removes nothing.
+    fn entity_remover(&self) -> fn(&mut dyn Any, Entity) -> bool {
+        |_, _| false
+    }
+
+    /// Returns a function that obtains the version of a deserialized storage,
+    /// by forwarding to its [`Storage::storage_version_number`] implementation.
+    ///
+    /// The default implementation behaves like the default [`Storage::storage_version_number`], and
+    /// reports no version.
+    fn storage_version_reader(&self) -> fn(&dyn Any) -> Option<u64> {
+        |_| None
+    }
+
+    /// Returns a function that sets the current step index of a deserialized storage,
+    /// by forwarding to its [`Storage::set_current_step`] implementation.
+    ///
+    /// The default implementation behaves like the default [`Storage::set_current_step`], and does nothing.
+    fn current_step_setter(&self) -> fn(&mut dyn Any, usize) {
+        |_, _| {}
+    }
+
+    /// Whether the storage is transient, in which case it is omitted from serialization altogether.
+    fn is_transient(&self) -> bool {
+        false
+    }
 }
 
+/// A container of component data that can be stored in a [`Universe`].
+///
+/// `Storage` is not implemented for arbitrary `'static` types, since storages may override the hooks
+/// below. Custom storage types therefore need an explicit implementation, which is empty if the
+/// defaults suffice:
+///
+/// ```
+/// # use dynamecs::Storage;
+/// struct MyStorage {
+///     values: Vec<f64>,
+/// }
+///
+/// impl Storage for MyStorage {}
+/// ```
+///
+/// Storages that associate data with entities should override [`remove_entity`](Storage::remove_entity),
+/// so that the data is removed when an entity is despawned.
 pub trait Storage: 'static {
     fn tag() -> String {
         // TODO: Ideally type_name should not be used for this purpose, so perhaps we should
         // force components to provide a tag?
         std::any::type_name::<Self>().to_string()
     }
-}
 
-impl<S: 'static> Storage for S {}
+    /// Removes any data associated with the given entity, and returns whether there was any such data.
+    ///
+    /// This is called for every storage in the universe by [`Universe::despawn_entity`]. Storages that
+    /// do not associate data with entities can rely on the default implementation, which does nothing.
+    fn remove_entity(&mut self, _entity: Entity) -> bool {
+        false
+    }
+
+    /// Returns the current version of the storage as a plain number, if the storage tracks a version
+    /// that is advanced whenever the storage may have been modified.
+    ///
+    /// This makes it possible to compare versions of storages without knowing their types,
+    /// see [`Universe::storage_version_by_tag`]. The default implementation returns `None`.
+    fn storage_version_number(&self) -> Option<u64> {
+        None
+    }
+
+    /// Informs the storage of the index of the current step.
+    ///
+    /// This is called for every storage in the universe by [`Universe::set_current_step`], so that storages
+    /// can record when their data was modified. The default implementation does nothing.
+    fn set_current_step(&mut self, _step_index: usize) {}
+}
 
 pub trait SerializableStorage: Storage + serde::Serialize + for<'de> serde::Deserialize<'de> {
     fn create_serializer() -> Box<dyn StorageSerializer> {
@@ -75,7 +149,41 @@ where
     register_storage::<C::Storage>()
 }
 
-pub trait System: Debug {
+/// Global data that is not associated with any entity, such as external handles or configuration.
+///
+/// Resources are stored in a [`ResourceStorage`](storages::ResourceStorage) and accessed with
+/// [`Universe::insert_resource`] and [`Universe::get_resource`]. In contrast to singular components,
+/// resources are not components, and can therefore not take part in joins.
+pub trait Resource: 'static {}
+
+/// Registers the storage of the resource `R` for serialization and deserialization.
+pub fn register_resource<R>() -> RegistrationStatus
+where
+    R: Resource + serde::Serialize + for<'de> serde::Deserialize<'de>,
+{
+    register_storage::<storages::ResourceStorage<R>>()
+}
+
+/// Helper trait for converting a value to [`Any`], implemented for all `'static` types.
+///
+/// This enables downcasting trait objects such as `dyn System` to their concrete type.
+pub trait AsAny: Any {
+    fn as_any(&self) -> &dyn Any;
+
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<T: Any> AsAny for T {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+pub trait System: AsAny + Debug {
     fn name(&self) -> String {
         std::any::type_name::<Self>().to_string()
     }
@@ -83,6 +191,14 @@ pub trait System: Debug {
     /// Registers components used by this system for serialization and deserialization
     fn register_components(&self) {}
 
+    /// Storage tags of the components accessed by this system.
+    ///
+    /// Declaring accesses is optional, but enables [`Systems::check_registrations`] to detect
+    /// components that are not registered for serialization before the systems are run.
+    fn accessed_storage_tags(&self) -> Vec<String> {
+        Vec::new()
+    }
+
     fn run(&mut self, data: &mut Universe) -> eyre::Result<()>;
 
     /// Wraps the system such that can only run once.
@@ -111,6 +227,32 @@ pub trait System: Debug {
     {
         DelayedSystem::new(self, activation_time)
     }
+
+    /// Wraps the system such that it only runs on every `n`-th step.
+    ///
+    /// The system runs only if the [`StepIndex`](`crate::components::StepIndex`) is divisible by `n`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is zero.
+    fn every_n_steps(self, n: usize) -> EveryNSteps<Self>
+    where
+        Self: Sized,
+    {
+        EveryNSteps::new(self, n)
+    }
+
+    /// Wraps the system such that errors are logged as warnings instead of aborting the run.
+    ///
+    /// Since every [`ObserverSystem`] is also a [`System`], this is also available for observers,
+    /// which are the primary use case, for example for live plotting. Use
+    /// [`ObserverSystem::soft_fail_observer`] instead if the wrapper must remain an observer.
+    fn soft_fail(self) -> SoftFailSystem<Self>
+    where
+        Self: Sized,
+    {
+        SoftFailSystem::new(self)
+    }
 }
 
 /// A [`System`] that only has immutable access to the data.
@@ -122,10 +264,25 @@ pub trait ObserverSystem: Debug {
     /// Registers components used by this system for serialization and deserialization
     fn register_components(&self) {}
 
+    /// Storage tags of the components accessed by this system, see [`System::accessed_storage_tags`].
+    fn accessed_storage_tags(&self) -> Vec<String> {
+        Vec::new()
+    }
+
     fn run(&mut self, data: &Universe) -> eyre::Result<()>;
+
+    /// Wraps the observer such that errors are logged as warnings instead of aborting the run.
+    ///
+    /// In contrast to [`System::soft_fail`], the returned wrapper is itself an [`ObserverSystem`].
+    fn soft_fail_observer(self) -> SoftFailObserver<Self>
+    where
+        Self: Sized,
+    {
+        SoftFailObserver::new(self)
+    }
 }
 
-impl<S: ObserverSystem> System for S {
+impl<S: ObserverSystem + 'static> System for S {
     fn name(&self) -> String {
         <S as ObserverSystem>::name(self)
     }
@@ -134,6 +291,10 @@ impl<S: ObserverSystem> System for S {
         <S as ObserverSystem>::register_components(self)
     }
 
+    fn accessed_storage_tags(&self) -> Vec<String> {
+        <S as ObserverSystem>::accessed_storage_tags(self)
+    }
+
     fn run(&mut self, data: &mut Universe) -> eyre::Result<()> {
         <S as ObserverSystem>::run(self, data)
     }
@@ -156,12 +317,80 @@ impl Systems {
         self
     }
 
+    /// Moves all systems from `other` to the end of this collection, preserving their order.
+    pub fn append(&mut self, other: Systems) -> &mut Self {
+        self.systems.extend(other.systems);
+        self
+    }
+
+    /// Instantiates the system registered with the given name in the registry and adds it.
+    ///
+    /// Returns an error if no system with the given name is registered.
+    pub fn add_by_name(&mut self, registry: &SystemRegistry, name: &str) -> eyre::Result<&mut Self> {
+        let system = registry.create_system(name).ok_or_else(|| {
+            let mut available: Vec<_> = registry.names().collect();
+            available.sort_unstable();
+            eyre!("no system named \"{name}\" in registry. Available systems: {available:?}")
+        })?;
+        self.systems.push(system);
+        Ok(self)
+    }
+
+    /// Returns the system at the given index if it has the concrete type `S`.
+    pub fn get_as<S: System>(&self, index: usize) -> Option<&S> {
+        self.systems
+            .get(index)
+            .and_then(|system| system.as_ref().as_any().downcast_ref())
+    }
+
+    /// Returns the system at the given index mutably if it has the concrete type `S`.
+    pub fn get_as_mut<S: System>(&mut self, index: usize) -> Option<&mut S> {
+        self.systems
+            .get_mut(index)
+            .and_then(|system| system.as_mut().as_any_mut().downcast_mut())
+    }
+
     pub fn register_components(&self) {
         for system in &self.systems {
             system.register_components();
         }
     }
 
+    /// Returns the storage tags accessed by the systems that are not registered for serialization.
+    ///
+    /// Only accesses declared through [`System::accessed_storage_tags`] are considered. The tags are
+    /// sorted and free of duplicates. Checking this before running the systems catches misconfiguration
+    /// that would otherwise only surface once a checkpoint is written.
+    pub fn check_registrations(&self) -> Vec<String> {
+        let registered_tags = registered_tags();
+        self.accessed_storage_tags()
+            .into_iter()
+            .filter(|tag| registered_tags.binary_search(tag).is_err())
+            .collect()
+    }
+
+    /// Returns the storage tags accessed by the systems, sorted and free of duplicates.
+    ///
+    /// Only accesses declared through [`System::accessed_storage_tags`] are considered.
+    pub fn accessed_storage_tags(&self) -> Vec<String> {
+        let tags: BTreeSet<_> = self
+            .systems
+            .iter()
+            .flat_map(|system| system.accessed_storage_tags())
+            .collect();
+        tags.into_iter().collect()
+    }
+
+    /// Returns whether every system declares the storages it accesses.
+    ///
+    /// Since declaring accesses is optional, a system is considered to declare its accesses if
+    /// [`System::accessed_storage_tags`] is non-empty.
+    pub fn all_declare_accesses(&self) -> bool {
+        self.systems
+            .iter()
+            .all(|system| !system.accessed_storage_tags().is_empty())
+    }
+
     pub fn run_all(&mut self, data: &mut Universe) -> eyre::Result<()> {
         for system in &mut self.systems {
             system
@@ -172,6 +401,56 @@ impl Systems {
     }
 }
 
+/// A registry of named system factories.
+///
+/// The registry makes it possible to compose [`Systems`] at runtime, for example from a list of
+/// system names given in a configuration file. See [`Systems::add_by_name`].
+#[derive(Default)]
+pub struct SystemRegistry {
+    factories: HashMap<String, Box<dyn Fn() -> Box<dyn System>>>,
+}
+
+impl Debug for SystemRegistry {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SystemRegistry")
+            .field("names", &self.factories.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl SystemRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a factory for the system with the given name.
+    ///
+    /// If a factory is already registered with the same name, it is replaced.
+    pub fn register<S, F>(&mut self, name: impl Into<String>, factory: F) -> &mut Self
+    where
+        S: Into<Box<dyn System>>,
+        F: Fn() -> S + 'static,
+    {
+        self.factories
+            .insert(name.into(), Box::new(move || factory().into()));
+        self
+    }
+
+    /// Creates a new instance of the system registered with the given name, if any.
+    pub fn create_system(&self, name: &str) -> Option<Box<dyn System>> {
+        self.factories.get(name).map(|factory| factory())
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.factories.contains_key(name)
+    }
+
+    /// Returns the names of all registered systems, in no particular order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.factories.keys().map(String::as_str)
+    }
+}
+
 pub fn join<Joinables: crate::join::Join>(joinables: Joinables) -> Joinables::Iter {
     joinables.join()
 }
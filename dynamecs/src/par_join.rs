@@ -0,0 +1,147 @@
+//! Parallel joins based on [`rayon`], available with the `rayon` feature.
+//!
+//! Parallel joins are driven by a [`VecStorage`], just like sequential joins. The entity and
+//! component slices of the driving storage are split into disjoint chunks that are processed
+//! by different threads, and the components of the remaining storages are looked up with
+//! [`SharedJoinable::try_make_component_ref_shared`]. Since the entities in a [`VecStorage`] are
+//! unique and every entity belongs to exactly one chunk, each entity is still visited only once
+//! throughout the join, which upholds the safety invariant of [`Joinable`].
+use crate::join::{IntoJoinable, Joinable, Optional, Without};
+use crate::storages::VecStorage;
+use crate::Entity;
+use rayon::prelude::*;
+
+/// A [`Joinable`] that can make component references through a shared reference, so that it can
+/// be used from several threads simultaneously.
+///
+/// # Safety
+///
+/// Implementors must ensure that `try_make_component_ref_shared` can soundly be called concurrently
+/// from multiple threads, provided that it is never called more than once with the same entity.
+pub unsafe trait SharedJoinable<'a>: Joinable<'a> + Send + Sync {
+    /// Same as [`Joinable::try_make_component_ref`], but through a shared reference.
+    ///
+    /// # Safety
+    ///
+    /// This function may never be called more than once with the same entity throughout the lifetime of
+    /// the joinable, regardless of which thread it is called from.
+    unsafe fn try_make_component_ref_shared(&self, entity: Entity) -> Option<Self::ComponentRef>;
+}
+
+unsafe impl<'a, J: SharedJoinable<'a>> SharedJoinable<'a> for Optional<J> {
+    unsafe fn try_make_component_ref_shared(&self, entity: Entity) -> Option<Self::ComponentRef> {
+        Some(self.0.try_make_component_ref_shared(entity))
+    }
+}
+
+unsafe impl<'a, J: SharedJoinable<'a>> SharedJoinable<'a> for Without<J> {
+    unsafe fn try_make_component_ref_shared(&self, entity: Entity) -> Option<Self::ComponentRef> {
+        match self.0.try_make_component_ref_shared(entity) {
+            Some(_) => None,
+            None => Some(()),
+        }
+    }
+}
+
+/// Parallel counterpart of [`Join`](crate::join::Join).
+pub trait ParJoin {
+    type Item: Send;
+
+    fn par_join(self) -> impl ParallelIterator<Item = Self::Item>;
+}
+
+impl<'a, C: Sync> ParJoin for &'a VecStorage<C> {
+    type Item = (Entity, &'a C);
+
+    fn par_join(self) -> impl ParallelIterator<Item = Self::Item> {
+        self.entities()
+            .par_iter()
+            .copied()
+            .zip(self.components().par_iter())
+    }
+}
+
+impl<'a, C: Send> ParJoin for &'a mut VecStorage<C> {
+    type Item = (Entity, &'a mut C);
+
+    fn par_join(self) -> impl ParallelIterator<Item = Self::Item> {
+        let (entities, components) = self.entities_and_components_mut();
+        entities.par_iter().copied().zip(components.par_iter_mut())
+    }
+}
+
+/// Implements ParJoin for tuples starting with a VecStorage reference (mutable/immutable)
+macro_rules! impl_vec_storage_tuple_par_join_base {
+    ($storage_ref:ty, $component_ref:ty, $component_bound:ident, $storage_var:ident => $entity_component_expr:expr, $($joinables:ident),*) => {
+        #[allow(unused_parens)]
+        #[allow(irrefutable_let_patterns)]
+        impl<'a, C: $component_bound, $($joinables),*> ParJoin for ($storage_ref, $($joinables),*)
+        where
+            $($joinables: IntoJoinable<'a>,
+              $joinables::Joinable: SharedJoinable<'a>,
+              <$joinables::Joinable as Joinable<'a>>::ComponentRef: Send),*
+        {
+            type Item = ($crate::Entity, $component_ref $(, <$joinables::Joinable as Joinable<'a>>::ComponentRef)*);
+
+            #[allow(non_snake_case)]
+            fn par_join(self) -> impl ParallelIterator<Item = Self::Item> {
+                // This unpacks the tuple by defining variables with the same names as the types
+                let ($storage_var, $($joinables),*) = self;
+                let joinables = ($($joinables.into_joinable(),)*);
+                $entity_component_expr.filter_map(move |(entity, c0)| {
+                    let ($($joinables,)*) = &joinables;
+                    // SAFETY: Entities in the driving VecStorage are unique, and every entity is
+                    // processed by exactly one thread
+                    $(let $joinables = unsafe { $joinables.try_make_component_ref_shared(entity) };)*
+                    if let ($(Some($joinables)),*) = ($($joinables),*) {
+                        Some((entity, c0 $(, $joinables)*))
+                    } else {
+                        None
+                    }
+                })
+            }
+        }
+    }
+}
+
+macro_rules! impl_vec_storage_tuple_par_join {
+    ($($joinables:ident),*) => {
+        impl_vec_storage_tuple_par_join_base!(&'a VecStorage<C>, &'a C, Sync,
+            storage => storage.par_join(),
+            $($joinables),*);
+    }
+}
+
+macro_rules! impl_vec_storage_tuple_par_join_mut {
+    ($($joinables:ident),*) => {
+        impl_vec_storage_tuple_par_join_base!(&'a mut VecStorage<C>, &'a mut C, Send,
+            storage => storage.par_join(),
+            $($joinables),*);
+    }
+}
+
+impl_vec_storage_tuple_par_join!();
+impl_vec_storage_tuple_par_join!(J1);
+impl_vec_storage_tuple_par_join!(J1, J2);
+impl_vec_storage_tuple_par_join!(J1, J2, J3);
+impl_vec_storage_tuple_par_join!(J1, J2, J3, J4);
+impl_vec_storage_tuple_par_join!(J1, J2, J3, J4, J5);
+impl_vec_storage_tuple_par_join!(J1, J2, J3, J4, J5, J6);
+impl_vec_storage_tuple_par_join!(J1, J2, J3, J4, J5, J6, J7);
+impl_vec_storage_tuple_par_join!(J1, J2, J3, J4, J5, J6, J7, J8);
+impl_vec_storage_tuple_par_join!(J1, J2, J3, J4, J5, J6, J7, J8, J9);
+impl_vec_storage_tuple_par_join!(J1, J2, J3, J4, J5, J6, J7, J8, J9, J10);
+impl_vec_storage_tuple_par_join!(J1, J2, J3, J4, J5, J6, J7, J8, J9, J10, J11);
+
+impl_vec_storage_tuple_par_join_mut!();
+impl_vec_storage_tuple_par_join_mut!(J1);
+impl_vec_storage_tuple_par_join_mut!(J1, J2);
+impl_vec_storage_tuple_par_join_mut!(J1, J2, J3);
+impl_vec_storage_tuple_par_join_mut!(J1, J2, J3, J4);
+impl_vec_storage_tuple_par_join_mut!(J1, J2, J3, J4, J5);
+impl_vec_storage_tuple_par_join_mut!(J1, J2, J3, J4, J5, J6);
+impl_vec_storage_tuple_par_join_mut!(J1, J2, J3, J4, J5, J6, J7);
+impl_vec_storage_tuple_par_join_mut!(J1, J2, J3, J4, J5, J6, J7, J8);
+impl_vec_storage_tuple_par_join_mut!(J1, J2, J3, J4, J5, J6, J7, J8, J9);
+impl_vec_storage_tuple_par_join_mut!(J1, J2, J3, J4, J5, J6, J7, J8, J9, J10);
+impl_vec_storage_tuple_par_join_mut!(J1, J2, J3, J4, J5, J6, J7, J8, J9, J10, J11);
@@ -3,7 +3,8 @@ use std::marker::PhantomData;
 
 use erased_serde::{Deserializer, Error, Serialize};
 
-use crate::{Storage, StorageSerializer};
+use crate::universe::{current_step_setter, entity_remover, storage_version_reader};
+use crate::{Entity, Storage, StorageSerializer};
 
 /// Generic storage serializer.
 ///
@@ -47,4 +48,16 @@ where
     fn storage_type_id(&self) -> TypeId {
         TypeId::of::<S>()
     }
+
+    fn entity_remover(&self) -> fn(&mut dyn Any, Entity) -> bool {
+        entity_remover::<S>()
+    }
+
+    fn storage_version_reader(&self) -> fn(&dyn Any) -> Option<u64> {
+        storage_version_reader::<S>()
+    }
+
+    fn current_step_setter(&self) -> fn(&mut dyn Any, usize) {
+        current_step_setter::<S>()
+    }
 }
@@ -1,3 +1,7 @@
 //! Functionality related to serialization of component storages.
 mod generic_serializer;
 pub use generic_serializer::*;
+mod transient_serializer;
+pub use transient_serializer::*;
+
+pub use crate::universe::registered_tags;
@@ -0,0 +1,64 @@
+use std::any::{Any, TypeId};
+use std::marker::PhantomData;
+
+use erased_serde::{Deserializer, Error, Serialize};
+
+use crate::storages::Transient;
+use crate::universe::{current_step_setter, entity_remover, storage_version_reader};
+use crate::{Entity, Storage, StorageSerializer};
+
+/// Serializer for [`Transient`] storages, which are omitted from serialization.
+///
+/// Not intended to be used outside this crate. See [`register_transient_storage`](crate::register_transient_storage).
+#[doc(hidden)]
+#[derive(Debug, Default)]
+pub struct TransientStorageSerializer<Storage> {
+    marker: PhantomData<Storage>,
+}
+
+impl<Storage> TransientStorageSerializer<Storage> {
+    pub fn new() -> Self {
+        Self { marker: PhantomData }
+    }
+}
+
+// Factory contains no data whatsoever and is therefore entirely safe to pass around across threads
+unsafe impl<Storage> Sync for TransientStorageSerializer<Storage> {}
+unsafe impl<Storage> Send for TransientStorageSerializer<Storage> {}
+
+impl<S> StorageSerializer for TransientStorageSerializer<S>
+where
+    S: 'static + Storage + Default,
+{
+    fn storage_tag(&self) -> String {
+        Transient::<S>::tag()
+    }
+
+    fn serializable_storage<'a>(&self, _storage: &'a dyn Any) -> Option<&'a dyn Serialize> {
+        None
+    }
+
+    fn deserialize_storage<'a>(&self, _deserializer: &mut dyn Deserializer) -> Result<Box<dyn Any>, Error> {
+        Ok(Box::new(Transient::<S>::default()))
+    }
+
+    fn storage_type_id(&self) -> TypeId {
+        TypeId::of::<Transient<S>>()
+    }
+
+    fn entity_remover(&self) -> fn(&mut dyn Any, Entity) -> bool {
+        entity_remover::<Transient<S>>()
+    }
+
+    fn storage_version_reader(&self) -> fn(&dyn Any) -> Option<u64> {
+        storage_version_reader::<Transient<S>>()
+    }
+
+    fn current_step_setter(&self) -> fn(&mut dyn Any, usize) {
+        current_step_setter::<Transient<S>>()
+    }
+
+    fn is_transient(&self) -> bool {
+        true
+    }
+}
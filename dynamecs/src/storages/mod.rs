@@ -1,10 +1,13 @@
 //! Various component storages.
-use crate::Entity;
+use crate::{Entity, Storage};
 use std::collections::HashMap;
 use std::marker::PhantomData;
 
+mod transient;
 mod version_impl;
 
+pub mod sparse_storage;
+pub mod step_tracked_vec_storage;
 pub mod vec_storage;
 pub mod versioned_vec_storage;
 
@@ -16,8 +19,6 @@ pub struct Version<T> {
 }
 
 /// A storage that stores its components in a [`Vec`].
-///
-/// TODO: Currently doesn't support removal.
 #[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub struct VecStorage<Component> {
     components: Vec<Component>,
@@ -25,9 +26,17 @@ pub struct VecStorage<Component> {
     lookup_table: HashMap<Entity, usize>,
 }
 
-/// A *versioned* variant of [`VecStorage`].
+/// A storage that stores its components in a [`HashMap`], keyed by entity.
 ///
-/// TODO: Currently doesn't support removal.
+/// In contrast to [`VecStorage`], memory usage is proportional to the number of components rather
+/// than the size of the entity lookup, which makes it suitable for components that only few
+/// entities have. A sparse storage can take part in joins, but cannot drive them.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct SparseStorage<Component> {
+    components: HashMap<Entity, Component>,
+}
+
+/// A *versioned* variant of [`VecStorage`].
 #[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub struct VersionedVecStorage<Component> {
     storage: VecStorage<Component>,
@@ -35,6 +44,31 @@ pub struct VersionedVecStorage<Component> {
     storage_version: Version<Self>,
 }
 
+/// A variant of [`VecStorage`] that records the step index at which each component was last
+/// inserted or modified.
+///
+/// Since storages have no access to the [`StepIndex`](crate::components::StepIndex) of the
+/// universe, the current step index must be set before components are mutated, either for all storages
+/// with [`Universe::set_current_step`](crate::Universe::set_current_step), as done by the simulation loop
+/// of `dynamecs-app`, or directly with [`set_current_step`](StepTrackedVecStorage::set_current_step).
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct StepTrackedVecStorage<Component> {
+    storage: VecStorage<Component>,
+    modified_steps: Vec<usize>,
+    current_step: usize,
+}
+
+/// A wrapper for storages holding transient data that should not be serialized, such as caches or scratch buffers.
+///
+/// Transient storages are omitted when a [`Universe`](crate::Universe) is serialized.
+/// After deserialization, the storage is therefore reconstructed with its [`Default`] implementation
+/// when it is first accessed. Transient storages must be registered with
+/// [`register_transient_storage`](crate::register_transient_storage).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Transient<S> {
+    storage: S,
+}
+
 /// A Storage that stores a single component without any Entity relation.
 #[derive(Debug, Copy, Clone, Default, serde::Serialize, serde::Deserialize)]
 pub struct SingularStorage<Component> {
@@ -55,6 +89,37 @@ impl<Component> SingularStorage<Component> {
     }
 }
 
+impl<Component: 'static> Storage for SingularStorage<Component> {}
+
+/// A storage that stores a single [`Resource`](crate::Resource).
+///
+/// Resources are usually accessed through [`Universe::get_resource`](crate::Universe::get_resource)
+/// rather than through the storage itself.
+#[derive(Debug, Copy, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ResourceStorage<R> {
+    resource: R,
+}
+
+impl<R> ResourceStorage<R> {
+    pub fn new(resource: R) -> Self {
+        Self { resource }
+    }
+
+    pub fn get_resource(&self) -> &R {
+        &self.resource
+    }
+
+    pub fn get_resource_mut(&mut self) -> &mut R {
+        &mut self.resource
+    }
+
+    pub fn into_resource(self) -> R {
+        self.resource
+    }
+}
+
+impl<R: 'static> Storage for ResourceStorage<R> {}
+
 /// A Storage that stores a single *immutable* component without any Entity relation.
 #[derive(Debug, Copy, Clone, Default, serde::Serialize, serde::Deserialize)]
 pub struct ImmutableSingularStorage<Component> {
@@ -70,3 +135,5 @@ impl<Component> ImmutableSingularStorage<Component> {
         &self.component
     }
 }
+
+impl<Component: 'static> Storage for ImmutableSingularStorage<Component> {}
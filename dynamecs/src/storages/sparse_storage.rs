@@ -0,0 +1,147 @@
+use crate::join::{IntoJoinable, Joinable};
+use crate::storages::SparseStorage;
+use crate::{Entity, GetComponentForEntity, GetComponentForEntityMut, InsertComponentForEntity, Storage};
+use std::collections::HashMap;
+
+impl<Component> SparseStorage<Component> {
+    pub fn new() -> Self {
+        Self {
+            components: HashMap::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.components.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.components.is_empty()
+    }
+
+    pub fn get_component(&self, id: Entity) -> Option<&Component> {
+        self.components.get(&id)
+    }
+
+    pub fn get_component_mut(&mut self, id: Entity) -> Option<&mut Component> {
+        self.components.get_mut(&id)
+    }
+
+    /// Inserts the component for the given entity, and returns the previous component, if any.
+    pub fn insert(&mut self, id: Entity, component: Component) -> Option<Component> {
+        self.components.insert(id, component)
+    }
+
+    /// Removes the component associated with the given entity and returns it, if it exists.
+    pub fn remove(&mut self, id: Entity) -> Option<Component> {
+        self.components.remove(&id)
+    }
+
+    pub fn clear(&mut self) {
+        self.components.clear();
+    }
+
+    /// Returns an iterator over entities and their components, in arbitrary order.
+    pub fn entity_component_iter(&self) -> impl Iterator<Item = (Entity, &Component)> {
+        self.components
+            .iter()
+            .map(|(&entity, component)| (entity, component))
+    }
+
+    /// Returns an iterator over entities and mutable references to their components, in arbitrary order.
+    pub fn entity_component_iter_mut(&mut self) -> impl Iterator<Item = (Entity, &mut Component)> {
+        self.components
+            .iter_mut()
+            .map(|(&entity, component)| (entity, component))
+    }
+}
+
+impl<Component> Default for SparseStorage<Component> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<C> InsertComponentForEntity<C> for SparseStorage<C> {
+    fn insert_component_for_entity(&mut self, entity: Entity, component: C) {
+        self.insert(entity, component);
+    }
+}
+
+impl<C> GetComponentForEntity<C> for SparseStorage<C> {
+    fn get_component_for_entity(&self, id: Entity) -> Option<&C> {
+        self.get_component(id)
+    }
+}
+
+impl<C> GetComponentForEntityMut<C> for SparseStorage<C> {
+    fn get_component_for_entity_mut(&mut self, id: Entity) -> Option<&mut C> {
+        self.get_component_mut(id)
+    }
+}
+
+impl<C: 'static> Storage for SparseStorage<C> {
+    fn remove_entity(&mut self, entity: Entity) -> bool {
+        self.remove(entity).is_some()
+    }
+}
+
+#[derive(Debug)]
+pub struct SparseStorageJoinable<'a, C> {
+    components: &'a HashMap<Entity, C>,
+}
+
+impl<'a, C: 'a> Joinable<'a> for SparseStorageJoinable<'a, C> {
+    type ComponentRef = &'a C;
+
+    unsafe fn try_make_component_ref(&mut self, entity: Entity) -> Option<Self::ComponentRef> {
+        self.components.get(&entity)
+    }
+}
+
+impl<'a, C> IntoJoinable<'a> for &'a SparseStorage<C> {
+    type Joinable = SparseStorageJoinable<'a, C>;
+
+    fn into_joinable(self) -> Self::Joinable {
+        SparseStorageJoinable {
+            components: &self.components,
+        }
+    }
+}
+
+/// Mutable joinable for a [`SparseStorage`].
+///
+/// Every entity is visited at most once in a join, so each mutable reference is handed out once
+/// and removed from the joinable, which requires no unsafe aliasing of the underlying map.
+#[derive(Debug)]
+pub struct SparseStorageJoinableMut<'a, C> {
+    components: HashMap<Entity, &'a mut C>,
+}
+
+impl<'a, C: 'a> Joinable<'a> for SparseStorageJoinableMut<'a, C> {
+    type ComponentRef = &'a mut C;
+
+    unsafe fn try_make_component_ref(&mut self, entity: Entity) -> Option<Self::ComponentRef> {
+        self.components.remove(&entity)
+    }
+}
+
+impl<'a, C> IntoJoinable<'a> for &'a mut SparseStorage<C> {
+    type Joinable = SparseStorageJoinableMut<'a, C>;
+
+    fn into_joinable(self) -> Self::Joinable {
+        SparseStorageJoinableMut {
+            components: self
+                .components
+                .iter_mut()
+                .map(|(&entity, component)| (entity, component))
+                .collect(),
+        }
+    }
+}
+
+#[cfg(feature = "rayon")]
+unsafe impl<'a, C: Sync + 'a> crate::par_join::SharedJoinable<'a> for SparseStorageJoinable<'a, C> {
+    unsafe fn try_make_component_ref_shared(&self, entity: Entity) -> Option<Self::ComponentRef> {
+        self.components.get(&entity)
+    }
+}
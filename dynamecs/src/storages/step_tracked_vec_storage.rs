@@ -0,0 +1,133 @@
+use crate::join::IntoJoinable;
+use crate::storages::vec_storage::VecStorageJoinable;
+use crate::storages::{StepTrackedVecStorage, VecStorage};
+use crate::{Entity, GetComponentForEntity, GetComponentForEntityMut, InsertComponentForEntity, Storage};
+use std::ops::Deref;
+
+impl<Component> Default for StepTrackedVecStorage<Component> {
+    fn default() -> Self {
+        Self {
+            storage: Default::default(),
+            modified_steps: Default::default(),
+            current_step: 0,
+        }
+    }
+}
+
+impl<Component> Deref for StepTrackedVecStorage<Component> {
+    type Target = VecStorage<Component>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.storage
+    }
+}
+
+impl<Component> StepTrackedVecStorage<Component> {
+    /// Sets the step index that is recorded for subsequent insertions and modifications.
+    pub fn set_current_step(&mut self, step_index: usize) {
+        self.current_step = step_index;
+    }
+
+    /// Returns the step index that is recorded for insertions and modifications.
+    pub fn current_step(&self) -> usize {
+        self.current_step
+    }
+
+    /// Inserts a component associated with the given entity, and returns the index in the
+    /// storage.
+    ///
+    /// The current step index is recorded as the step at which the component was last modified.
+    pub fn insert(&mut self, entity: Entity, component: Component) -> usize {
+        let idx = self.storage.insert(entity, component);
+        // idx can be one-past the current length, but not greater
+        if let Some(step) = self.modified_steps.get_mut(idx) {
+            *step = self.current_step;
+        } else {
+            assert_eq!(idx, self.modified_steps.len());
+            self.modified_steps.push(self.current_step);
+        }
+        idx
+    }
+
+    /// Removes the component associated with the given entity and returns it, if it exists.
+    ///
+    /// As with [`VecStorage::remove`], the order of the components is not preserved.
+    pub fn remove(&mut self, entity: Entity) -> Option<Component> {
+        let idx = self.storage.get_index(entity)?;
+        self.modified_steps.swap_remove(idx);
+        self.storage.remove(entity)
+    }
+
+    /// Removes all components from the storage.
+    pub fn clear(&mut self) {
+        self.storage.clear();
+        self.modified_steps.clear();
+    }
+
+    /// Returns a mutable reference to the component associated with the given entity.
+    ///
+    /// If the component exists, the current step index is recorded as the step at which it was
+    /// last modified.
+    pub fn get_component_mut(&mut self, id: Entity) -> Option<&mut Component> {
+        self.storage.get_index(id).map(|idx| {
+            self.modified_steps[idx] = self.current_step;
+            &mut self.storage.components_mut()[idx]
+        })
+    }
+
+    /// Returns a mutable slice to the components.
+    ///
+    /// The current step index is recorded for *all* components.
+    pub fn components_mut(&mut self) -> &mut [Component] {
+        self.modified_steps.fill(self.current_step);
+        self.storage.components_mut()
+    }
+
+    /// Returns the step index at which the component associated with the given entity was last
+    /// inserted or modified, if the component exists.
+    pub fn last_modified_step(&self, id: Entity) -> Option<usize> {
+        self.storage
+            .get_index(id)
+            .map(|idx| self.modified_steps[idx])
+    }
+
+    pub fn modified_steps(&self) -> &[usize] {
+        &self.modified_steps
+    }
+}
+
+impl<'a, Component> IntoJoinable<'a> for &'a StepTrackedVecStorage<Component> {
+    type Joinable = VecStorageJoinable<'a, Component>;
+
+    fn into_joinable(self) -> Self::Joinable {
+        self.storage.into_joinable()
+    }
+}
+
+impl<C> GetComponentForEntity<C> for StepTrackedVecStorage<C> {
+    fn get_component_for_entity(&self, id: Entity) -> Option<&C> {
+        self.get_component(id)
+    }
+}
+
+impl<C> GetComponentForEntityMut<C> for StepTrackedVecStorage<C> {
+    fn get_component_for_entity_mut(&mut self, id: Entity) -> Option<&mut C> {
+        self.get_component_mut(id)
+    }
+}
+
+impl<C> InsertComponentForEntity<C> for StepTrackedVecStorage<C> {
+    fn insert_component_for_entity(&mut self, entity: Entity, component: C) {
+        self.insert(entity, component);
+    }
+}
+
+impl<C: 'static> Storage for StepTrackedVecStorage<C> {
+    fn remove_entity(&mut self, entity: Entity) -> bool {
+        self.remove(entity).is_some()
+    }
+
+    fn set_current_step(&mut self, step_index: usize) {
+        StepTrackedVecStorage::set_current_step(self, step_index)
+    }
+}
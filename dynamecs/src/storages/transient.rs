@@ -0,0 +1,59 @@
+use crate::storages::Transient;
+use crate::{Entity, GetComponentForEntity, GetComponentForEntityMut, InsertComponentForEntity, Storage};
+use std::ops::{Deref, DerefMut};
+
+impl<S> Transient<S> {
+    pub fn new(storage: S) -> Self {
+        Self { storage }
+    }
+
+    pub fn into_inner(self) -> S {
+        self.storage
+    }
+}
+
+impl<S> Deref for Transient<S> {
+    type Target = S;
+
+    fn deref(&self) -> &Self::Target {
+        &self.storage
+    }
+}
+
+impl<S> DerefMut for Transient<S> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.storage
+    }
+}
+
+impl<C, S: InsertComponentForEntity<C>> InsertComponentForEntity<C> for Transient<S> {
+    fn insert_component_for_entity(&mut self, entity: Entity, component: C) {
+        self.storage.insert_component_for_entity(entity, component)
+    }
+}
+
+impl<C, S: GetComponentForEntity<C>> GetComponentForEntity<C> for Transient<S> {
+    fn get_component_for_entity(&self, id: Entity) -> Option<&C> {
+        self.storage.get_component_for_entity(id)
+    }
+}
+
+impl<C, S: GetComponentForEntityMut<C>> GetComponentForEntityMut<C> for Transient<S> {
+    fn get_component_for_entity_mut(&mut self, id: Entity) -> Option<&mut C> {
+        self.storage.get_component_for_entity_mut(id)
+    }
+}
+
+impl<S: Storage> Storage for Transient<S> {
+    fn remove_entity(&mut self, entity: Entity) -> bool {
+        self.storage.remove_entity(entity)
+    }
+
+    fn storage_version_number(&self) -> Option<u64> {
+        self.storage.storage_version_number()
+    }
+
+    fn set_current_step(&mut self, step_index: usize) {
+        self.storage.set_current_step(step_index)
+    }
+}
@@ -1,6 +1,6 @@
 use crate::join::{IntoJoinable, Joinable};
 use crate::storages::VecStorage;
-use crate::{Entity, GetComponentForEntity, GetComponentForEntityMut, InsertComponentForEntity};
+use crate::{Entity, GetComponentForEntity, GetComponentForEntityMut, InsertComponentForEntity, Storage};
 use std::collections::HashMap;
 
 /// Stores component in a vector, with a one-to-one relationship between entities and components.
@@ -51,6 +51,35 @@ impl<Component> VecStorage<Component> {
         index
     }
 
+    /// Inserts a component associated with the given entity, and returns the component that was
+    /// previously associated with the entity, if any.
+    ///
+    /// In contrast to [`insert`](Self::insert), which returns the index of the component, this makes
+    /// it possible to recover a replaced component.
+    pub fn replace(&mut self, id: Entity, component: Component) -> Option<Component> {
+        match self.get_component_mut(id) {
+            Some(existing) => Some(std::mem::replace(existing, component)),
+            None => {
+                self.insert(id, component);
+                None
+            }
+        }
+    }
+
+    /// Removes the component associated with the given entity and returns it, if it exists.
+    ///
+    /// The last component in the storage is moved into the place of the removed component,
+    /// so the order of the components is not preserved.
+    pub fn remove(&mut self, id: Entity) -> Option<Component> {
+        let index = self.lookup_table.remove(&id)?;
+        self.entities.swap_remove(index);
+        let component = self.components.swap_remove(index);
+        if let Some(&moved_entity) = self.entities.get(index) {
+            self.lookup_table.insert(moved_entity, index);
+        }
+        Some(component)
+    }
+
     pub fn clear(&mut self) {
         self.entities.clear();
         self.components.clear();
@@ -65,6 +94,22 @@ impl<Component> VecStorage<Component> {
         &mut self.components
     }
 
+    /// Iterates over the components, without their associated entities.
+    pub fn iter(&self) -> std::slice::Iter<'_, Component> {
+        self.components.iter()
+    }
+
+    /// Iterates mutably over the components, without their associated entities.
+    pub fn iter_mut(&mut self) -> std::slice::IterMut<'_, Component> {
+        self.components.iter_mut()
+    }
+
+    /// Returns the entities together with mutable access to their components.
+    #[cfg(feature = "rayon")]
+    pub(crate) fn entities_and_components_mut(&mut self) -> (&[Entity], &mut [Component]) {
+        (&self.entities, &mut self.components)
+    }
+
     pub fn entities(&self) -> &[Entity] {
         &self.entities
     }
@@ -86,6 +131,11 @@ impl<Component> VecStorage<Component> {
     }
 }
 
+pub struct VecStorageEntityComponentIntoIter<Component> {
+    // We keep the inner iterator as an implementation detail so that we can swap it out if required later on
+    inner_iter: std::iter::Zip<std::vec::IntoIter<Entity>, std::vec::IntoIter<Component>>,
+}
+
 pub struct VecStorageEntityComponentIter<'a, Component> {
     // We keep the inner iterator as an implementation detail so that we can swap it out if required later on
     inner_iter: std::iter::Zip<std::iter::Copied<std::slice::Iter<'a, Entity>>, std::slice::Iter<'a, Component>>,
@@ -96,6 +146,25 @@ pub struct VecStorageEntityComponentIterMut<'a, Component> {
     inner_iter: std::iter::Zip<std::iter::Copied<std::slice::Iter<'a, Entity>>, std::slice::IterMut<'a, Component>>,
 }
 
+impl<Component> Iterator for VecStorageEntityComponentIntoIter<Component> {
+    type Item = (Entity, Component);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner_iter.next()
+    }
+}
+
+impl<Component> IntoIterator for VecStorage<Component> {
+    type Item = (Entity, Component);
+    type IntoIter = VecStorageEntityComponentIntoIter<Component>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        VecStorageEntityComponentIntoIter {
+            inner_iter: self.entities.into_iter().zip(self.components),
+        }
+    }
+}
+
 impl<'a, Component> Iterator for VecStorageEntityComponentIter<'a, Component> {
     type Item = (Entity, &'a Component);
 
@@ -124,6 +193,12 @@ impl<C> InsertComponentForEntity<C> for VecStorage<C> {
     }
 }
 
+impl<C: 'static> Storage for VecStorage<C> {
+    fn remove_entity(&mut self, entity: Entity) -> bool {
+        self.remove(entity).is_some()
+    }
+}
+
 impl<C> GetComponentForEntity<C> for VecStorage<C> {
     fn get_component_for_entity(&self, id: Entity) -> Option<&C> {
         self.components.get(self.get_index(id)?)
@@ -196,3 +271,40 @@ impl<'a, C> IntoJoinable<'a> for &'a mut VecStorage<C> {
         }
     }
 }
+
+// SAFETY: The joinable is equivalent to a `&'a [C]` together with a shared reference to the lookup
+// table, and only ever hands out `&C`. It is therefore safe to send and share across threads
+// whenever `&C` is, that is when `C: Sync`. This is only needed for parallel joins.
+#[cfg(feature = "rayon")]
+unsafe impl<'a, C: Sync> Send for VecStorageJoinable<'a, C> {}
+#[cfg(feature = "rayon")]
+unsafe impl<'a, C: Sync> Sync for VecStorageJoinable<'a, C> {}
+
+#[cfg(feature = "rayon")]
+unsafe impl<'a, C: Sync + 'a> crate::par_join::SharedJoinable<'a> for VecStorageJoinable<'a, C> {
+    unsafe fn try_make_component_ref_shared(&self, entity: Entity) -> Option<Self::ComponentRef> {
+        self.lookup_table
+            .get(&entity)
+            .map(|index| &*self.components.add(*index))
+    }
+}
+
+// SAFETY: The joinable is equivalent to a `&'a mut [C]` together with a shared reference to the lookup
+// table, and hands out `&mut C`, which may be sent to other threads when `C: Send`. Sharing the joinable
+// across threads is sound because `SharedJoinable` requires that every entity is visited at most once,
+// so that no two threads obtain references to the same component. This is only needed for parallel joins.
+#[cfg(feature = "rayon")]
+unsafe impl<'a, C: Send> Send for VecStorageJoinableMut<'a, C> {}
+#[cfg(feature = "rayon")]
+unsafe impl<'a, C: Send> Sync for VecStorageJoinableMut<'a, C> {}
+
+#[cfg(feature = "rayon")]
+unsafe impl<'a, C: Send + 'a> crate::par_join::SharedJoinable<'a> for VecStorageJoinableMut<'a, C> {
+    unsafe fn try_make_component_ref_shared(&self, entity: Entity) -> Option<Self::ComponentRef> {
+        // Since every entity is visited at most once, no two mutable references to the same
+        // component can be created
+        self.lookup_table
+            .get(&entity)
+            .map(|index| &mut *self.components.add(*index))
+    }
+}
@@ -1,8 +1,8 @@
-use crate::join::IntoJoinable;
+use crate::join::{IntoJoinable, Joinable};
 use crate::storages::vec_storage::VecStorageJoinable;
 use crate::storages::Version;
 use crate::storages::{VecStorage, VersionedVecStorage};
-use crate::{Entity, GetComponentForEntity, GetComponentForEntityMut, InsertComponentForEntity};
+use crate::{Entity, GetComponentForEntity, GetComponentForEntityMut, InsertComponentForEntity, Storage};
 use std::ops::Deref;
 
 impl<Component> Default for VersionedVecStorage<Component> {
@@ -42,6 +42,26 @@ impl<Component> VersionedVecStorage<Component> {
         idx
     }
 
+    /// Removes the component associated with the given entity and returns it, if it exists.
+    ///
+    /// If the component exists, the storage version is advanced. As with [`VecStorage::remove`],
+    /// the order of the components is not preserved.
+    pub fn remove(&mut self, entity: Entity) -> Option<Component> {
+        let idx = self.storage.get_index(entity)?;
+        self.storage_version.advance();
+        self.versions.swap_remove(idx);
+        self.storage.remove(entity)
+    }
+
+    /// Removes all components from the storage.
+    ///
+    /// The storage version is advanced, so that any state derived from the storage is invalidated.
+    pub fn clear(&mut self) {
+        self.storage_version.advance();
+        self.storage.clear();
+        self.versions.clear();
+    }
+
     /// Returns a mutable reference to the component associated with the given entity.
     ///
     /// If the component exists, the storage version and the version associated with the
@@ -71,6 +91,17 @@ impl<Component> VersionedVecStorage<Component> {
             .map(|idx| self.versions[idx].clone())
     }
 
+    /// Returns an iterator over entities, together with the current version of their component and
+    /// the component itself.
+    pub fn entity_version_component_iter(&self) -> impl Iterator<Item = (Entity, Version<Component>, &Component)> {
+        self.storage
+            .entities()
+            .iter()
+            .zip(&self.versions)
+            .zip(self.storage.components())
+            .map(|((&entity, &version), component)| (entity, version, component))
+    }
+
     pub fn storage_version(&self) -> Version<Self> {
         self.storage_version
     }
@@ -88,6 +119,63 @@ impl<'a, Component> IntoJoinable<'a> for &'a VersionedVecStorage<Component> {
     }
 }
 
+/// A joinable that only yields components whose version is strictly greater than the given version.
+///
+/// This is useful for systems that incrementally recompute data derived from a
+/// [`VersionedVecStorage`], for example together with a
+/// [`VersionedEntityCache`](crate::cache::VersionedEntityCache):
+///
+/// ```
+/// # use dynamecs::join::Join;
+/// # use dynamecs::storages::versioned_vec_storage::ChangedSince;
+/// # use dynamecs::storages::VersionedVecStorage;
+/// # use dynamecs::Universe;
+/// # let universe = Universe::default();
+/// # let entity = universe.new_entity();
+/// # let mut storage = VersionedVecStorage::default();
+/// storage.insert(entity, 1.0);
+/// let version = storage.get_component_version(entity).unwrap();
+/// *storage.get_component_mut(entity).unwrap() = 2.0;
+/// let changed: Vec<_> = (&storage, ChangedSince(&storage, version)).join().collect();
+/// assert_eq!(changed, vec![(entity, &2.0, &2.0)]);
+/// ```
+#[derive(Debug)]
+pub struct ChangedSince<'a, C>(pub &'a VersionedVecStorage<C>, pub Version<C>);
+
+impl<'a, C> Clone for ChangedSince<'a, C> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, C> Copy for ChangedSince<'a, C> {}
+
+impl<'a, C> Joinable<'a> for ChangedSince<'a, C> {
+    type ComponentRef = &'a C;
+
+    unsafe fn try_make_component_ref(&mut self, entity: Entity) -> Option<Self::ComponentRef> {
+        let ChangedSince(storage, version) = *self;
+        let idx = storage.get_index(entity)?;
+        (storage.versions[idx] > version).then(|| &storage.components()[idx])
+    }
+}
+
+impl<'a, C> IntoJoinable<'a> for ChangedSince<'a, C> {
+    type Joinable = Self;
+
+    fn into_joinable(self) -> Self::Joinable {
+        self
+    }
+}
+
+#[cfg(feature = "rayon")]
+unsafe impl<'a, C: Send + Sync> crate::par_join::SharedJoinable<'a> for ChangedSince<'a, C> {
+    unsafe fn try_make_component_ref_shared(&self, entity: Entity) -> Option<Self::ComponentRef> {
+        let mut joinable = *self;
+        joinable.try_make_component_ref(entity)
+    }
+}
+
 impl<C> GetComponentForEntity<C> for VersionedVecStorage<C> {
     fn get_component_for_entity(&self, id: Entity) -> Option<&C> {
         self.get_component(id)
@@ -105,3 +193,13 @@ impl<C> InsertComponentForEntity<C> for VersionedVecStorage<C> {
         self.insert(entity, component);
     }
 }
+
+impl<C: 'static> Storage for VersionedVecStorage<C> {
+    fn remove_entity(&mut self, entity: Entity) -> bool {
+        self.remove(entity).is_some()
+    }
+
+    fn storage_version_number(&self) -> Option<u64> {
+        Some(self.storage_version.version)
+    }
+}
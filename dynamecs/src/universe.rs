@@ -1,16 +1,23 @@
+use crate::commands::CommandBuffer;
 use crate::fetch::{FetchComponentStorages, FetchComponentStoragesMut};
-use crate::join::Join;
+use crate::join::{EntityJoinIter, IntoJoinables, Join};
+#[cfg(feature = "rayon")]
+use crate::par_join::ParJoin;
+use crate::storages::{ResourceStorage, VecStorage};
 use crate::{
-    register_component, Component, Entity, EntityFactory, GetComponentForEntity, GetComponentForEntityMut,
-    InsertComponentForEntity, SerializableStorage, Storage,
+    register_component, Component, Entity, EntityFactory, EntitySet, GetComponentForEntity, GetComponentForEntityMut,
+    InsertComponentForEntity, Resource, SerializableStorage, Storage,
 };
 use std::any::{Any, TypeId};
 use std::cell::RefCell;
 use std::collections::HashMap;
 use std::fmt::{Debug, Formatter};
 use std::ops::{Deref, DerefMut};
+use tracing::debug;
 
-pub use universe_serialize::{register_serializer, register_storage, RegistrationStatus};
+pub use universe_serialize::{
+    register_serializer, register_storage, register_transient_storage, registered_tags, RegistrationStatus,
+};
 
 // Make universe_serialize a submodule of this module, so that it can still
 // access private members of `StorageContainer`, without exposing this to the rest of the
@@ -35,6 +42,13 @@ pub struct Universe {
     // pointers to the storages (although there are some provenance issues to be aware of here)
     storages: Storages,
     entity_factory: EntityFactory,
+    // Debugging aid that only applies to the current session, so it is not persisted
+    #[serde(skip)]
+    warn_on_empty_joins: bool,
+    // The step index most recently passed to set_current_step, so that storages that are created
+    // later in the step are also informed
+    #[serde(skip)]
+    current_step: Option<usize>,
 }
 
 #[derive(Default)]
@@ -63,6 +77,67 @@ struct TaggedTypeErasedStorage {
     // TODO: Move tag to Storage trait, then provide tag as constructor parameter?
     tag: String,
     storage: Box<dyn Any>,
+    // Forwards to Storage::remove_entity of the concrete storage type
+    remove_entity: EntityRemover,
+    // Forwards to Storage::storage_version_number of the concrete storage type
+    storage_version: StorageVersionReader,
+    // Forwards to Storage::set_current_step of the concrete storage type
+    set_current_step: CurrentStepSetter,
+}
+
+/// Removes the data associated with an entity from a type-erased storage, see [`Storage::remove_entity`].
+pub(crate) type EntityRemover = fn(&mut dyn Any, Entity) -> bool;
+
+/// Returns an [`EntityRemover`] for storages of type `S`.
+pub(crate) fn entity_remover<S: Storage>() -> EntityRemover {
+    |storage, entity| {
+        storage
+            .downcast_mut::<S>()
+            .expect("Can always downcast since TypeIds match")
+            .remove_entity(entity)
+    }
+}
+
+/// Obtains the version of a type-erased storage, see [`Storage::storage_version_number`].
+pub(crate) type StorageVersionReader = fn(&dyn Any) -> Option<u64>;
+
+/// Returns a [`StorageVersionReader`] for storages of type `S`.
+pub(crate) fn storage_version_reader<S: Storage>() -> StorageVersionReader {
+    |storage| {
+        storage
+            .downcast_ref::<S>()
+            .expect("Can always downcast since TypeIds match")
+            .storage_version_number()
+    }
+}
+
+/// Sets the current step index of a type-erased storage, see [`Storage::set_current_step`].
+pub(crate) type CurrentStepSetter = fn(&mut dyn Any, usize);
+
+/// Returns a [`CurrentStepSetter`] for storages of type `S`.
+pub(crate) fn current_step_setter<S: Storage>() -> CurrentStepSetter {
+    |storage, step_index| {
+        storage
+            .downcast_mut::<S>()
+            .expect("Can always downcast since TypeIds match")
+            .set_current_step(step_index)
+    }
+}
+
+impl TaggedTypeErasedStorage {
+    /// Creates a type-erased storage, which is informed of the current step index if one has been set.
+    fn new<S: Storage>(mut storage: S, current_step: Option<usize>) -> Self {
+        if let Some(step_index) = current_step {
+            storage.set_current_step(step_index);
+        }
+        Self {
+            tag: S::tag(),
+            storage: Box::new(storage),
+            remove_entity: entity_remover::<S>(),
+            storage_version: storage_version_reader::<S>(),
+            set_current_step: current_step_setter::<S>(),
+        }
+    }
 }
 
 impl Universe {
@@ -71,6 +146,41 @@ impl Universe {
         self.entity_factory.new_entity()
     }
 
+    /// Starts recording the entities created by this universe.
+    ///
+    /// Together with [`replay_entities`](Self::replay_entities), this makes it possible to reproduce the exact
+    /// entity ids of one run in another. Any previous recording or replay is discarded.
+    pub fn start_recording_entities(&self) {
+        self.entity_factory.start_recording()
+    }
+
+    /// Stops recording entities and returns the entities created since recording started, in order of creation.
+    ///
+    /// Returns an empty vector if entities are not being recorded.
+    pub fn stop_recording_entities(&self) -> Vec<Entity> {
+        self.entity_factory.stop_recording()
+    }
+
+    /// Hands out the given entities in order from subsequent calls to [`new_entity`](Self::new_entity).
+    ///
+    /// Once all entities have been handed out, entities are created as usual again. Entities created after
+    /// the replay never collide with the replayed entities. Any previous recording or replay is discarded.
+    ///
+    /// Returns an error if the entities contain duplicates or entities that have already been created by this
+    /// universe, in which case the replay is not started.
+    pub fn replay_entities(&self, entities: impl IntoIterator<Item = Entity>) -> eyre::Result<()> {
+        self.entity_factory.replay(entities)
+    }
+
+    /// Enables or disables logging of joins that yield no results because the driving storage is empty or absent.
+    ///
+    /// Joining over a component whose storage was never populated silently yields no results. When enabled,
+    /// such joins are logged with `tracing` at the debug level, naming the component types involved.
+    /// This is disabled by default.
+    pub fn warn_on_empty_joins(&mut self, enabled: bool) {
+        self.warn_on_empty_joins = enabled;
+    }
+
     /// Returns the provided storage if it already exists.
     pub fn try_get_storage<S: Storage>(&self) -> Option<&S> {
         self.storages
@@ -119,14 +229,9 @@ impl Universe {
                 .expect("Can always downcast since TypeIds match");
             storage_ref as *const _
         } else {
-            // TODO: Obtain tag directly through storage?
-            let tag = S::tag();
             let storage_ref = storages
                 .entry(TypeId::of::<S>())
-                .or_insert(TaggedTypeErasedStorage {
-                    tag,
-                    storage: Box::new(S::default()),
-                })
+                .or_insert_with(|| TaggedTypeErasedStorage::new(S::default(), self.current_step))
                 // Here it's OK that we have a mutable reference as we know nobody else can
                 // have a mutable reference to this storage as we *just* inserted it
                 .storage
@@ -138,7 +243,7 @@ impl Universe {
         // SAFETY: We need unsafe here in order to extend the lifetime beyond that provided
         // by RefCell. This is sound because the pointer to the storage is valid for as long as
         // the universe exists, and changes to the hash map does not invalidate the pointer,
-        // since we never remove entries.
+        // since entries are only ever removed or replaced through &mut self.
         unsafe { &*storage_ptr }
     }
 
@@ -146,15 +251,11 @@ impl Universe {
     ///
     /// If a storage of the same type was already present, it is returned. Otherwise `None` is returned.
     pub fn insert_storage<S: Storage>(&mut self, storage: S) -> Option<S> {
-        let tag = S::tag();
         self.storages
             .get_mut()
             .insert(
                 TypeId::of::<S>(),
-                TaggedTypeErasedStorage {
-                    tag,
-                    storage: Box::new(storage),
-                },
+                TaggedTypeErasedStorage::new(storage, self.current_step),
             )
             .map(|tagged_storage| {
                 let boxed = tagged_storage
@@ -165,6 +266,85 @@ impl Universe {
             })
     }
 
+    /// Removes the given storage from the container and returns it.
+    ///
+    /// Returns `None` if no storage of the given type was present. This is useful for reclaiming the memory
+    /// of large storages that are no longer needed.
+    ///
+    /// Since removal requires `&mut self`, no references to the storage can be outstanding.
+    /// However, any raw pointers previously obtained from references to the storage are invalidated.
+    /// If the storage is subsequently accessed again, a new storage is created in its place.
+    pub fn remove_storage<S: Storage>(&mut self) -> Option<S> {
+        self.storages
+            .get_mut()
+            .remove(&TypeId::of::<S>())
+            .map(|tagged_storage| {
+                let boxed = tagged_storage
+                    .storage
+                    .downcast::<S>()
+                    .expect("Downcast cannot fail since TypeIDs match");
+                *boxed
+            })
+    }
+
+    /// Inserts the given resource into the universe.
+    ///
+    /// If a resource of the same type was already present, it is returned. Otherwise `None` is returned.
+    /// To include the resource in serialization, it must be registered with
+    /// [`register_resource`](crate::register_resource).
+    pub fn insert_resource<R: Resource>(&mut self, resource: R) -> Option<R> {
+        self.insert_storage(ResourceStorage::new(resource))
+            .map(ResourceStorage::into_resource)
+    }
+
+    /// Returns the resource of type `R`, if it has been inserted.
+    pub fn get_resource<R: Resource>(&self) -> Option<&R> {
+        self.try_get_storage::<ResourceStorage<R>>()
+            .map(ResourceStorage::get_resource)
+    }
+
+    /// Returns the resource of type `R` mutably, if it has been inserted.
+    pub fn get_resource_mut<R: Resource>(&mut self) -> Option<&mut R> {
+        self.storages
+            .get_mut()
+            .get_mut(&TypeId::of::<ResourceStorage<R>>())
+            .map(|tagged_storage| {
+                tagged_storage
+                    .storage
+                    .downcast_mut::<ResourceStorage<R>>()
+                    .expect("Can always downcast since TypeIds match")
+                    .get_resource_mut()
+            })
+    }
+
+    /// Appends the entities and components of another [`VecStorage`] to the storage of the component `C`.
+    ///
+    /// The entities of `other` are remapped to entities in this universe through `id_map`, which maps
+    /// entities in `other` to entities in this universe. Entities that are not present in the map are
+    /// assigned a new entity, which is then recorded in the map. By reusing the same map for multiple storages,
+    /// the relations between components of the same entity are preserved.
+    ///
+    /// If the remapped entity already has a component in this universe, it is replaced.
+    pub fn append_vec_storage<C>(&mut self, other: VecStorage<C>, id_map: &mut HashMap<Entity, Entity>)
+    where
+        C: Component<Storage = VecStorage<C>>,
+    {
+        let remapped: Vec<_> = other
+            .into_iter()
+            .map(|(entity, component)| {
+                let new_entity = *id_map
+                    .entry(entity)
+                    .or_insert_with(|| self.entity_factory.new_entity());
+                (new_entity, component)
+            })
+            .collect();
+
+        let storage = self.get_component_storage_mut::<C>();
+        for (entity, component) in remapped {
+            storage.insert(entity, component);
+        }
+    }
+
     /// Same as [`insert_storage`](Self::insert_storage), but additionally registers the storage for deserialization.
     pub fn register_insert_storage<S: SerializableStorage>(&mut self, storage: S) -> Option<S> {
         register_storage::<S>();
@@ -179,13 +359,11 @@ impl Universe {
     /// The storage is stable in memory: For as long as the universe is alive, the pointer to the
     /// storage will remain valid.
     pub fn get_storage_mut<S: Storage + Default>(&mut self) -> &mut S {
+        let current_step = self.current_step;
         let mut storages = self.storages.borrow_mut();
         let ref_mut = storages
             .entry(TypeId::of::<S>())
-            .or_insert_with(|| TaggedTypeErasedStorage {
-                tag: S::tag(),
-                storage: Box::new(S::default()),
-            })
+            .or_insert_with(|| TaggedTypeErasedStorage::new(S::default(), current_step))
             .storage
             .downcast_mut()
             .expect("Can always downcast since TypeIds match");
@@ -201,6 +379,33 @@ impl Universe {
         unsafe { &mut *ptr }
     }
 
+    /// Returns the set of entities that have a component of type `C`.
+    pub fn entities_with<C>(&self) -> EntitySet
+    where
+        C: Component<Storage = VecStorage<C>>,
+    {
+        self.get_component_storage::<C>()
+            .entities()
+            .iter()
+            .copied()
+            .collect()
+    }
+
+    /// Moves the storage out of the universe, leaving a default-constructed storage in its place.
+    ///
+    /// This enables exclusive processing of a single storage while the rest of the universe
+    /// remains accessible. The storage can be returned with [`put_storage`](Self::put_storage).
+    pub fn take_storage<S: Storage + Default>(&mut self) -> S {
+        std::mem::take(self.get_storage_mut::<S>())
+    }
+
+    /// Puts a storage back into the universe, replacing the current storage of the same type.
+    ///
+    /// This is the counterpart to [`take_storage`](Self::take_storage).
+    pub fn put_storage<S: Storage + Default>(&mut self, storage: S) {
+        *self.get_storage_mut::<S>() = storage;
+    }
+
     pub fn get_component_storage<C: Component>(&self) -> &C::Storage
     where
         C::Storage: Default,
@@ -228,7 +433,7 @@ impl Universe {
     /// # Examples
     /// ```rust
     ///# use dynamecs::{Component, Universe};
-    ///# use dynamecs::storages::vec_storage::VecStorage;
+    ///# use dynamecs::storages::VecStorage;
     ///# use std::default::Default;
     ///# use serde::{Serialize, Deserialize};
     ///# #[derive(Serialize, Deserialize)]
@@ -259,7 +464,7 @@ impl Universe {
     /// # Examples
     /// ```rust
     ///# use dynamecs::{Component, Universe};
-    ///# use dynamecs::storages::vec_storage::VecStorage;
+    ///# use dynamecs::storages::VecStorage;
     ///# use std::default::Default;
     ///# use serde::{Serialize, Deserialize};
     ///# #[derive(Serialize, Deserialize)]
@@ -288,7 +493,7 @@ impl Universe {
     ///
     /// ```
     ///# use dynamecs::{Component, Universe};
-    ///# use dynamecs::storages::vec_storage::VecStorage;
+    ///# use dynamecs::storages::VecStorage;
     ///# use std::default::Default;
     ///# use serde::{Serialize, Deserialize};
     ///# #[derive(Serialize, Deserialize)]
@@ -309,9 +514,51 @@ impl Universe {
         Fetch::Storages: 'a + Join,
     {
         let storages = Fetch::fetch_storages(self);
+        log_empty_join::<Fetch>(self.warn_on_empty_joins, || storages.driving_len());
         storages.join()
     }
 
+    /// Performs an immutable join over all entities in the storage of the `Master` component.
+    ///
+    /// In contrast to [`join`](Self::join), iteration is driven by the entities of `Master`, which
+    /// need not be part of the fetched components. Components wrapped in [`Optional`](crate::join::Optional)
+    /// do not exclude entities from the join, and are instead given as `Option<&C>`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    ///# use dynamecs::{Component, Universe};
+    ///# use dynamecs::join::Optional;
+    ///# use dynamecs::storages::VecStorage;
+    ///# use std::default::Default;
+    ///# use serde::{Serialize, Deserialize};
+    ///# #[derive(Serialize, Deserialize)]
+    ///# struct A; impl Component for A { type Storage = VecStorage<Self>; };
+    ///# #[derive(Serialize, Deserialize)]
+    ///# struct B; impl Component for B { type Storage = VecStorage<Self>; };
+    ///# #[derive(Serialize, Deserialize)]
+    ///# struct M; impl Component for M { type Storage = VecStorage<Self>; };
+    ///#
+    ///# let universe = Universe::default();
+    /// for (entity, a, maybe_b) in universe.join_driven_by::<M, (&A, Optional<&B>)>() {
+    ///     // Process components
+    /// }
+    /// ```
+    pub fn join_driven_by<'a, Master, Fetch>(
+        &'a self,
+    ) -> EntityJoinIter<'a, <Fetch::Storages as IntoJoinables<'a>>::Joinables>
+    where
+        Master: Component<Storage = VecStorage<Master>>,
+        Fetch: FetchComponentStorages<'a>,
+        Fetch::Storages: IntoJoinables<'a>,
+    {
+        let entities = self.get_component_storage::<Master>().entities();
+        log_empty_join::<Master>(self.warn_on_empty_joins, || Some(entities.len()));
+        let joinables = Fetch::fetch_storages(self).into_joinables();
+        // SAFETY: Entities in a VecStorage are unique
+        unsafe { EntityJoinIter::new(entities, joinables) }
+    }
+
     /// Performs a join operation on the storages associated with the given components, possibly giving mutable
     /// access to components.
     ///
@@ -322,7 +569,7 @@ impl Universe {
     ///
     /// ```
     ///# use dynamecs::{Component, Universe};
-    ///# use dynamecs::storages::vec_storage::VecStorage;
+    ///# use dynamecs::storages::VecStorage;
     ///# use std::default::Default;
     ///# use serde::{Serialize, Deserialize};
     ///# #[derive(Serialize, Deserialize)]
@@ -343,10 +590,97 @@ impl Universe {
         Fetch: FetchComponentStoragesMut<'a>,
         Fetch::Storages: 'a + Join,
     {
+        let warn_on_empty_joins = self.warn_on_empty_joins;
         let storages = Fetch::fetch_storages_mut(self);
+        log_empty_join::<Fetch>(warn_on_empty_joins, || storages.driving_len());
         storages.join()
     }
 
+    /// Performs a parallel immutable join over the storages associated with the given components.
+    ///
+    /// Same as [`join`](Self::join), but returns a [`rayon`] parallel iterator. The order in which entities
+    /// are visited is unspecified. Requires the `rayon` feature.
+    #[cfg(feature = "rayon")]
+    pub fn par_join<'a, Fetch>(
+        &'a self,
+    ) -> impl rayon::iter::ParallelIterator<Item = <Fetch::Storages as ParJoin>::Item> + 'a
+    where
+        Fetch: FetchComponentStorages<'a>,
+        Fetch::Storages: 'a + ParJoin,
+    {
+        let storages = Fetch::fetch_storages(self);
+        storages.par_join()
+    }
+
+    /// Performs a parallel join over the storages associated with the given components, possibly giving
+    /// mutable access to components.
+    ///
+    /// Same as [`join_mut`](Self::join_mut), but returns a [`rayon`] parallel iterator. The order in which
+    /// entities are visited is unspecified. Requires the `rayon` feature.
+    #[cfg(feature = "rayon")]
+    pub fn par_join_mut<'a, Fetch>(
+        &'a mut self,
+    ) -> impl rayon::iter::ParallelIterator<Item = <Fetch::Storages as ParJoin>::Item> + 'a
+    where
+        Fetch: FetchComponentStoragesMut<'a>,
+        Fetch::Storages: 'a + ParJoin,
+    {
+        let storages = Fetch::fetch_storages_mut(self);
+        storages.par_join()
+    }
+
+    /// Removes all data associated with the given entity from every storage.
+    ///
+    /// Data is removed through [`Storage::remove_entity`]. Returns the number of storages from which
+    /// data was removed.
+    pub fn despawn_entity(&mut self, entity: Entity) -> usize {
+        self.storages
+            .get_mut()
+            .values_mut()
+            .map(|tagged_storage| (tagged_storage.remove_entity)(tagged_storage.storage.as_mut(), entity))
+            .filter(|&removed| removed)
+            .count()
+    }
+
+    /// Informs every storage of the index of the current step, see [`Storage::set_current_step`].
+    ///
+    /// Storages that are created later, for example when they are first accessed, are also informed.
+    pub fn set_current_step(&mut self, step_index: usize) {
+        self.current_step = Some(step_index);
+        for tagged_storage in self.storages.get_mut().values_mut() {
+            (tagged_storage.set_current_step)(tagged_storage.storage.as_mut(), step_index);
+        }
+    }
+
+    /// Returns the tags of all storages present in the universe, in sorted order.
+    pub fn storage_tags(&self) -> Vec<String> {
+        let mut tags: Vec<_> = self
+            .storages
+            .borrow()
+            .values()
+            .map(|tagged_storage| tagged_storage.tag.clone())
+            .collect();
+        tags.sort_unstable();
+        tags
+    }
+
+    /// Returns the version of the storage with the given tag.
+    ///
+    /// The version is obtained through [`Storage::storage_version_number`]. Returns `None` if no
+    /// storage with the given tag exists in the universe, or if the storage does not track a version.
+    pub fn storage_version_by_tag(&self, tag: &str) -> Option<u64> {
+        self.storages
+            .borrow()
+            .values()
+            .find(|tagged_storage| tagged_storage.tag == tag)
+            .and_then(|tagged_storage| (tagged_storage.storage_version)(tagged_storage.storage.as_ref()))
+    }
+
+    /// Applies the commands recorded in the given buffer, in the order in which they were recorded.
+    pub fn apply_commands(&mut self, buffer: CommandBuffer) {
+        buffer.apply(self)
+    }
+
     pub fn insert_component<C: Component>(&mut self, entity: Entity, component: C)
     where
         C::Storage: Default + InsertComponentForEntity<C>,
@@ -355,6 +689,32 @@ impl Universe {
             .insert_component_for_entity(entity, component)
     }
 
+    /// Inserts a component for the given entity, and returns the component that was previously
+    /// associated with the entity, if any.
+    ///
+    /// See [`VecStorage::replace`].
+    pub fn replace_component<C>(&mut self, entity: Entity, component: C) -> Option<C>
+    where
+        C: Component<Storage = VecStorage<C>>,
+    {
+        self.get_component_storage_mut::<C>()
+            .replace(entity, component)
+    }
+
+    /// Registers the component `C` for serialization and eagerly creates its storage.
+    ///
+    /// Storages are otherwise created lazily when first accessed. Creating the storage up front
+    /// ensures that [`try_get_component_storage`](Self::try_get_component_storage) returns `Some`
+    /// even before any component has been inserted, which distinguishes storages that are declared
+    /// but empty from storages that have never been touched.
+    pub fn register_and_create<C: Component>(&mut self) -> &mut C::Storage
+    where
+        C::Storage: SerializableStorage + Default,
+    {
+        register_component::<C>();
+        self.get_component_storage_mut::<C>()
+    }
+
     /// Same as [`insert_component`](Self::insert_component), but additionally registers the component
     /// for deserialization.
     pub fn register_insert_component<C: Component>(&mut self, entity: Entity, component: C)
@@ -391,6 +751,16 @@ impl Universe {
     }
 }
 
+/// Logs that a join over the components `Fetch` yields no results, if enabled and the driving storage is empty.
+fn log_empty_join<Fetch>(enabled: bool, driving_len: impl FnOnce() -> Option<usize>) {
+    if enabled && driving_len() == Some(0) {
+        debug!(
+            "Join over {} yields no results, since the driving storage is empty or absent",
+            std::any::type_name::<Fetch>()
+        );
+    }
+}
+
 impl Debug for Universe {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         let storage_tags: Vec<_> = self
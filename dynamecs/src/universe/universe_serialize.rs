@@ -1,4 +1,3 @@
-use std::any::Any;
 use std::cell::RefCell;
 use std::collections::HashMap;
 use std::fmt;
@@ -11,8 +10,9 @@ use serde::de::{DeserializeSeed, SeqAccess, Visitor};
 use serde::ser::{SerializeSeq, SerializeTuple};
 use serde::{Deserialize, Deserializer, Serializer};
 
+use crate::serialization::TransientStorageSerializer;
 use crate::universe::{Storages, TaggedTypeErasedStorage};
-use crate::{SerializableStorage, StorageSerializer, Universe};
+use crate::{SerializableStorage, Storage, StorageSerializer, Universe};
 
 static REGISTRY: Lazy<Mutex<HashMap<String, Box<dyn StorageSerializer>>>> = Lazy::new(|| Mutex::new(HashMap::new()));
 
@@ -47,6 +47,24 @@ where
     register_serializer(serializer)
 }
 
+/// Registers the [`Transient`](crate::storages::Transient) wrapper of the given storage, so that it is omitted from serialization.
+pub fn register_transient_storage<S>() -> RegistrationStatus
+where
+    S: Storage + Default,
+{
+    register_serializer(Box::new(TransientStorageSerializer::<S>::new()))
+}
+
+/// Returns the tags of all storages registered for serialization in this process, in sorted order.
+pub fn registered_tags() -> Vec<String> {
+    let hash_map = REGISTRY
+        .lock()
+        .expect("Internal error: Lock should never fail");
+    let mut tags: Vec<_> = hash_map.keys().cloned().collect();
+    tags.sort_unstable();
+    tags
+}
+
 fn look_up_serializer<R>(tag: &str, f: impl FnOnce(&dyn StorageSerializer) -> R) -> Option<R> {
     let hash_map = REGISTRY
         .lock()
@@ -110,7 +128,7 @@ impl<'de> Visitor<'de> for TaggedTypeErasedStorageVisitor {
         }
 
         impl<'a, 'de> DeserializeSeed<'de> for TypeErasedStorageSeed<'a> {
-            type Value = Box<dyn Any + 'static>;
+            type Value = TaggedTypeErasedStorage;
 
             fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
             where
@@ -118,7 +136,15 @@ impl<'de> Visitor<'de> for TaggedTypeErasedStorageVisitor {
             {
                 look_up_serializer(&self.tag, |storage_serializer| {
                     let erased_deserializer = &mut <dyn erased_serde::Deserializer>::erase(deserializer);
-                    storage_serializer.deserialize_storage(erased_deserializer)
+                    storage_serializer
+                        .deserialize_storage(erased_deserializer)
+                        .map(|storage| TaggedTypeErasedStorage {
+                            tag: self.tag.to_string(),
+                            storage,
+                            remove_entity: storage_serializer.entity_remover(),
+                            storage_version: storage_serializer.storage_version_reader(),
+                            set_current_step: storage_serializer.current_step_setter(),
+                        })
                 })
                 .ok_or_else(|| {
                     let msg = format!(
@@ -135,14 +161,8 @@ impl<'de> Visitor<'de> for TaggedTypeErasedStorageVisitor {
             .next_element()?
             .ok_or_else(|| serde::de::Error::custom("missing tag in sequence"))?;
 
-        let erased_storage = seq
-            .next_element_seed(TypeErasedStorageSeed { tag: &tag })?
-            .ok_or_else(|| serde::de::Error::custom("missing storage in sequence"))?;
-
-        Ok(TaggedTypeErasedStorage {
-            tag,
-            storage: erased_storage,
-        })
+        seq.next_element_seed(TypeErasedStorageSeed { tag: &tag })?
+            .ok_or_else(|| serde::de::Error::custom("missing storage in sequence"))
     }
 }
 
@@ -161,9 +181,14 @@ impl serde::Serialize for Storages {
         S: Serializer,
     {
         let storages = self.storages.borrow();
-        let mut seq = serializer.serialize_seq(Some(storages.len()))?;
-        for (_, storage) in storages.iter() {
-            seq.serialize_element(&storage)?;
+        // Transient storages are omitted entirely
+        let non_transient_storages: Vec<_> = storages
+            .values()
+            .filter(|storage| look_up_serializer(&storage.tag, |serializer| serializer.is_transient()) != Some(true))
+            .collect();
+        let mut seq = serializer.serialize_seq(Some(non_transient_storages.len()))?;
+        for storage in non_transient_storages {
+            seq.serialize_element(storage)?;
         }
         seq.end()
     }
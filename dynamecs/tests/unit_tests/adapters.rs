@@ -1,14 +1,20 @@
+use crate::unit_tests::CapturedLogs;
 use dynamecs::{
-    adapters::{FilterSystem, FnOnceSystem, FnSystem, SingleShotSystem},
+    adapters::{FilterSystem, FnOnceSystem, FnSystem, PrecomputeThenObserve, SingleShotSystem},
+    components::{get_step_index, StepIndex},
     storages::SingularStorage,
-    Component, System, Universe,
+    Component, ObserverSystem, System, Systems, Universe,
 };
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
 
 #[test]
 fn fn_system() {
-    let mut value = 0;
-    let mut system = FnSystem::new("test", |_universe| {
-        value = 27;
+    // Systems must be 'static, so we share the value through a reference-counted cell
+    let value = Rc::new(Cell::new(0));
+    let value_in_system = Rc::clone(&value);
+    let mut system = FnSystem::new("test", move |_universe| {
+        value_in_system.set(27);
         Ok(())
     });
 
@@ -18,7 +24,7 @@ fn fn_system() {
     let res = system.run(&mut universe);
 
     assert!(res.is_ok());
-    assert_eq!(value, 27);
+    assert_eq!(value.get(), 27);
 }
 
 #[test]
@@ -28,11 +34,11 @@ fn fn_once_system() {
     }
 
     let value_source = Test { value: 27 };
-    let mut value_target = Test { value: 0 };
-    let value_target_mut = &mut value_target;
+    let value_target = Rc::new(RefCell::new(Test { value: 0 }));
+    let value_target_in_system = Rc::clone(&value_target);
 
     let mut system = FnOnceSystem::new("test", move |_universe| {
-        *value_target_mut = value_source;
+        *value_target_in_system.borrow_mut() = value_source;
         Ok(())
     });
 
@@ -44,7 +50,7 @@ fn fn_once_system() {
 
     assert!(res.is_ok());
     assert_eq!(system.has_run(), true);
-    assert_eq!(value_target.value, 27);
+    assert_eq!(value_target.borrow().value, 27);
 }
 
 #[derive(Debug)]
@@ -194,3 +200,151 @@ fn filter_system_combinator() {
     assert!(res.is_ok());
     assert_eq!(MockSystem::runs(&universe), 1);
 }
+
+#[test]
+fn every_n_steps_combinator() {
+    let mut universe = Universe::default();
+
+    let steps_run = Rc::new(RefCell::new(Vec::new()));
+    let steps_run_in_system = Rc::clone(&steps_run);
+    let mut system = FnSystem::new("record_step", move |universe| {
+        steps_run_in_system
+            .borrow_mut()
+            .push(get_step_index(universe).0);
+        Ok(())
+    })
+    .every_n_steps(3);
+
+    for step_index in 0..10 {
+        universe.insert_storage(SingularStorage::new(StepIndex(step_index)));
+        let res = system.run(&mut universe);
+        assert!(res.is_ok());
+    }
+
+    assert_eq!(*steps_run.borrow(), vec![0, 3, 6, 9]);
+}
+
+#[test]
+#[should_panic(expected = "n = 0")]
+fn every_n_steps_panics_for_zero() {
+    let _ = MockSystem {}.every_n_steps(0);
+}
+
+#[derive(Debug, Default)]
+struct FailingObserver {
+    runs: usize,
+}
+
+impl ObserverSystem for FailingObserver {
+    fn run(&mut self, _data: &Universe) -> eyre::Result<()> {
+        self.runs += 1;
+        Err(eyre::eyre!("plotting window closed"))
+    }
+}
+
+#[test]
+fn soft_fail_system_logs_error_and_continues() {
+    let logs = CapturedLogs::default();
+    let writer = logs.clone();
+    let subscriber = tracing_subscriber::fmt()
+        .with_writer(move || writer.clone())
+        .finish();
+
+    let mut universe = Universe::default();
+    let mut system = FailingObserver::default().soft_fail();
+    assert!(system.name().starts_with("SoftFailSystem("));
+
+    tracing::subscriber::with_default(subscriber, || {
+        system.run(&mut universe).unwrap();
+        system.run(&mut universe).unwrap();
+    });
+    assert_eq!(system.system.runs, 2);
+    assert_eq!(system.failure_count(), 2);
+    let contents = logs.contents();
+    assert!(contents.contains("WARN"));
+    assert!(contents.contains("plotting window closed"));
+
+    // Subsequent systems still run
+    let counter = Rc::new(Cell::new(0));
+    let mut systems = Systems::default();
+    systems.add_system(FailingObserver::default().soft_fail());
+    systems.add_system(FnSystem::new("count", {
+        let counter = counter.clone();
+        move |_| {
+            counter.set(counter.get() + 1);
+            Ok(())
+        }
+    }));
+    systems.run_all(&mut universe).unwrap();
+    assert_eq!(counter.get(), 1);
+}
+
+#[test]
+fn soft_fail_observer_logs_error_and_remains_observer() {
+    fn run_observer(observer: &mut impl ObserverSystem, universe: &Universe) -> eyre::Result<()> {
+        observer.run(universe)
+    }
+
+    let logs = CapturedLogs::default();
+    let writer = logs.clone();
+    let subscriber = tracing_subscriber::fmt()
+        .with_writer(move || writer.clone())
+        .finish();
+
+    let universe = Universe::default();
+    let mut observer = FailingObserver::default().soft_fail_observer();
+    assert_eq!(
+        ObserverSystem::name(&observer),
+        format!("SoftFailObserver({})", std::any::type_name::<FailingObserver>())
+    );
+
+    tracing::subscriber::with_default(subscriber, || {
+        run_observer(&mut observer, &universe).unwrap();
+        run_observer(&mut observer, &universe).unwrap();
+    });
+    assert_eq!(observer.observer.runs, 2);
+    assert_eq!(observer.failure_count(), 2);
+    let contents = logs.contents();
+    assert!(contents.contains("WARN"));
+    assert!(contents.contains("plotting window closed"));
+}
+
+#[test]
+fn precompute_then_observe_shares_precomputed_result() {
+    let num_precomputations = Rc::new(Cell::new(0));
+    let observed = Rc::new(RefCell::new(Vec::new()));
+
+    let system = PrecomputeThenObserve::new("spatial_hash", {
+        let num_precomputations = num_precomputations.clone();
+        move |universe: &Universe| {
+            num_precomputations.set(num_precomputations.get() + 1);
+            Ok(get_step_index(universe).0 * 10)
+        }
+    })
+    .with_observer({
+        let observed = observed.clone();
+        move |precomputed: &usize, _| {
+            observed.borrow_mut().push(("first", *precomputed));
+            Ok(())
+        }
+    })
+    .with_observer({
+        let observed = observed.clone();
+        move |precomputed: &usize, universe: &Universe| {
+            assert_eq!(*precomputed, get_step_index(universe).0 * 10);
+            observed.borrow_mut().push(("second", *precomputed));
+            Ok(())
+        }
+    });
+    assert_eq!(system.num_observers(), 2);
+    assert_eq!(ObserverSystem::name(&system), "spatial_hash");
+
+    let mut universe = Universe::default();
+    universe.insert_storage(SingularStorage::new(StepIndex(3)));
+    let mut systems = Systems::default();
+    systems.add_system(system);
+    systems.run_all(&mut universe).unwrap();
+
+    assert_eq!(num_precomputations.get(), 1);
+    assert_eq!(*observed.borrow(), vec![("first", 30), ("second", 30)]);
+}
@@ -1,6 +1,8 @@
-use super::dummy_components::{A, B, C, D, E, F, G, H};
+use super::dummy_components::{A, B, C, D, E, F, G, H, I, J, K, L};
 use cool_asserts::assert_panics;
-use dynamecs::{Component, Universe};
+use dynamecs::storages::VecStorage;
+use dynamecs::{registered_tags, Component, GetComponentForEntity, Storage, Universe};
+use std::collections::HashMap;
 
 type StorageFor<C> = <C as Component>::Storage;
 type S<C> = StorageFor<C>;
@@ -24,6 +26,7 @@ fn get_component_storages_compiles_for_tuple_arguments() {
 }
 
 #[test]
+#[allow(clippy::type_complexity)]
 fn get_component_storages_mut_compiles_for_tuple_arguments() {
     let mut universe = Universe::default();
 
@@ -101,6 +104,23 @@ fn get_component_storages_mut_compiles_for_tuple_arguments() {
         &mut S<G>,
         &mut S<H>,
     ) = universe.get_component_storages_mut::<(&mut A, &mut B, &mut C, &mut D, &mut E, &mut F, &mut G, &mut H)>();
+
+    // 12-element tuple
+    let _: (
+        &mut S<A>,
+        &S<B>,
+        &mut S<C>,
+        &S<D>,
+        &S<E>,
+        &mut S<F>,
+        &S<G>,
+        &mut S<H>,
+        &S<I>,
+        &mut S<J>,
+        &S<K>,
+        &mut S<L>,
+    ) = universe
+        .get_component_storages_mut::<(&mut A, &B, &mut C, &D, &E, &mut F, &G, &mut H, &I, &mut J, &K, &mut L)>();
 }
 
 #[test]
@@ -128,4 +148,157 @@ fn get_component_storages_mut_panics_if_duplicate_arguments_provided() {
         },
         includes(expected_msg)
     );
+
+    assert_panics!(
+        {
+            let _ = Universe::default()
+                .get_component_storages_mut::<(&mut A, &B, &C, &D, &E, &F, &G, &H, &I, &J, &K, &mut A)>();
+        },
+        includes(expected_msg)
+    );
+}
+
+#[test]
+fn append_vec_storage_remaps_entities() {
+    let mut universe = Universe::default();
+    let e0 = universe.new_entity();
+    let e1 = universe.new_entity();
+    universe.insert_component(e0, A(0));
+    universe.insert_component(e1, A(1));
+
+    // Entities from a different universe, which may collide with entities in the target universe
+    let other_universe = Universe::default();
+    let o0 = other_universe.new_entity();
+    let o1 = other_universe.new_entity();
+    let mut other_a = VecStorage::new();
+    other_a.insert(o0, A(10));
+    other_a.insert(o1, A(11));
+    let mut other_b = VecStorage::new();
+    other_b.insert(o1, B(21));
+
+    let mut id_map = HashMap::new();
+    universe.append_vec_storage(other_a, &mut id_map);
+    universe.append_vec_storage(other_b, &mut id_map);
+
+    assert_eq!(id_map.len(), 2);
+    let (n0, n1) = (id_map[&o0], id_map[&o1]);
+    assert!(![e0, e1].contains(&n0));
+    assert!(![e0, e1].contains(&n1));
+    assert_ne!(n0, n1);
+
+    let a_storage = universe.get_component_storage::<A>();
+    assert_eq!(a_storage.len(), 4);
+    assert_eq!(a_storage.get_component(e0), Some(&A(0)));
+    assert_eq!(a_storage.get_component(e1), Some(&A(1)));
+    assert_eq!(a_storage.get_component(n0), Some(&A(10)));
+    assert_eq!(a_storage.get_component(n1), Some(&A(11)));
+
+    let b_storage = universe.get_component_storage::<B>();
+    assert_eq!(b_storage.len(), 1);
+    assert_eq!(b_storage.get_component(n1), Some(&B(21)));
+}
+
+#[test]
+fn recorded_entities_can_be_replayed_in_another_universe() {
+    let recording_universe = Universe::default();
+    // Create some entities before recording, so that ids differ from a fresh universe
+    recording_universe.new_entity();
+    recording_universe.new_entity();
+
+    recording_universe.start_recording_entities();
+    let created: Vec<_> = (0..3).map(|_| recording_universe.new_entity()).collect();
+    let recorded = recording_universe.stop_recording_entities();
+    assert_eq!(recorded, created);
+    assert!(recording_universe.stop_recording_entities().is_empty());
+
+    let replaying_universe = Universe::default();
+    replaying_universe
+        .replay_entities(recorded.clone())
+        .unwrap();
+    let replayed: Vec<_> = (0..3).map(|_| replaying_universe.new_entity()).collect();
+    assert_eq!(replayed, recorded);
+
+    // After the replay is exhausted, new entities must not collide with the replayed ones
+    let next = replaying_universe.new_entity();
+    assert!(!recorded.contains(&next));
+}
+
+#[test]
+fn replaying_colliding_entities_fails() {
+    let recording_universe = Universe::default();
+    recording_universe.start_recording_entities();
+    let e0 = recording_universe.new_entity();
+    let e1 = recording_universe.new_entity();
+    recording_universe.stop_recording_entities();
+
+    // Duplicate entities in the replay collide with each other
+    let replaying_universe = Universe::default();
+    assert!(replaying_universe.replay_entities([e0, e1, e0]).is_err());
+
+    // The entity e0 has already been created by the replaying universe
+    assert_eq!(replaying_universe.new_entity(), e0);
+    assert!(replaying_universe.replay_entities([e0, e1]).is_err());
+    assert!(replaying_universe.replay_entities([e1]).is_ok());
+    assert_eq!(replaying_universe.new_entity(), e1);
+}
+
+#[test]
+fn take_storage_and_put_storage_round_trip() {
+    let mut universe = Universe::default();
+    let e1 = universe.new_entity();
+    let e2 = universe.new_entity();
+    universe.insert_component(e1, A(1));
+    universe.insert_component(e1, B(1));
+
+    let mut a_storage = universe.take_storage::<VecStorage<A>>();
+    assert!(universe.get_component_storage::<A>().is_empty());
+
+    // Other storages remain accessible while the taken storage is processed standalone
+    a_storage.insert(e2, A(universe.get_component_storage::<B>().components()[0].0 + 1));
+    universe.insert_component(e2, B(2));
+
+    universe.put_storage(a_storage);
+    assert_eq!(universe.get_component_storage::<A>().components(), &[A(1), A(2)]);
+    assert_eq!(universe.get_component_storage::<A>().entities(), &[e1, e2]);
+    assert_eq!(universe.get_component_storage::<B>().components(), &[B(1), B(2)]);
+}
+
+#[test]
+fn remove_storage_then_reinsert() {
+    let mut universe = Universe::default();
+    assert!(universe.remove_storage::<S<A>>().is_none());
+
+    let entity = universe.new_entity();
+    universe.insert_component(entity, A(1));
+
+    let removed = universe.remove_storage::<S<A>>().unwrap();
+    assert_eq!(removed.get_component_for_entity(entity), Some(&A(1)));
+    assert!(universe.try_get_storage::<S<A>>().is_none());
+    assert!(universe.remove_storage::<S<A>>().is_none());
+
+    let mut fresh_storage = VecStorage::default();
+    fresh_storage.insert(entity, A(2));
+    assert!(universe.insert_storage(fresh_storage).is_none());
+    assert_eq!(
+        universe
+            .get_component_storage::<A>()
+            .get_component_for_entity(entity),
+        Some(&A(2))
+    );
+}
+
+#[test]
+fn register_and_create_creates_empty_storage() {
+    let mut universe = Universe::default();
+    assert!(universe.try_get_component_storage::<A>().is_none());
+
+    assert!(universe.register_and_create::<A>().is_empty());
+    let storage = universe.try_get_component_storage::<A>();
+    assert!(storage.is_some_and(|storage| storage.is_empty()));
+    assert!(registered_tags().contains(&StorageFor::<A>::tag()));
+
+    // Creating an existing storage leaves its contents untouched
+    let entity = universe.new_entity();
+    universe.insert_component(entity, A(1));
+    assert_eq!(universe.register_and_create::<A>().len(), 1);
 }
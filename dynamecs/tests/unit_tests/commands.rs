@@ -0,0 +1,55 @@
+use crate::unit_tests::dummy_components::{A, B};
+use dynamecs::commands::CommandBuffer;
+use dynamecs::Universe;
+
+#[test]
+fn command_buffer_inserts_components_queued_during_join() {
+    let mut universe = Universe::default();
+    let [e1, e2, e3] = [(); 3].map(|_| universe.new_entity());
+    for (i, entity) in [e1, e2, e3].into_iter().enumerate() {
+        universe.insert_component(entity, A(i));
+    }
+
+    let mut commands = CommandBuffer::new();
+    let mut spawned = Vec::new();
+    for (entity, a) in universe.join::<&A>() {
+        if a.0 != 1 {
+            commands.insert_component(entity, B(10 * a.0));
+            let child = commands.spawn(&universe);
+            commands.insert_component(child, A(100 + a.0));
+            spawned.push(child);
+        }
+    }
+    assert_eq!(commands.len(), 4);
+    // The spawned entities are allocated immediately, but no components are inserted yet
+    assert!(![e1, e2, e3].contains(&spawned[0]));
+    assert_ne!(spawned[0], spawned[1]);
+    assert!(universe.get_component_storage::<B>().is_empty());
+
+    universe.apply_commands(commands);
+
+    let joined: Vec<_> = universe
+        .join::<(&A, &B)>()
+        .map(|(entity, a, b)| (entity, a.0, b.0))
+        .collect();
+    assert_eq!(joined, vec![(e1, 0, 0), (e3, 2, 20)]);
+    let a_storage = universe.get_component_storage::<A>();
+    assert_eq!(a_storage.get_component(spawned[0]), Some(&A(100)));
+    assert_eq!(a_storage.get_component(spawned[1]), Some(&A(102)));
+}
+
+#[test]
+fn command_buffer_applies_commands_in_order() {
+    let mut universe = Universe::default();
+    let entity = universe.new_entity();
+
+    let mut commands = CommandBuffer::new();
+    assert!(commands.is_empty());
+    commands.insert_component(entity, A(1));
+    commands.despawn(entity);
+    commands.insert_component(entity, B(2));
+    universe.apply_commands(commands);
+
+    assert_eq!(universe.get_component_storage::<A>().get_component(entity), None);
+    assert_eq!(universe.get_component_storage::<B>().get_component(entity), Some(&B(2)));
+}
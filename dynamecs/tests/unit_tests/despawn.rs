@@ -0,0 +1,57 @@
+use crate::unit_tests::dummy_components::{A, B, C, D};
+use dynamecs::components::{register_default_components, Name};
+use dynamecs::storages::{SingularStorage, VersionedVecStorage};
+use dynamecs::{register_component, Universe};
+
+#[test]
+fn despawn_entity_removes_components_from_all_storages() {
+    let mut universe = Universe::default();
+    let [e1, e2, e3] = [(); 3].map(|_| universe.new_entity());
+    for (i, entity) in [e1, e2, e3].into_iter().enumerate() {
+        universe.insert_component(entity, A(i));
+        universe.insert_component(entity, B(i));
+        universe.insert_component(entity, C(i));
+        universe
+            .get_storage_mut::<VersionedVecStorage<D>>()
+            .insert(entity, D(i));
+    }
+    universe.insert_component(e2, Name::from("e2"));
+    universe.insert_storage(SingularStorage::new(A(42)));
+
+    assert_eq!(universe.despawn_entity(e2), 5);
+    assert_eq!(universe.despawn_entity(e2), 0);
+
+    let joined: Vec<_> = universe
+        .join::<(&A, &B, &C)>()
+        .map(|(entity, ..)| entity)
+        .collect();
+    assert_eq!(joined, vec![e1, e3]);
+    assert_eq!(universe.get_component_for_entity::<A>(e2), None);
+    assert!(universe.get_component_for_entity::<Name>(e2).is_none());
+    let d_storage = universe.get_storage::<VersionedVecStorage<D>>();
+    assert_eq!(d_storage.get_component(e2), None);
+    assert_eq!(d_storage.entities().len(), 2);
+    // Storages that are not associated with entities are untouched
+    let singular_storage = universe.try_get_storage::<SingularStorage<A>>().unwrap();
+    assert_eq!(singular_storage.get_component(), &A(42));
+}
+
+#[test]
+fn despawn_entity_removes_components_from_deserialized_storages() {
+    register_component::<A>();
+    register_default_components();
+
+    let mut universe = Universe::default();
+    let [e1, e2] = [(); 2].map(|_| universe.new_entity());
+    universe.insert_component(e1, A(1));
+    universe.insert_component(e2, A(2));
+    universe.insert_component(e2, Name::from("e2"));
+
+    let json = serde_json::to_string(&universe).unwrap();
+    let mut restored: Universe = serde_json::from_str(&json).unwrap();
+    assert_eq!(restored.despawn_entity(e2), 2);
+    let a_storage = restored.get_component_storage::<A>();
+    assert_eq!(a_storage.get_component(e1), Some(&A(1)));
+    assert_eq!(a_storage.get_component(e2), None);
+    assert!(restored.get_component_for_entity::<Name>(e2).is_none());
+}
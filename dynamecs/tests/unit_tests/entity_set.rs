@@ -0,0 +1,54 @@
+use super::dummy_components::{A, B};
+use dynamecs::{EntitySet, Universe};
+
+#[test]
+fn entity_set_operations() {
+    let universe = Universe::default();
+    let entities: Vec<_> = (0..5).map(|_| universe.new_entity()).collect();
+    let set1: EntitySet = entities[0..3].iter().copied().collect();
+    let set2: EntitySet = entities[2..5].iter().copied().collect();
+
+    assert_eq!(set1.intersection(&set2).iter().collect::<Vec<_>>(), vec![entities[2]]);
+    assert_eq!(set1.union(&set2).iter().collect::<Vec<_>>(), entities);
+    assert_eq!(
+        set1.difference(&set2).iter().collect::<Vec<_>>(),
+        vec![entities[0], entities[1]]
+    );
+    assert_eq!(
+        set2.difference(&set1).iter().collect::<Vec<_>>(),
+        vec![entities[3], entities[4]]
+    );
+    assert!(set1.difference(&set1).is_empty());
+
+    let mut set = EntitySet::new();
+    assert!(set.insert(entities[1]));
+    assert!(!set.insert(entities[1]));
+    assert!(set.contains(entities[1]));
+    assert!(set.remove(entities[1]));
+    assert!(!set.contains(entities[1]));
+    assert_eq!(set.len(), 0);
+}
+
+#[test]
+fn entity_set_from_component_entities() {
+    let mut universe = Universe::default();
+    let entities: Vec<_> = (0..4).map(|_| universe.new_entity()).collect();
+    universe.insert_component(entities[0], A(0));
+    universe.insert_component(entities[1], A(1));
+    universe.insert_component(entities[2], A(2));
+    universe.insert_component(entities[1], B(1));
+    universe.insert_component(entities[3], B(3));
+
+    let with_a = universe.entities_with::<A>();
+    let with_b = universe.entities_with::<B>();
+    assert_eq!(with_a.len(), 3);
+    assert!(with_a.contains(entities[2]));
+    assert!(!with_a.contains(entities[3]));
+    assert_eq!(
+        with_a.intersection(&with_b).iter().collect::<Vec<_>>(),
+        vec![entities[1]]
+    );
+    assert!(universe
+        .entities_with::<dynamecs::components::Name>()
+        .is_empty());
+}
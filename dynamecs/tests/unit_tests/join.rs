@@ -1,7 +1,9 @@
 use crate::unit_tests::dummy_components::{A, B, C};
-use dynamecs::join::Join;
+use crate::unit_tests::CapturedLogs;
+use dynamecs::join::{IntoJoinable, Join, Joinable, Optional, Without};
 use dynamecs::storages::VecStorage;
 use dynamecs::{Entity, Universe};
+use std::cell::Cell;
 
 #[test]
 #[rustfmt::skip]
@@ -124,6 +126,44 @@ fn join_multiple_storages() {
     }
 }
 
+#[test]
+fn join_without_excludes_entities_with_component() {
+    let mut universe = Universe::default();
+    let TestData {
+        v,
+        x,
+        y,
+        z,
+        mut a_storage,
+        b_storage,
+        c_storage,
+    } = TestData::new_for_universe(&universe);
+
+    let a_without_b: Vec<_> = (&a_storage, Without(&b_storage)).join().collect();
+    assert_eq!(a_without_b, vec![(y, &A(3), ())]);
+
+    let a_without_c: Vec<_> = (&a_storage, Without(&c_storage))
+        .join()
+        .map(|(entity, ..)| entity)
+        .collect();
+    assert_eq!(a_without_c, vec![z]);
+
+    for (_, a, ()) in (&mut a_storage, Without(&b_storage)).join() {
+        a.0 = 0;
+    }
+    assert_eq!(a_storage.get_component(y), Some(&A(0)));
+    assert_eq!(a_storage.get_component(v), Some(&A(1)));
+    assert_eq!(a_storage.get_component(x), Some(&A(2)));
+
+    universe.insert_storage(a_storage);
+    universe.insert_storage(b_storage);
+    let universe_join: Vec<_> = universe
+        .join::<(&A, Without<&B>)>()
+        .map(|(entity, ..)| entity)
+        .collect();
+    assert_eq!(universe_join, vec![y]);
+}
+
 #[test]
 fn universe_join_is_consistent_with_join() {
     let universe = Universe::default();
@@ -155,3 +195,232 @@ fn universe_join_is_consistent_with_join() {
         ]
     );
 }
+
+#[test]
+fn universe_join_driven_by_covers_all_master_entities() {
+    let universe = Universe::default();
+    let TestData {
+        v,
+        x,
+        y,
+        z,
+        a_storage,
+        b_storage,
+        c_storage,
+    } = TestData::new_for_universe(&universe);
+
+    let mut universe = Universe::default();
+    universe.insert_storage(a_storage);
+    universe.insert_storage(b_storage);
+    universe.insert_storage(c_storage);
+
+    // C is present for v, x, y and A is present for all of them, but B is missing for y
+    let joined: Vec<_> = universe.join_driven_by::<C, (&A, Optional<&B>)>().collect();
+    assert_eq!(
+        joined,
+        vec![(v, &A(1), Some(&B(1))), (x, &A(2), Some(&B(2))), (y, &A(3), None)]
+    );
+
+    // Entities of the master storage are skipped if a required component is missing
+    let joined: Vec<_> = universe.join_driven_by::<A, (&B, Optional<&C>)>().collect();
+    assert_eq!(
+        joined,
+        vec![(v, &B(1), Some(&C(1))), (x, &B(2), Some(&C(2))), (z, &B(3), None)]
+    );
+
+    let joined: Vec<_> = universe
+        .join_driven_by::<A, (Optional<&B>, Optional<&C>)>()
+        .collect();
+    assert_eq!(
+        joined,
+        vec![
+            (v, Some(&B(1)), Some(&C(1))),
+            (x, Some(&B(2)), Some(&C(2))),
+            (y, None, Some(&C(3))),
+            (z, Some(&B(3)), None)
+        ]
+    );
+}
+
+#[test]
+fn warn_on_empty_joins_logs_empty_driving_storage() {
+    let logs = CapturedLogs::default();
+    let writer = logs.clone();
+    let subscriber = tracing_subscriber::fmt()
+        .with_max_level(tracing::Level::DEBUG)
+        .with_writer(move || writer.clone())
+        .finish();
+
+    let mut universe = Universe::default();
+    let entity = universe.new_entity();
+    universe.insert_component(entity, B(1));
+
+    tracing::subscriber::with_default(subscriber, || {
+        // Disabled by default
+        assert_eq!(universe.join::<(&A, &B)>().count(), 0);
+        assert!(logs.contents().is_empty());
+
+        universe.warn_on_empty_joins(true);
+        assert_eq!(universe.join::<(&B, &C)>().count(), 0);
+        assert!(logs.contents().is_empty(), "driving storage B is not empty");
+
+        assert_eq!(universe.join::<(&A, &B)>().count(), 0);
+        let contents = logs.contents();
+        assert!(contents.contains("yields no results"));
+        assert!(contents.contains(std::any::type_name::<A>()));
+        assert!(contents.contains(std::any::type_name::<B>()));
+    });
+}
+
+#[test]
+fn universe_join_ten_components() {
+    use crate::unit_tests::dummy_components::{D, E, F, G, H, I, J, K, L};
+
+    let mut universe = Universe::default();
+    let entities: Vec<_> = (0..4).map(|_| universe.new_entity()).collect();
+    for (i, &entity) in entities.iter().enumerate() {
+        universe.insert_component(entity, A(i));
+        universe.insert_component(entity, B(i));
+        universe.insert_component(entity, C(i));
+        universe.insert_component(entity, D(i));
+        universe.insert_component(entity, E(i));
+        universe.insert_component(entity, F(i));
+        universe.insert_component(entity, G(i));
+        universe.insert_component(entity, H(i));
+        universe.insert_component(entity, I(i));
+        // The third entity lacks the last component, so it must be excluded from the join
+        if i != 2 {
+            universe.insert_component(entity, J(i));
+        }
+    }
+
+    let joined: Vec<_> = universe
+        .join::<(&A, &B, &C, &D, &E, &F, &G, &H, &I, &J)>()
+        .map(|tuple| {
+            let (entity, a, b, c, d, e, f, g, h, i, j): (Entity, &A, &B, &C, &D, &E, &F, &G, &H, &I, &J) = tuple;
+            let values = [a.0, b.0, c.0, d.0, e.0, f.0, g.0, h.0, i.0, j.0];
+            assert!(values.iter().all(|&value| value == a.0));
+            (entity, a.0)
+        })
+        .collect();
+    assert_eq!(joined, vec![(entities[0], 0), (entities[1], 1), (entities[3], 3)]);
+
+    let (a_storage, j_storage) = universe.get_component_storages::<(&A, &J)>();
+    let storages = universe.get_component_storages::<(&J, &B, &C, &D, &E, &F, &G, &H, &I, &A, &K, &L)>();
+    assert_eq!(storages.0.len(), j_storage.len());
+    assert_eq!(storages.9.len(), a_storage.len());
+    assert!(storages.11.is_empty());
+}
+
+#[test]
+fn universe_join_mut_twelve_components() {
+    use crate::unit_tests::dummy_components::{D, E, F, G, H, I, J, K, L};
+
+    let mut universe = Universe::default();
+    let entities: Vec<_> = (0..3).map(|_| universe.new_entity()).collect();
+    for (i, &entity) in entities.iter().enumerate() {
+        universe.insert_component(entity, A(i));
+        universe.insert_component(entity, B(i));
+        universe.insert_component(entity, C(i));
+        universe.insert_component(entity, D(i));
+        universe.insert_component(entity, E(i));
+        universe.insert_component(entity, F(i));
+        universe.insert_component(entity, G(i));
+        universe.insert_component(entity, H(i));
+        universe.insert_component(entity, I(i));
+        universe.insert_component(entity, J(i));
+        universe.insert_component(entity, K(i));
+        // The second entity lacks the last component, so it must be excluded from the join
+        if i != 1 {
+            universe.insert_component(entity, L(i));
+        }
+    }
+
+    let mut visited = Vec::new();
+    for (entity, a, _, _, _, _, _, _, _, _, _, k, l) in
+        universe.join_mut::<(&mut A, &B, &C, &D, &E, &F, &G, &H, &I, &J, &mut K, &L)>()
+    {
+        a.0 += 10;
+        k.0 += l.0;
+        visited.push(entity);
+    }
+    assert_eq!(visited, vec![entities[0], entities[2]]);
+
+    let a_values: Vec<_> = universe.join::<(&A,)>().map(|(_, a)| a.0).collect();
+    assert_eq!(a_values, vec![10, 1, 12]);
+    let k_values: Vec<_> = universe.join::<(&K,)>().map(|(_, k)| k.0).collect();
+    assert_eq!(k_values, vec![0, 1, 4]);
+}
+
+/// Joinable that counts the number of component lookups, and otherwise forwards to a `VecStorage`.
+struct CountingJoinable<'a, J> {
+    joinable: J,
+    lookups: &'a Cell<usize>,
+}
+
+impl<'a, J: Joinable<'a>> Joinable<'a> for CountingJoinable<'a, J> {
+    type ComponentRef = J::ComponentRef;
+
+    unsafe fn try_make_component_ref(&mut self, entity: Entity) -> Option<Self::ComponentRef> {
+        self.lookups.set(self.lookups.get() + 1);
+        self.joinable.try_make_component_ref(entity)
+    }
+}
+
+impl<'a, J: Joinable<'a>> IntoJoinable<'a> for CountingJoinable<'a, J> {
+    type Joinable = Self;
+
+    fn into_joinable(self) -> Self::Joinable {
+        self
+    }
+}
+
+#[test]
+fn join_filter_component_skips_entities() {
+    let universe = Universe::default();
+    let TestData {
+        v,
+        x,
+        y,
+        z,
+        mut a_storage,
+        b_storage,
+        c_storage,
+    } = TestData::new_for_universe(&universe);
+
+    let odd: Vec<_> = (&a_storage, &b_storage)
+        .join()
+        .filter_component(|a: &A| !a.0.is_multiple_of(2))
+        .collect();
+    assert_eq!(odd, vec![(v, &A(1), &B(1))]);
+
+    let even: Vec<_> = (&a_storage, &b_storage, &c_storage)
+        .join()
+        .filter_component(|a: &A| a.0.is_multiple_of(2))
+        .map(|(entity, ..)| entity)
+        .collect();
+    assert_eq!(even, vec![x]);
+
+    // The predicate is evaluated before the other components are looked up
+    let lookups = Cell::new(0);
+    let counting = CountingJoinable {
+        joinable: (&b_storage).into_joinable(),
+        lookups: &lookups,
+    };
+    let filtered: Vec<_> = (&a_storage, counting)
+        .join()
+        .filter_component(|a: &A| a.0 > 2)
+        .map(|(entity, ..)| entity)
+        .collect();
+    assert_eq!(filtered, vec![z]);
+    assert_eq!(lookups.get(), 2);
+
+    for (_, a, _) in (&mut a_storage, &c_storage)
+        .join()
+        .filter_component(|a: &A| a.0 < 3)
+    {
+        a.0 *= 10;
+    }
+    assert_eq!(a_storage.components(), &[A(10), A(20), A(3), A(4)]);
+    assert_eq!(a_storage.get_component(y), Some(&A(3)));
+}
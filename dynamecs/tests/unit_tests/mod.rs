@@ -1,9 +1,43 @@
 mod adapters;
 mod basic_api;
+mod commands;
+mod despawn;
+mod entity_set;
 mod join;
+#[cfg(feature = "rayon")]
+mod par_join;
+mod resources;
 mod serialization;
+mod sparse_storage;
+mod step_tracked_vec_storage;
+mod system_registry;
+mod systems;
+mod vec_storage;
 mod versioned_vec_storage;
 
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+
+/// Writer that captures formatted log output in a shared buffer.
+#[derive(Clone, Default)]
+pub struct CapturedLogs(Arc<Mutex<Vec<u8>>>);
+
+impl Write for CapturedLogs {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl CapturedLogs {
+    pub fn contents(&self) -> String {
+        String::from_utf8(self.0.lock().unwrap().clone()).unwrap()
+    }
+}
+
 pub mod dummy_components {
     use dynamecs::storages::VecStorage;
     use dynamecs::Component;
@@ -22,5 +56,5 @@ pub mod dummy_components {
     }
 }
 
-    generate_dummy_components!(A, B, C, D, E, F, G, H);
+    generate_dummy_components!(A, B, C, D, E, F, G, H, I, J, K, L);
 }
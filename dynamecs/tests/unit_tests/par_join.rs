@@ -0,0 +1,97 @@
+use crate::unit_tests::dummy_components::{A, B, C};
+use dynamecs::join::{Join, Optional};
+use dynamecs::par_join::ParJoin;
+use dynamecs::storages::SparseStorage;
+use dynamecs::Universe;
+use rayon::prelude::*;
+
+fn universe_with_components(n: usize) -> Universe {
+    let mut universe = Universe::default();
+    for i in 0..n {
+        let entity = universe.new_entity();
+        universe.insert_component(entity, A(i));
+        if i % 3 != 0 {
+            universe.insert_component(entity, B(2 * i));
+        }
+        if i % 5 == 0 {
+            universe.insert_component(entity, C(i + 1));
+        }
+    }
+    universe
+}
+
+#[test]
+fn par_join_sum_matches_sequential_join() {
+    let universe = universe_with_components(10_000);
+
+    let sequential: usize = universe.join::<(&A, &B)>().map(|(_, a, b)| a.0 * b.0).sum();
+    let parallel: usize = universe
+        .par_join::<(&A, &B)>()
+        .map(|(_, a, b)| a.0 * b.0)
+        .sum();
+    assert_eq!(parallel, sequential);
+    assert_eq!(
+        universe.par_join::<(&A, &B)>().count(),
+        universe.join::<(&A, &B)>().count()
+    );
+
+    let sequential: usize = universe
+        .join::<(&A, Optional<&C>)>()
+        .map(|(_, a, c)| a.0 + c.map_or(0, |c| c.0))
+        .sum();
+    let parallel: usize = universe
+        .par_join::<(&A, Optional<&C>)>()
+        .map(|(_, a, c)| a.0 + c.map_or(0, |c| c.0))
+        .sum();
+    assert_eq!(parallel, sequential);
+}
+
+#[test]
+fn par_join_mut_visits_each_entity_once() {
+    let mut universe = universe_with_components(10_000);
+    let mut expected: Vec<_> = universe
+        .join::<(&A, &B)>()
+        .map(|(entity, a, b)| (entity, a.0 + b.0))
+        .collect();
+
+    universe
+        .par_join_mut::<(&mut A, &B)>()
+        .for_each(|(_, a, b)| a.0 += b.0);
+    universe
+        .par_join_mut::<(&B, &mut C)>()
+        .for_each(|(_, _, c)| c.0 = 0);
+
+    let mut actual: Vec<_> = universe
+        .join::<(&A, &B)>()
+        .map(|(entity, a, _)| (entity, a.0))
+        .collect();
+    expected.sort();
+    actual.sort();
+    assert_eq!(actual, expected);
+    assert!(universe.join::<(&B, &C)>().all(|(_, _, c)| c.0 == 0));
+}
+
+#[test]
+fn par_join_with_sparse_storage() {
+    let universe = universe_with_components(100);
+    let mut sparse = SparseStorage::default();
+    for (entity, a) in universe.join::<&A>() {
+        if a.0 % 10 == 0 {
+            sparse.insert(entity, B(a.0));
+        }
+    }
+
+    let a_storage = universe.get_component_storage::<A>();
+    let mut parallel: Vec<_> = (a_storage, &sparse)
+        .par_join()
+        .map(|(entity, _, b)| (entity, b.0))
+        .collect();
+    let mut sequential: Vec<_> = (a_storage, &sparse)
+        .join()
+        .map(|(entity, _, b)| (entity, b.0))
+        .collect();
+    parallel.sort();
+    sequential.sort();
+    assert_eq!(parallel.len(), 10);
+    assert_eq!(parallel, sequential);
+}
@@ -0,0 +1,69 @@
+use crate::unit_tests::dummy_components::A;
+use dynamecs::storages::SingularStorage;
+use dynamecs::{register_component, register_resource, Resource, Universe};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct SolverSettings {
+    max_iterations: usize,
+}
+
+impl Resource for SolverSettings {}
+
+#[test]
+fn insert_and_get_resource() {
+    let mut universe = Universe::default();
+    assert_eq!(universe.get_resource::<SolverSettings>(), None);
+
+    let previous = universe.insert_resource(SolverSettings { max_iterations: 10 });
+    assert_eq!(previous, None);
+    assert_eq!(
+        universe.get_resource::<SolverSettings>(),
+        Some(&SolverSettings { max_iterations: 10 })
+    );
+
+    universe
+        .get_resource_mut::<SolverSettings>()
+        .unwrap()
+        .max_iterations = 20;
+    let previous = universe.insert_resource(SolverSettings { max_iterations: 30 });
+    assert_eq!(previous, Some(SolverSettings { max_iterations: 20 }));
+    assert_eq!(
+        universe
+            .get_resource::<SolverSettings>()
+            .unwrap()
+            .max_iterations,
+        30
+    );
+}
+
+#[test]
+fn resources_do_not_appear_in_joins() {
+    let mut universe = Universe::default();
+    let entity = universe.new_entity();
+    universe.insert_component(entity, A(1));
+    universe.insert_resource(SolverSettings { max_iterations: 10 });
+
+    let joined: Vec<_> = universe.join::<&A>().collect();
+    assert_eq!(joined, vec![(entity, &A(1))]);
+    assert!(universe.entities_with::<A>().contains(entity));
+    // The resource is not stored as a singular component
+    assert!(universe
+        .try_get_storage::<SingularStorage<SolverSettings>>()
+        .is_none());
+}
+
+#[test]
+fn registered_resources_are_serialized() {
+    register_component::<A>();
+    register_resource::<SolverSettings>();
+    let mut universe = Universe::default();
+    universe.insert_resource(SolverSettings { max_iterations: 10 });
+
+    let json = serde_json::to_string(&universe).unwrap();
+    let deserialized: Universe = serde_json::from_str(&json).unwrap();
+    assert_eq!(
+        deserialized.get_resource::<SolverSettings>(),
+        Some(&SolverSettings { max_iterations: 10 })
+    );
+}
@@ -1,5 +1,9 @@
-use dynamecs::storages::VecStorage;
-use dynamecs::{register_component, Component, Entity, Universe};
+use dynamecs::components::{register_default_components, Name, Paused};
+use dynamecs::serialization::registered_tags;
+use dynamecs::storages::{Transient, VecStorage};
+use dynamecs::{
+    register_component, register_transient_storage, Component, Entity, RegistrationStatus, Storage, Universe,
+};
 
 use serde::{Deserialize, Serialize};
 
@@ -109,3 +113,62 @@ fn bincode_test() {
     assert_ne!(new_entity, e2);
     assert_ne!(new_entity, e3);
 }
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Scratch(i32);
+
+impl Component for Scratch {
+    type Storage = Transient<VecStorage<Scratch>>;
+}
+
+#[test]
+fn transient_storage_is_omitted_from_serialization() {
+    let TestData { mut universe, e1, .. } = TestData::default();
+    register_transient_storage::<VecStorage<Scratch>>();
+    universe.insert_component(e1, Scratch(7));
+    assert_eq!(universe.get_component_storage::<Scratch>().len(), 1);
+
+    let json = serde_json::to_string_pretty(&universe).unwrap();
+    assert!(!json.contains("Transient"));
+    assert!(!json.contains("Scratch"));
+
+    let deserialized_universe: Universe = serde_json::from_str(&json).unwrap();
+    assert!(deserialized_universe
+        .try_get_component_storage::<Scratch>()
+        .is_none());
+    // The storage is reconstructed with its default on access
+    assert!(deserialized_universe
+        .get_component_storage::<Scratch>()
+        .is_empty());
+    assert_eq!(
+        deserialized_universe.get_component_storage::<Foo>(),
+        universe.get_component_storage::<Foo>()
+    );
+}
+
+#[test]
+fn registered_tags_contains_registered_components() {
+    register_component::<Foo>();
+    register_component::<Bar>();
+
+    let tags = registered_tags();
+    assert!(tags.contains(&VecStorage::<Foo>::tag()));
+    assert!(tags.contains(&VecStorage::<Bar>::tag()));
+}
+
+#[test]
+fn register_default_components_reports_already_registered() {
+    // Other tests may already have registered the default components, so we can only make
+    // assertions about the second call
+    let first = register_default_components();
+    let second = register_default_components();
+
+    let tags: Vec<_> = second.iter().map(|(tag, _)| tag.clone()).collect();
+    assert_eq!(tags, first.iter().map(|(tag, _)| tag.clone()).collect::<Vec<_>>());
+    assert_eq!(tags.len(), 7);
+    assert!(tags.contains(&<Name as Component>::Storage::tag()));
+    assert!(tags.contains(&<Paused as Component>::Storage::tag()));
+    assert!(second
+        .iter()
+        .all(|(_, status)| *status == RegistrationStatus::Replaced));
+}
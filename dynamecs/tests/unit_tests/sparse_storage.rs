@@ -0,0 +1,94 @@
+use crate::unit_tests::dummy_components::{A, B};
+use dynamecs::join::{Join, Optional};
+use dynamecs::storages::{SparseStorage, VecStorage};
+use dynamecs::{register_component, Component, GetComponentForEntity, Universe};
+use serde::{Deserialize, Serialize};
+use std::array;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct Marker(usize);
+
+impl Component for Marker {
+    type Storage = SparseStorage<Self>;
+}
+
+#[test]
+fn sparse_storage_insert_get_remove() {
+    let universe = Universe::default();
+    let [e1, e2] = array::from_fn(|_| universe.new_entity());
+    let mut storage = SparseStorage::default();
+    assert!(storage.is_empty());
+
+    assert_eq!(storage.insert(e1, A(1)), None);
+    assert_eq!(storage.insert(e1, A(2)), Some(A(1)));
+    assert_eq!(storage.len(), 1);
+    assert_eq!(storage.get_component_for_entity(e1), Some(&A(2)));
+    assert_eq!(storage.get_component(e2), None);
+
+    storage.get_component_mut(e1).unwrap().0 += 1;
+    assert_eq!(storage.remove(e1), Some(A(3)));
+    assert_eq!(storage.remove(e1), None);
+    assert!(storage.is_empty());
+}
+
+#[test]
+fn join_vec_storage_with_sparse_storage() {
+    let universe = Universe::default();
+    let [e1, e2, e3, e4] = array::from_fn(|_| universe.new_entity());
+
+    let mut a_storage = VecStorage::default();
+    let mut b_storage = SparseStorage::default();
+    for (i, entity) in [e1, e2, e3, e4].into_iter().enumerate() {
+        a_storage.insert(entity, A(i));
+    }
+    b_storage.insert(e2, B(20));
+    b_storage.insert(e4, B(40));
+
+    let joined: Vec<_> = (&a_storage, &b_storage).join().collect();
+    assert_eq!(joined, vec![(e2, &A(1), &B(20)), (e4, &A(3), &B(40))]);
+
+    for (_, a, b) in (&mut a_storage, &b_storage).join() {
+        a.0 += b.0;
+    }
+    assert_eq!(a_storage.components(), &[A(0), A(21), A(2), A(43)]);
+
+    for (_, a, b) in (&a_storage, &mut b_storage).join() {
+        b.0 += a.0;
+    }
+    assert_eq!(b_storage.get_component(e2), Some(&B(41)));
+    assert_eq!(b_storage.get_component(e4), Some(&B(83)));
+
+    let optional: Vec<_> = (&a_storage, Optional(&b_storage))
+        .join()
+        .map(|(entity, _, b)| (entity, b.is_some()))
+        .collect();
+    assert_eq!(optional, vec![(e1, false), (e2, true), (e3, false), (e4, true)]);
+}
+
+#[test]
+fn universe_join_and_serialization_with_sparse_component() {
+    register_component::<A>();
+    register_component::<Marker>();
+
+    let mut universe = Universe::default();
+    let [e1, e2, e3] = array::from_fn(|_| universe.new_entity());
+    for (i, entity) in [e1, e2, e3].into_iter().enumerate() {
+        universe.insert_component(entity, A(i));
+    }
+    universe.insert_component(e3, Marker(7));
+
+    let joined: Vec<_> = universe.join::<(&A, &Marker)>().collect();
+    assert_eq!(joined, vec![(e3, &A(2), &Marker(7))]);
+
+    for (_, a, marker) in universe.join_mut::<(&A, &mut Marker)>() {
+        marker.0 += a.0;
+    }
+    assert_eq!(universe.get_component_for_entity::<Marker>(e3), Some(&Marker(9)));
+
+    let json = serde_json::to_string(&universe).unwrap();
+    let deserialized: Universe = serde_json::from_str(&json).unwrap();
+    assert_eq!(
+        deserialized.get_component_storage::<Marker>(),
+        universe.get_component_storage::<Marker>()
+    );
+}
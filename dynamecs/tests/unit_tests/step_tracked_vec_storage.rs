@@ -0,0 +1,97 @@
+use crate::unit_tests::dummy_components::{A, B};
+use dynamecs::join::Join;
+use dynamecs::storages::{StepTrackedVecStorage, Transient, VecStorage};
+use dynamecs::{register_storage, register_transient_storage, Universe};
+use std::array;
+
+#[test]
+fn last_modified_step_is_recorded_on_insert_and_modification() {
+    let mut universe = Universe::default();
+    let [e1, e2, e3] = array::from_fn(|_| universe.new_entity());
+    let storage = universe.get_storage_mut::<StepTrackedVecStorage<A>>();
+
+    storage.insert(e1, A(1));
+    storage.insert(e2, A(2));
+    assert_eq!(storage.last_modified_step(e1), Some(0));
+    assert_eq!(storage.last_modified_step(e2), Some(0));
+    assert_eq!(storage.last_modified_step(e3), None);
+
+    storage.set_current_step(3);
+    storage.get_component_mut(e2).unwrap().0 += 1;
+    assert_eq!(storage.last_modified_step(e1), Some(0));
+    assert_eq!(storage.last_modified_step(e2), Some(3));
+    assert_eq!(storage.components(), &[A(1), A(3)]);
+
+    storage.set_current_step(5);
+    storage.insert(e1, A(10));
+    storage.insert(e3, A(30));
+    assert_eq!(storage.modified_steps(), &[5, 3, 5]);
+
+    // Removal swaps the last component into the removed slot, which must keep the steps in sync
+    assert_eq!(storage.remove(e1), Some(A(10)));
+    assert_eq!(storage.last_modified_step(e1), None);
+    assert_eq!(storage.last_modified_step(e2), Some(3));
+    assert_eq!(storage.last_modified_step(e3), Some(5));
+
+    storage.set_current_step(7);
+    storage.components_mut();
+    assert_eq!(storage.modified_steps(), &[7, 7]);
+}
+
+#[test]
+fn step_tracked_vec_storage_join() {
+    let universe = Universe::default();
+    let [e1, e2] = array::from_fn(|_| universe.new_entity());
+
+    let mut a_storage = StepTrackedVecStorage::default();
+    a_storage.insert(e1, A(1));
+    a_storage.insert(e2, A(2));
+    let mut b_storage = VecStorage::default();
+    b_storage.insert(e2, B(20));
+
+    let joined: Vec<_> = (&a_storage, &b_storage).join().collect();
+    assert_eq!(joined, vec![(e2, &A(2), &B(20))]);
+    let joined: Vec<_> = a_storage.join().collect();
+    assert_eq!(joined, vec![(e1, &A(1)), (e2, &A(2))]);
+}
+
+#[test]
+fn universe_sets_current_step_of_existing_and_new_storages() {
+    register_storage::<StepTrackedVecStorage<A>>();
+    register_transient_storage::<StepTrackedVecStorage<B>>();
+    let mut universe = Universe::default();
+    let entity = universe.new_entity();
+    universe.get_storage_mut::<StepTrackedVecStorage<A>>();
+
+    universe.set_current_step(4);
+    universe
+        .get_storage_mut::<StepTrackedVecStorage<A>>()
+        .insert(entity, A(1));
+    // Storages created after the step index was set are also informed
+    universe
+        .get_storage_mut::<Transient<StepTrackedVecStorage<B>>>()
+        .insert(entity, B(1));
+    assert_eq!(
+        universe
+            .get_storage::<StepTrackedVecStorage<A>>()
+            .last_modified_step(entity),
+        Some(4)
+    );
+    assert_eq!(
+        universe
+            .get_storage::<Transient<StepTrackedVecStorage<B>>>()
+            .last_modified_step(entity),
+        Some(4)
+    );
+
+    // Deserialized storages are informed as well
+    let json = serde_json::to_string(&universe).unwrap();
+    let mut restored: Universe = serde_json::from_str(&json).unwrap();
+    restored.set_current_step(7);
+    assert_eq!(
+        restored
+            .get_storage::<StepTrackedVecStorage<A>>()
+            .current_step(),
+        7
+    );
+}
@@ -0,0 +1,51 @@
+use dynamecs::adapters::FnSystem;
+use dynamecs::storages::SingularStorage;
+use dynamecs::{Component, SystemRegistry, Systems, Universe};
+
+#[derive(Debug, Default)]
+struct Log(Vec<String>);
+
+impl Component for Log {
+    type Storage = SingularStorage<Self>;
+}
+
+fn log_system(name: &'static str) -> FnSystem<impl FnMut(&mut Universe) -> eyre::Result<()>> {
+    FnSystem::new(name, move |universe| {
+        universe
+            .get_component_storage_mut::<Log>()
+            .get_component_mut()
+            .0
+            .push(name.to_string());
+        Ok(())
+    })
+}
+
+#[test]
+fn systems_can_be_composed_by_name_from_registry() {
+    let mut registry = SystemRegistry::new();
+    registry
+        .register("gravity", || log_system("gravity"))
+        .register("collision", || log_system("collision"));
+
+    let mut systems = Systems::default();
+    for name in ["collision", "gravity", "collision"] {
+        systems.add_by_name(&registry, name).unwrap();
+    }
+
+    let mut universe = Universe::default();
+    universe.insert_storage(SingularStorage::new(Log::default()));
+    systems.run_all(&mut universe).unwrap();
+
+    let log = &universe.get_component_storage::<Log>().get_component().0;
+    assert_eq!(log, &["collision", "gravity", "collision"]);
+}
+
+#[test]
+fn add_by_name_fails_for_unregistered_name() {
+    let mut registry = SystemRegistry::new();
+    registry.register("gravity", || log_system("gravity"));
+
+    let mut systems = Systems::default();
+    let err = systems.add_by_name(&registry, "collision").unwrap_err();
+    assert!(err.to_string().contains("collision"));
+}
@@ -0,0 +1,110 @@
+use dynamecs::adapters::FnSystem;
+use dynamecs::storages::VecStorage;
+use dynamecs::{register_component, Component, Storage, System, Systems, Universe};
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+#[derive(Debug, Default)]
+struct CountingSystem {
+    runs: usize,
+}
+
+impl System for CountingSystem {
+    fn run(&mut self, _data: &mut Universe) -> eyre::Result<()> {
+        self.runs += 1;
+        Ok(())
+    }
+}
+
+#[test]
+fn systems_get_as_downcasts_to_concrete_type() {
+    let mut systems = Systems::default();
+    systems
+        .add_system(FnSystem::new("noop", |_| Ok(())))
+        .add_system(CountingSystem::default());
+
+    let mut universe = Universe::default();
+    systems.run_all(&mut universe).unwrap();
+    systems.run_all(&mut universe).unwrap();
+
+    assert_eq!(systems.get_as::<CountingSystem>(1).unwrap().runs, 2);
+    assert!(systems.get_as::<CountingSystem>(0).is_none());
+    assert!(systems.get_as::<CountingSystem>(2).is_none());
+
+    systems.get_as_mut::<CountingSystem>(1).unwrap().runs = 0;
+    assert_eq!(systems.get_as::<CountingSystem>(1).unwrap().runs, 0);
+}
+
+#[test]
+fn systems_append_preserves_order() {
+    let log = Rc::new(RefCell::new(Vec::new()));
+    let log_system = |name: &'static str| {
+        let log = Rc::clone(&log);
+        FnSystem::new(name, move |_| {
+            log.borrow_mut().push(name);
+            Ok(())
+        })
+    };
+
+    let mut systems = Systems::default();
+    systems.add_system(log_system("a"));
+
+    let mut other = Systems::default();
+    other
+        .add_system(log_system("b"))
+        .add_system(log_system("c"));
+
+    systems.append(other);
+    systems.run_all(&mut Universe::default()).unwrap();
+
+    assert_eq!(*log.borrow(), ["a", "b", "c"]);
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct RegisteredComponent;
+
+impl Component for RegisteredComponent {
+    type Storage = VecStorage<Self>;
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct UnregisteredComponent;
+
+impl Component for UnregisteredComponent {
+    type Storage = VecStorage<Self>;
+}
+
+#[derive(Debug)]
+struct AccessingSystem;
+
+impl System for AccessingSystem {
+    fn register_components(&self) {
+        register_component::<RegisteredComponent>();
+    }
+
+    fn accessed_storage_tags(&self) -> Vec<String> {
+        vec![
+            VecStorage::<RegisteredComponent>::tag(),
+            VecStorage::<UnregisteredComponent>::tag(),
+        ]
+    }
+
+    fn run(&mut self, _data: &mut Universe) -> eyre::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn systems_check_registrations_flags_unregistered_accesses() {
+    let mut systems = Systems::default();
+    systems
+        .add_system(AccessingSystem.single_shot())
+        .add_system(CountingSystem::default());
+    systems.register_components();
+
+    assert_eq!(
+        systems.check_registrations(),
+        vec![VecStorage::<UnregisteredComponent>::tag()]
+    );
+}
@@ -0,0 +1,68 @@
+use crate::unit_tests::dummy_components::A;
+use dynamecs::storages::VecStorage;
+use dynamecs::Universe;
+use std::array;
+
+#[test]
+fn test_iter_and_iter_mut() {
+    let universe = Universe::default();
+    let [e1, e2, e3] = array::from_fn(|_| universe.new_entity());
+    let mut storage = VecStorage::default();
+    storage.insert(e1, A(1));
+    storage.insert(e2, A(2));
+    storage.insert(e3, A(3));
+
+    for component in storage.iter_mut() {
+        component.0 *= 10;
+    }
+    storage
+        .iter_mut()
+        .filter(|component| component.0 > 10)
+        .for_each(|component| component.0 += 1);
+
+    assert_eq!(storage.iter().collect::<Vec<_>>(), vec![&A(10), &A(21), &A(31)]);
+    assert_eq!(storage.iter().map(|component| component.0).sum::<usize>(), 62);
+    assert_eq!(storage.get_component(e2), Some(&A(21)));
+    assert_eq!(storage.entities(), &[e1, e2, e3]);
+}
+
+#[test]
+fn test_remove() {
+    let universe = Universe::default();
+    let [e1, e2, e3] = array::from_fn(|_| universe.new_entity());
+    let mut storage = VecStorage::default();
+    storage.insert(e1, A(1));
+    storage.insert(e2, A(2));
+    storage.insert(e3, A(3));
+
+    assert_eq!(storage.remove(e1), Some(A(1)));
+    assert_eq!(storage.remove(e1), None);
+    assert_eq!(storage.len(), 2);
+    // The last component takes the place of the removed component
+    assert_eq!(storage.entities(), &[e3, e2]);
+    assert_eq!(storage.get_component(e3), Some(&A(3)));
+    assert_eq!(storage.get_component(e2), Some(&A(2)));
+
+    assert_eq!(storage.remove(e2), Some(A(2)));
+    assert_eq!(storage.entities(), &[e3]);
+    storage.insert(e1, A(4));
+    assert_eq!(storage.get_component(e1), Some(&A(4)));
+    assert_eq!(storage.get_index(e1), Some(1));
+}
+
+#[test]
+fn test_replace() {
+    let mut universe = Universe::default();
+    let [e1, e2] = array::from_fn(|_| universe.new_entity());
+    let mut storage = VecStorage::default();
+
+    assert_eq!(storage.replace(e1, A(1)), None);
+    assert_eq!(storage.replace(e2, A(2)), None);
+    assert_eq!(storage.replace(e1, A(3)), Some(A(1)));
+    assert_eq!(storage.entities(), &[e1, e2]);
+    assert_eq!(storage.components(), &[A(3), A(2)]);
+
+    assert_eq!(universe.replace_component(e1, A(4)), None);
+    assert_eq!(universe.replace_component(e1, A(5)), Some(A(4)));
+    assert_eq!(universe.get_component_storage::<A>().get_component(e1), Some(&A(5)));
+}
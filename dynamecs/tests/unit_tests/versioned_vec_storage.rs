@@ -1,6 +1,8 @@
 use crate::unit_tests::dummy_components::{A, B, C};
-use dynamecs::storages::VersionedVecStorage;
-use dynamecs::{Component, Universe};
+use dynamecs::join::Join;
+use dynamecs::storages::versioned_vec_storage::ChangedSince;
+use dynamecs::storages::{VecStorage, VersionedVecStorage};
+use dynamecs::{register_storage, Component, Storage, Universe};
 use std::array;
 
 #[test]
@@ -62,3 +64,161 @@ fn test_versioned_vec_storage_join() {
     // TODO: In the above tests, we have only checked that some join statements type check
     // but we have not checked actual correctness. Should do this
 }
+
+#[test]
+fn test_clear_advances_storage_version() {
+    let mut universe = Universe::default();
+    let [e1, e2] = array::from_fn(|_| universe.new_entity());
+    let storage = universe.get_storage_mut::<VersionedVecStorage<A>>();
+
+    storage.insert(e1, A(1));
+    storage.insert(e2, A(2));
+    let v_storage = storage.storage_version();
+
+    storage.clear();
+    assert!(storage.components().is_empty());
+    assert!(storage.entities().is_empty());
+    assert!(storage.versions().is_empty());
+    assert_eq!(storage.get_component(e1), None);
+    assert!(storage.storage_version() > v_storage);
+
+    // The storage can be reused after clearing
+    storage.insert(e2, A(3));
+    assert_eq!(storage.components(), &[A(3)]);
+    assert_eq!(storage.entities(), &[e2]);
+    assert_eq!(storage.versions().len(), 1);
+}
+
+#[test]
+fn test_remove_advances_storage_version() {
+    let mut universe = Universe::default();
+    let [e1, e2] = array::from_fn(|_| universe.new_entity());
+    let storage = universe.get_storage_mut::<VersionedVecStorage<A>>();
+
+    storage.insert(e1, A(1));
+    storage.insert(e2, A(2));
+    storage.insert(e2, A(3));
+    let v_storage = storage.storage_version();
+    let v_e2 = storage.get_component_version(e2).unwrap();
+
+    assert_eq!(storage.remove(e1), Some(A(1)));
+    assert!(storage.storage_version() > v_storage);
+    assert_eq!(storage.entities(), &[e2]);
+    assert_eq!(storage.versions().len(), 1);
+    assert_eq!(storage.get_component_version(e2), Some(v_e2));
+
+    // Removing a missing component leaves the version unchanged
+    let v_storage = storage.storage_version();
+    assert_eq!(storage.remove(e1), None);
+    assert_eq!(storage.storage_version(), v_storage);
+}
+
+#[test]
+fn test_entity_version_component_iter() {
+    let mut universe = Universe::default();
+    let [e1, e2, e3] = array::from_fn(|_| universe.new_entity());
+    let storage = universe.get_storage_mut::<VersionedVecStorage<A>>();
+
+    storage.insert(e1, A(1));
+    storage.insert(e2, A(2));
+    storage.insert(e3, A(3));
+    // Give the entities distinct versions
+    storage.get_component_mut(e2);
+    storage.insert(e3, A(4));
+    storage.get_component_mut(e3);
+
+    let items: Vec<_> = storage.entity_version_component_iter().collect();
+    assert_eq!(items.len(), 3);
+    assert_eq!(
+        items
+            .iter()
+            .map(|&(entity, _, _)| entity)
+            .collect::<Vec<_>>(),
+        [e1, e2, e3]
+    );
+    assert_eq!(
+        items
+            .iter()
+            .map(|&(_, _, component)| component)
+            .collect::<Vec<_>>(),
+        [&A(1), &A(2), &A(4)]
+    );
+    for &(entity, version, _) in &items {
+        assert_eq!(Some(version), storage.get_component_version(entity));
+    }
+    assert!(items[0].1 < items[1].1 && items[1].1 < items[2].1);
+}
+
+#[test]
+fn test_changed_since_join() {
+    let universe = Universe::default();
+    let [e1, e2, e3] = array::from_fn(|_| universe.new_entity());
+    let mut storage = VersionedVecStorage::default();
+    storage.insert(e1, A(1));
+    storage.insert(e2, A(2));
+    storage.insert(e3, A(3));
+    let version = storage.get_component_version(e1).unwrap();
+    assert!(storage
+        .versions()
+        .iter()
+        .all(|&component_version| component_version == version));
+
+    let changed: Vec<_> = (&storage, ChangedSince(&storage, version)).join().collect();
+    assert!(changed.is_empty());
+
+    storage.get_component_mut(e1).unwrap().0 = 10;
+    storage.get_component_mut(e3).unwrap().0 = 30;
+
+    let changed: Vec<_> = (&storage, ChangedSince(&storage, version))
+        .join()
+        .map(|(entity, _, component)| (entity, component))
+        .collect();
+    assert_eq!(changed, vec![(e1, &A(10)), (e3, &A(30))]);
+
+    // The filter can also be combined with joins driven by other storages
+    let mut b_storage = VecStorage::default();
+    b_storage.insert(e2, B(2));
+    b_storage.insert(e3, B(3));
+    let changed: Vec<_> = (&b_storage, ChangedSince(&storage, version))
+        .join()
+        .collect();
+    assert_eq!(changed, vec![(e3, &B(3), &A(30))]);
+}
+
+#[test]
+fn test_storage_version_by_tag() {
+    let mut universe = Universe::default();
+    let tag = VersionedVecStorage::<A>::tag();
+    assert_eq!(universe.storage_version_by_tag(&tag), None);
+
+    let entity = universe.new_entity();
+    universe
+        .get_storage_mut::<VersionedVecStorage<A>>()
+        .insert(entity, A(1));
+    let version = universe.storage_version_by_tag(&tag).unwrap();
+
+    universe
+        .get_storage_mut::<VersionedVecStorage<A>>()
+        .get_component_mut(entity);
+    assert!(universe.storage_version_by_tag(&tag).unwrap() > version);
+
+    // Storages that do not track a version have no version
+    universe.get_storage_mut::<VecStorage<B>>();
+    assert_eq!(universe.storage_version_by_tag(&VecStorage::<B>::tag()), None);
+}
+
+#[test]
+fn test_storage_version_by_tag_for_deserialized_storage() {
+    register_storage::<VersionedVecStorage<C>>();
+    let mut universe = Universe::default();
+    let entity = universe.new_entity();
+    universe
+        .get_storage_mut::<VersionedVecStorage<C>>()
+        .insert(entity, C(1));
+    let tag = VersionedVecStorage::<C>::tag();
+    let version = universe.storage_version_by_tag(&tag).unwrap();
+
+    let json = serde_json::to_string(&universe).unwrap();
+    let restored: Universe = serde_json::from_str(&json).unwrap();
+    assert_eq!(restored.storage_version_by_tag(&tag), Some(version));
+}